@@ -95,7 +95,7 @@ impl State {
         let from = ManagedBuffer::new_from_bytes(&b""[..]);
         let to = ManagedBuffer::new_from_bytes(&b""[..]);
 
-        let result_value: OptionalValue<MultiValue5<u32, ManagedBuffer<DebugApi>, ManagedBuffer<DebugApi>, BigUint<DebugApi>, u8>> = self
+        let result_value: OptionalValue<MultiValue6<u32, ManagedBuffer<DebugApi>, ManagedBuffer<DebugApi>, u64, BigUint<DebugApi>, u8>> = self
             .interactor
             .vm_query(self.contract.latest_price_feed_optional(from, to))
             .await;
@@ -7,12 +7,13 @@ pub struct AggregatorResult<M: ManagedTypeApi> {
     pub round_id: u32,
     pub from_token_name: ManagedBuffer<M>,
     pub to_token_name: ManagedBuffer<M>,
+    pub timestamp: u64,
     pub price: BigUint<M>,
     pub decimals: u8,
 }
 
 pub type AggregatorResultAsMultiValue<M> =
-    MultiValue5<u32, ManagedBuffer<M>, ManagedBuffer<M>, BigUint<M>, u8>;
+    MultiValue6<u32, ManagedBuffer<M>, ManagedBuffer<M>, u64, BigUint<M>, u8>;
 
 const DEFAULT_PRICE: u64 = 1_000;
 const DEFAULT_PRICE_DECIMALS: u8 = 2;
@@ -28,10 +29,11 @@ pub trait PriceAggregatorMock {
         from: ManagedBuffer,
         to: ManagedBuffer,
     ) -> OptionalValue<AggregatorResultAsMultiValue<Self::Api>> {
-        OptionalValue::Some(MultiValue5::from((
-            1u32,
+        OptionalValue::Some(MultiValue6::from((
+            self.get_round_or_current(&from, &to),
             from.clone(),
             to.clone(),
+            self.get_timestamp_or_current(&from, &to),
             self.get_price_or_default(&from, &to),
             DEFAULT_PRICE_DECIMALS,
         )))
@@ -45,9 +47,30 @@ pub trait PriceAggregatorMock {
         }
     }
 
+    // Feeds that were never set are reported as fresh, with a new round for every timestamp
+    fn get_round_or_current(&self, from: &ManagedBuffer, to: &ManagedBuffer) -> u32 {
+        if self.latest_price_feed(from, to).is_empty() {
+            self.blockchain().get_block_timestamp() as u32
+        } else {
+            self.latest_price_feed_round(from, to).get()
+        }
+    }
+
+    fn get_timestamp_or_current(&self, from: &ManagedBuffer, to: &ManagedBuffer) -> u64 {
+        if self.latest_price_feed(from, to).is_empty() {
+            self.blockchain().get_block_timestamp()
+        } else {
+            self.latest_price_feed_timestamp(from, to).get()
+        }
+    }
+
     #[endpoint(setLatestPriceFeed)]
     fn set_latest_price_feed(&self, from: ManagedBuffer, to: ManagedBuffer, price: BigUint) {
-        self.latest_price_feed(&from, &to).set(&price)
+        let timestamp = self.blockchain().get_block_timestamp();
+        let round = self.get_round_or_current(&from, &to).max(timestamp as u32) + 1;
+        self.latest_price_feed(&from, &to).set(&price);
+        self.latest_price_feed_timestamp(&from, &to).set(timestamp);
+        self.latest_price_feed_round(&from, &to).set(round);
     }
 
     #[storage_mapper("latest_price_feed")]
@@ -56,4 +79,18 @@ pub trait PriceAggregatorMock {
         from: &ManagedBuffer,
         to: &ManagedBuffer,
     ) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("latest_price_feed_round")]
+    fn latest_price_feed_round(
+        &self,
+        from: &ManagedBuffer,
+        to: &ManagedBuffer,
+    ) -> SingleValueMapper<u32>;
+
+    #[storage_mapper("latest_price_feed_timestamp")]
+    fn latest_price_feed_timestamp(
+        &self,
+        from: &ManagedBuffer,
+        to: &ManagedBuffer,
+    ) -> SingleValueMapper<u64>;
 }
@@ -0,0 +1,72 @@
+#![no_std]
+
+multiversx_sc::imports!();
+
+use common_structs::{PausableAction, Role};
+
+const ALL_ACTIONS: [PausableAction; 5] = [
+    PausableAction::Supply,
+    PausableAction::Borrow,
+    PausableAction::Repay,
+    PausableAction::Withdraw,
+    PausableAction::Liquidate,
+];
+
+#[multiversx_sc::module]
+pub trait PauseModule: common_permissions::PermissionsModule {
+    // Pause guardians can pause but only the owner unpauses; no action given pauses them all
+    #[endpoint]
+    fn pause(&self, actions: MultiValueEncoded<PausableAction>) {
+        self.require_role(Role::PauseGuardian);
+
+        self.set_actions_paused(actions, true);
+    }
+
+    #[only_owner]
+    #[endpoint]
+    fn unpause(&self, actions: MultiValueEncoded<PausableAction>) {
+        self.set_actions_paused(actions, false);
+    }
+
+    #[view(isPaused)]
+    fn is_paused(&self, action: PausableAction) -> bool {
+        self.paused_actions().contains(&action)
+    }
+
+    fn require_not_paused(&self, action: PausableAction) {
+        require!(!self.is_paused(action), "action is paused");
+    }
+
+    fn set_actions_paused(&self, actions: MultiValueEncoded<PausableAction>, paused: bool) {
+        if actions.is_empty() {
+            for action in ALL_ACTIONS.iter() {
+                self.set_action_paused(*action, paused);
+            }
+        } else {
+            for action in actions {
+                self.set_action_paused(action, paused);
+            }
+        }
+    }
+
+    fn set_action_paused(&self, action: PausableAction, paused: bool) {
+        let caller = self.blockchain().get_caller();
+        if paused {
+            if self.paused_actions().insert(action) {
+                self.action_paused_event(action, &caller);
+            }
+        } else if self.paused_actions().swap_remove(&action) {
+            self.action_unpaused_event(action, &caller);
+        }
+    }
+
+    #[event("action_paused")]
+    fn action_paused_event(&self, #[indexed] action: PausableAction, caller: &ManagedAddress);
+
+    #[event("action_unpaused")]
+    fn action_unpaused_event(&self, #[indexed] action: PausableAction, caller: &ManagedAddress);
+
+    #[view(getPausedActions)]
+    #[storage_mapper("paused_actions")]
+    fn paused_actions(&self) -> UnorderedSetMapper<PausableAction>;
+}
@@ -0,0 +1,62 @@
+#![no_std]
+
+multiversx_sc::imports!();
+
+use common_structs::Role;
+
+#[multiversx_sc::module]
+pub trait PermissionsModule {
+    #[only_owner]
+    #[endpoint(grantRole)]
+    fn grant_role(&self, role: Role, address: ManagedAddress) {
+        require!(!address.is_zero(), "address is zero");
+
+        if self.role_members(role).insert(address.clone()) {
+            self.role_granted_event(role, &address);
+        }
+    }
+
+    #[only_owner]
+    #[endpoint(revokeRole)]
+    fn revoke_role(&self, role: Role, address: ManagedAddress) {
+        if self.role_members(role).swap_remove(&address) {
+            self.role_revoked_event(role, &address);
+        }
+    }
+
+    #[view(hasRole)]
+    fn has_role(&self, role: Role, address: ManagedAddress) -> bool {
+        address == self.blockchain().get_owner_address()
+            || self.role_members(role).contains(&address)
+    }
+
+    fn require_role(&self, role: Role) {
+        require!(
+            self.has_role(role, self.blockchain().get_caller()),
+            "caller does not have the required role"
+        );
+    }
+
+    // Once a timelock delay is set, risk parameters only change through queued proposals
+    fn require_not_timelocked(&self) {
+        require!(
+            self.timelock_delay().get() == 0,
+            "parameter changes have to go through the timelock"
+        );
+    }
+
+    #[event("role_granted")]
+    fn role_granted_event(&self, #[indexed] role: Role, address: &ManagedAddress);
+
+    #[event("role_revoked")]
+    fn role_revoked_event(&self, #[indexed] role: Role, address: &ManagedAddress);
+
+    #[view(getRoleMembers)]
+    #[storage_mapper("role_members")]
+    fn role_members(&self, role: Role) -> UnorderedSetMapper<ManagedAddress>;
+
+    // Seconds a queued risk parameter change waits before it can be executed; only contracts
+    // with a timelock set it
+    #[storage_mapper("timelock_delay")]
+    fn timelock_delay(&self) -> SingleValueMapper<u64>;
+}
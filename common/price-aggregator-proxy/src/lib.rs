@@ -2,10 +2,16 @@
 
 multiversx_sc::imports!();
 
+use common_structs::Role;
+
 pub const GWEI_STRING: &[u8] = b"GWEI";
+pub const DOLLAR_TICKER: &[u8] = b"USD";
+pub const PRICE_DECIMALS: u8 = 18;
+pub const DEFAULT_MAX_PRICE_STALENESS: u64 = 60 * 60;
+const TOKEN_ID_SUFFIX_LEN: usize = 7; // "dash" + 6 random bytes
 
 pub type AggregatorResultAsMultiValue<M> =
-    MultiValue5<u32, ManagedBuffer<M>, ManagedBuffer<M>, BigUint<M>, u8>;
+    MultiValue6<u32, ManagedBuffer<M>, ManagedBuffer<M>, u64, BigUint<M>, u8>;
 
 mod price_aggregator_proxy_mod {
     multiversx_sc::imports!();
@@ -25,18 +31,21 @@ pub struct AggregatorResult<M: ManagedTypeApi> {
     pub round_id: u32,
     pub from_token_name: ManagedBuffer<M>,
     pub to_token_name: ManagedBuffer<M>,
+    pub timestamp: u64,
     pub price: BigUint<M>,
     pub decimals: u8,
 }
 
 impl<M: ManagedTypeApi> From<AggregatorResultAsMultiValue<M>> for AggregatorResult<M> {
     fn from(multi_result: AggregatorResultAsMultiValue<M>) -> Self {
-        let (round_id, from_token_name, to_token_name, price, decimals) = multi_result.into_tuple();
+        let (round_id, from_token_name, to_token_name, timestamp, price, decimals) =
+            multi_result.into_tuple();
 
         AggregatorResult {
             round_id,
             from_token_name,
             to_token_name,
+            timestamp,
             price,
             decimals,
         }
@@ -44,16 +53,92 @@ impl<M: ManagedTypeApi> From<AggregatorResultAsMultiValue<M>> for AggregatorResu
 }
 
 #[multiversx_sc::module]
-pub trait PriceAggregatorModule {
-    #[only_owner]
+pub trait PriceAggregatorModule: common_permissions::PermissionsModule {
+    // Replaces all configured aggregators with a single one
     #[endpoint(setPriceAggregatorAddress)]
     fn set_price_aggregator_address(&self, address: ManagedAddress) {
-        require!(
-            self.blockchain().is_smart_contract(&address),
-            "Invalid price aggregator address"
-        );
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.update_price_aggregator_address(address);
+    }
+
+    fn update_price_aggregator_address(&self, address: ManagedAddress) {
+        self.require_valid_price_source(&address);
+
+        self.price_aggregator_addresses().clear();
+        self.price_aggregator_addresses().insert(address);
+    }
+
+    #[endpoint(addPriceAggregatorAddress)]
+    fn add_price_aggregator_address(&self, address: ManagedAddress) {
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.insert_price_aggregator_address(address);
+    }
+
+    fn insert_price_aggregator_address(&self, address: ManagedAddress) {
+        self.require_valid_price_source(&address);
+
+        self.price_aggregator_addresses().insert(address);
+    }
+
+    #[endpoint(removePriceAggregatorAddress)]
+    fn remove_price_aggregator_address(&self, address: ManagedAddress) {
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.price_aggregator_addresses().swap_remove(&address);
+    }
+
+    // Secondary source, e.g. a DEX spot/TWAP adapter exposing latestPriceFeedOptional
+    #[endpoint(setFallbackPriceSource)]
+    fn set_fallback_price_source(&self, address: ManagedAddress) {
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.update_fallback_price_source(&address);
+    }
+
+    fn update_fallback_price_source(&self, address: &ManagedAddress) {
+        self.require_valid_price_source(address);
+
+        self.fallback_price_source().set(address);
+    }
+
+    #[endpoint(setMaxMissingPriceFeeds)]
+    fn set_max_missing_price_feeds(&self, max_missing_feeds: usize) {
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
 
-        self.price_aggregator_address().set(&address);
+        self.max_missing_price_feeds().set(max_missing_feeds);
+    }
+
+    #[endpoint(setMaxPriceStaleness)]
+    fn set_max_price_staleness(&self, max_staleness: u64) {
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.update_max_price_staleness(max_staleness);
+    }
+
+    fn update_max_price_staleness(&self, max_staleness: u64) {
+        require!(max_staleness > 0, "max price staleness can not be zero");
+
+        self.max_price_staleness().set(max_staleness);
+    }
+
+    // Deployments from before several aggregators were supported stored a single one;
+    // called from init so an upgrade keeps it
+    fn migrate_price_aggregator_address(&self) {
+        let legacy_mapper = self.legacy_price_aggregator_address();
+        if legacy_mapper.is_empty() {
+            return;
+        }
+
+        self.price_aggregator_addresses()
+            .insert(legacy_mapper.take());
     }
 
     fn get_price_for_pair(
@@ -65,22 +150,187 @@ pub trait PriceAggregatorModule {
             .map(|aggregator_result| aggregator_result.price)
     }
 
+    // Median of all aggregators that answered; the fallback source is used when
+    // none answered or more than max_missing_price_feeds were missing
     fn get_full_result_for_pair(
         &self,
         from_ticker: ManagedBuffer,
         to_ticker: ManagedBuffer,
     ) -> Option<AggregatorResult<Self::Api>> {
-        let price_aggregator_address = self.price_aggregator_address().get();
-        if self.price_aggregator_address().is_empty() {
-            return None;
+        let mut prices: ManagedVec<BigUint> = ManagedVec::new();
+        let mut median_result: Option<AggregatorResult<Self::Api>> = None;
+        let mut missing_feeds = 0usize;
+        for address in self.price_aggregator_addresses().iter() {
+            match self.get_result_from_source(address, from_ticker.clone(), to_ticker.clone()) {
+                Some(result) => {
+                    prices.push(result.price.clone());
+                    match &mut median_result {
+                        // report the oldest timestamp among the answers
+                        Some(median) if median.timestamp > result.timestamp => {
+                            median.timestamp = result.timestamp
+                        }
+                        Some(_) => {}
+                        None => median_result = Some(result),
+                    }
+                }
+                None => missing_feeds += 1,
+            }
+        }
+
+        if prices.is_empty() || missing_feeds > self.max_missing_price_feeds().get() {
+            if self.fallback_price_source().is_empty() {
+                return None;
+            }
+
+            let fallback_address = self.fallback_price_source().get();
+            return self.get_result_from_source(fallback_address, from_ticker, to_ticker);
+        }
+
+        let mut result = median_result.unwrap();
+        result.price = self.compute_median(prices);
+
+        Some(result)
+    }
+
+    // Prices are always returned with PRICE_DECIMALS
+    fn get_result_from_source(
+        &self,
+        address: ManagedAddress,
+        from_ticker: ManagedBuffer,
+        to_ticker: ManagedBuffer,
+    ) -> Option<AggregatorResult<Self::Api>> {
+        let result = self.query_source(&address, from_ticker, to_ticker)?;
+
+        Some(self.normalize_decimals(result))
+    }
+
+    // Records the round each source answers for the pair, so later reads refuse older rounds;
+    // reads never write, endpoints call this before pricing anything
+    fn update_price_rounds(&self, from_ticker: ManagedBuffer, to_ticker: ManagedBuffer) {
+        let mut sources: ManagedVec<ManagedAddress> =
+            self.price_aggregator_addresses().iter().collect();
+        if !self.fallback_price_source().is_empty() {
+            sources.push(self.fallback_price_source().get());
         }
 
+        for address in sources.iter() {
+            let result = match self.query_source(&address, from_ticker.clone(), to_ticker.clone()) {
+                Some(result) => result,
+                None => continue,
+            };
+
+            self.last_price_round(&address, &result.from_token_name, &result.to_token_name)
+                .set(result.round_id);
+            self.last_price_round_timestamp(
+                &address,
+                &result.from_token_name,
+                &result.to_token_name,
+            )
+            .set(result.timestamp);
+        }
+    }
+
+    // Results that are stale or not newer than the last recorded round are treated as missing,
+    // except for re-reads of that same answer
+    fn query_source(
+        &self,
+        address: &ManagedAddress,
+        from_ticker: ManagedBuffer,
+        to_ticker: ManagedBuffer,
+    ) -> Option<AggregatorResult<Self::Api>> {
         let result: OptionalValue<AggregatorResultAsMultiValue<Self::Api>> = self
-            .aggregator_proxy(price_aggregator_address)
+            .aggregator_proxy(address.clone())
             .latest_price_feed_optional(from_ticker, to_ticker)
             .execute_on_dest_context();
 
-        result.into_option().map(AggregatorResult::from)
+        let result = AggregatorResult::from(result.into_option()?);
+        if !self.is_price_fresh(&result) {
+            return None;
+        }
+
+        let last_round_mapper =
+            self.last_price_round(address, &result.from_token_name, &result.to_token_name);
+        if !last_round_mapper.is_empty() {
+            let last_round = last_round_mapper.get();
+            let last_timestamp = self
+                .last_price_round_timestamp(address, &result.from_token_name, &result.to_token_name)
+                .get();
+            let is_same_answer =
+                result.round_id == last_round && result.timestamp == last_timestamp;
+            if result.round_id <= last_round && !is_same_answer {
+                return None;
+            }
+        }
+
+        Some(result)
+    }
+
+    fn get_token_ticker(&self, token_id: &TokenIdentifier) -> ManagedBuffer {
+        let as_buffer = token_id.clone().into_managed_buffer();
+        let ticker_start_index = 0;
+        let ticker_end_index = as_buffer.len() - TOKEN_ID_SUFFIX_LEN;
+
+        let result = as_buffer.copy_slice(ticker_start_index, ticker_end_index);
+
+        match result {
+            Some(r) => r,
+            None => sc_panic!("failed to get token ticker"),
+        }
+    }
+
+    fn compute_median(&self, mut prices: ManagedVec<BigUint>) -> BigUint {
+        // insertion sort, there are only a handful of sources
+        for i in 1..prices.len() {
+            let mut j = i;
+            while j > 0 && *prices.get(j - 1) > *prices.get(j) {
+                let previous = prices.get(j - 1).clone_value();
+                let current = prices.get(j).clone_value();
+                let _ = prices.set(j - 1, &current);
+                let _ = prices.set(j, &previous);
+                j -= 1;
+            }
+        }
+
+        let middle = prices.len() / 2;
+        if prices.len() % 2 == 0 {
+            (prices.get(middle - 1).clone_value() + &*prices.get(middle)) / 2u32
+        } else {
+            prices.get(middle).clone_value()
+        }
+    }
+
+    fn is_price_fresh(&self, result: &AggregatorResult<Self::Api>) -> bool {
+        result.timestamp + self.get_max_price_staleness() >= self.blockchain().get_block_timestamp()
+    }
+
+    #[view(getMaxPriceStaleness)]
+    fn get_max_price_staleness(&self) -> u64 {
+        if self.max_price_staleness().is_empty() {
+            DEFAULT_MAX_PRICE_STALENESS
+        } else {
+            self.max_price_staleness().get()
+        }
+    }
+
+    fn normalize_decimals(
+        &self,
+        mut result: AggregatorResult<Self::Api>,
+    ) -> AggregatorResult<Self::Api> {
+        if result.decimals < PRICE_DECIMALS {
+            result.price *= BigUint::from(10u64).pow((PRICE_DECIMALS - result.decimals) as u32);
+        } else if result.decimals > PRICE_DECIMALS {
+            result.price /= BigUint::from(10u64).pow((result.decimals - PRICE_DECIMALS) as u32);
+        }
+        result.decimals = PRICE_DECIMALS;
+
+        result
+    }
+
+    fn require_valid_price_source(&self, address: &ManagedAddress) {
+        require!(
+            self.blockchain().is_smart_contract(address),
+            "Invalid price aggregator address"
+        );
     }
 
     #[proxy]
@@ -89,7 +339,38 @@ pub trait PriceAggregatorModule {
         address: ManagedAddress,
     ) -> price_aggregator_proxy_mod::Proxy<Self::Api>;
 
-    #[view(getAggregatorAddress)]
+    #[view(getAggregatorAddresses)]
+    #[storage_mapper("priceAggregatorAddresses")]
+    fn price_aggregator_addresses(&self) -> UnorderedSetMapper<ManagedAddress>;
+
     #[storage_mapper("priceAggregatorAddress")]
-    fn price_aggregator_address(&self) -> SingleValueMapper<ManagedAddress>;
+    fn legacy_price_aggregator_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getFallbackPriceSource)]
+    #[storage_mapper("fallbackPriceSource")]
+    fn fallback_price_source(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getMaxMissingPriceFeeds)]
+    #[storage_mapper("maxMissingPriceFeeds")]
+    fn max_missing_price_feeds(&self) -> SingleValueMapper<usize>;
+
+    #[storage_mapper("maxPriceStaleness")]
+    fn max_price_staleness(&self) -> SingleValueMapper<u64>;
+
+    #[view(getLastPriceRound)]
+    #[storage_mapper("lastPriceRound")]
+    fn last_price_round(
+        &self,
+        source: &ManagedAddress,
+        from: &ManagedBuffer,
+        to: &ManagedBuffer,
+    ) -> SingleValueMapper<u32>;
+
+    #[storage_mapper("lastPriceRoundTimestamp")]
+    fn last_price_round_timestamp(
+        &self,
+        source: &ManagedAddress,
+        from: &ManagedBuffer,
+        to: &ManagedBuffer,
+    ) -> SingleValueMapper<u64>;
 }
@@ -3,11 +3,15 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
+mod ray;
+pub use ray::*;
+
 pub const BP: u64 = 1_000_000_000;
 pub const MAX_THRESHOLD: u64 = BP / 2;
 pub const MAX_THRESHOLD_ERROR_MSG: &[u8] =
     b"Cannot liquidate more than 50% of Liquidatee's position!";
 pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+pub const EGLD_DECIMALS: u32 = 18;
 
 #[derive(TopEncode, TopDecode, TypeAbi)]
 pub struct PoolParams<M: ManagedTypeApi> {
@@ -18,6 +22,66 @@ pub struct PoolParams<M: ManagedTypeApi> {
     pub reserve_factor: BigUint<M>,
 }
 
+// Jump-rate uses the slopes of the pool params, the PID controller targets their u_optimal;
+// rates and gains are in BP
+#[derive(NestedEncode, NestedDecode, TopEncode, TopDecode, TypeAbi, Clone, PartialEq, Debug)]
+pub enum InterestRateStrategy<M: ManagedTypeApi> {
+    JumpRate,
+    Fixed {
+        rate: BigUint<M>,
+    },
+    MultiKink {
+        base_rate: BigUint<M>,
+        kinks: ManagedVec<M, RateKink<M>>,
+    },
+    Pid {
+        base_rate: BigUint<M>,
+        k_p: BigUint<M>,
+        k_i: BigUint<M>,
+        k_d: BigUint<M>,
+        min_rate: BigUint<M>,
+        max_rate: BigUint<M>,
+    },
+}
+
+#[derive(
+    ManagedVecItem,
+    NestedEncode,
+    NestedDecode,
+    TopEncode,
+    TopDecode,
+    TypeAbi,
+    Clone,
+    PartialEq,
+    Debug,
+)]
+pub struct RateKink<M: ManagedTypeApi> {
+    pub utilisation: BigUint<M>,
+    pub rate: BigUint<M>,
+}
+
+impl<M: ManagedTypeApi> RateKink<M> {
+    pub fn new(utilisation: BigUint<M>, rate: BigUint<M>) -> Self {
+        RateKink { utilisation, rate }
+    }
+}
+
+// Utilisation errors are signed and in BP, the integral is in BP-years
+#[derive(TopEncode, TopDecode, TypeAbi, Clone, PartialEq, Debug)]
+pub struct PidControllerState<M: ManagedTypeApi> {
+    pub integral: BigInt<M>,
+    pub last_error: BigInt<M>,
+}
+
+impl<M: ManagedTypeApi> Default for PidControllerState<M> {
+    fn default() -> Self {
+        PidControllerState {
+            integral: BigInt::zero(),
+            last_error: BigInt::zero(),
+        }
+    }
+}
+
 #[derive(TopEncode, TopDecode, TypeAbi)]
 pub struct IssueData<M: ManagedTypeApi> {
     pub name: ManagedBuffer<M>,
@@ -38,19 +102,47 @@ pub struct DepositPosition<M: ManagedTypeApi> {
     pub amount: BigUint<M>,
     pub owner_nonce: u64,
     pub round: u64,
-    pub initial_supply_index: BigUint<M>,
+    // Deposited amount divided by the supply index at entry
+    pub scaled_amount: Ray<M>,
+}
+
+// Attributes of a safety module position NFT
+#[derive(NestedEncode, NestedDecode, TopEncode, TopDecode, TypeAbi, Clone, PartialEq, Debug)]
+pub struct SafetyModulePosition<M: ManagedTypeApi> {
+    pub deposited_amount: BigUint<M>,
+    pub shares: BigUint<M>,
+    pub round: u64,
 }
 
-#[derive(ManagedVecItem, NestedEncode, NestedDecode, TopEncode, TopDecode, TypeAbi, Clone, PartialEq, Debug)]
+#[derive(
+    ManagedVecItem,
+    NestedEncode,
+    NestedDecode,
+    TopEncode,
+    TopDecode,
+    TypeAbi,
+    Clone,
+    PartialEq,
+    Debug,
+)]
 pub struct BorrowPosition<M: ManagedTypeApi> {
     pub token_id: TokenIdentifier<M>,
     pub amount: BigUint<M>,
     pub owner_nonce: u64,
     pub round: u64,
-    pub initial_borrow_index: BigUint<M>,
+    // Borrowed amount divided by the borrow index at entry
+    pub scaled_amount: Ray<M>,
     pub nft: Option<EsdtTokenPayment<M>>,
 }
 
+// Borrow and supply indexes of an asset's pool, read once per call
+#[derive(ManagedVecItem, Clone)]
+pub struct PoolIndexes<M: ManagedTypeApi> {
+    pub token_id: TokenIdentifier<M>,
+    pub borrow_index: Ray<M>,
+    pub supply_index: Ray<M>,
+}
+
 impl<M: ManagedTypeApi> TokenAmountPair<M> {
     pub fn new(token_id: TokenIdentifier<M>, nonce: u64, amount: BigUint<M>) -> Self {
         TokenAmountPair {
@@ -67,14 +159,14 @@ impl<M: ManagedTypeApi> DepositPosition<M> {
         amount: BigUint<M>,
         owner_nonce: u64,
         round: u64,
-        initial_supply_index: BigUint<M>,
+        scaled_amount: Ray<M>,
     ) -> Self {
         DepositPosition {
             token_id,
             amount,
             owner_nonce,
             round,
-            initial_supply_index,
+            scaled_amount,
         }
     }
 }
@@ -85,7 +177,7 @@ impl<M: ManagedTypeApi> BorrowPosition<M> {
         amount: BigUint<M>,
         owner_nonce: u64,
         round: u64,
-        initial_borrow_index: BigUint<M>,
+        scaled_amount: Ray<M>,
         nft: Option<EsdtTokenPayment<M>>,
     ) -> Self {
         BorrowPosition {
@@ -93,7 +185,7 @@ impl<M: ManagedTypeApi> BorrowPosition<M> {
             amount,
             owner_nonce,
             round,
-            initial_borrow_index,
+            scaled_amount,
             nft,
         }
     }
@@ -125,3 +217,182 @@ impl<M: ManagedTypeApi> CollectionParams<M> {
         }
     }
 }
+
+#[derive(NestedEncode, NestedDecode, TopEncode, TopDecode, TypeAbi, Clone)]
+pub struct CollectionOracleParams<M: ManagedTypeApi> {
+    pub ticker: ManagedBuffer<M>,
+    pub max_staleness: u64,
+    pub max_deviation: BigUint<M>,
+}
+
+impl<M: ManagedTypeApi> CollectionOracleParams<M> {
+    pub fn new(ticker: ManagedBuffer<M>, max_staleness: u64, max_deviation: BigUint<M>) -> Self {
+        CollectionOracleParams {
+            ticker,
+            max_staleness,
+            max_deviation,
+        }
+    }
+}
+
+#[derive(NestedEncode, NestedDecode, TopEncode, TopDecode, TypeAbi, Clone)]
+pub struct NftAuction<M: ManagedTypeApi> {
+    pub token_id: TokenIdentifier<M>,
+    pub start_price: BigUint<M>,
+    pub end_price: BigUint<M>,
+    pub start_timestamp: u64,
+    pub end_timestamp: u64,
+}
+
+impl<M: ManagedTypeApi> NftAuction<M> {
+    pub fn new(
+        token_id: TokenIdentifier<M>,
+        start_price: BigUint<M>,
+        end_price: BigUint<M>,
+        start_timestamp: u64,
+        end_timestamp: u64,
+    ) -> Self {
+        NftAuction {
+            token_id,
+            start_price,
+            end_price,
+            start_timestamp,
+            end_timestamp,
+        }
+    }
+
+    // Dutch auction: the price decreases linearly from start_price to end_price
+    pub fn get_price(&self, timestamp: u64) -> BigUint<M> {
+        if timestamp >= self.end_timestamp || self.start_price <= self.end_price {
+            return self.end_price.clone();
+        }
+
+        let elapsed = timestamp - self.start_timestamp;
+        let duration = self.end_timestamp - self.start_timestamp;
+        let price_drop = (&self.start_price - &self.end_price) * elapsed / duration;
+
+        &self.start_price - &price_drop
+    }
+}
+
+// Risk parameters for correlated assets, in BP; a price source replaces the assets' own
+// feeds for the accounts in the category
+#[derive(NestedEncode, NestedDecode, TopEncode, TopDecode, TypeAbi, Clone)]
+pub struct EModeCategory<M: ManagedTypeApi> {
+    pub loan_to_value: BigUint<M>,
+    pub liquidation_threshold: BigUint<M>,
+    pub liquidation_bonus: BigUint<M>,
+    pub price_source: Option<ManagedBuffer<M>>,
+}
+
+impl<M: ManagedTypeApi> EModeCategory<M> {
+    pub fn new(
+        loan_to_value: BigUint<M>,
+        liquidation_threshold: BigUint<M>,
+        liquidation_bonus: BigUint<M>,
+        price_source: Option<ManagedBuffer<M>>,
+    ) -> Self {
+        EModeCategory {
+            loan_to_value,
+            liquidation_threshold,
+            liquidation_bonus,
+            price_source,
+        }
+    }
+}
+
+// User actions the pause guardian can halt, protocol-wide or for a single asset
+#[derive(
+    NestedEncode, NestedDecode, TopEncode, TopDecode, TypeAbi, Clone, Copy, PartialEq, Debug,
+)]
+pub enum PausableAction {
+    Supply,
+    Borrow,
+    Repay,
+    Withdraw,
+    Liquidate,
+}
+
+// Admin duties the owner hands out per address; the owner itself holds every role
+#[derive(
+    NestedEncode, NestedDecode, TopEncode, TopDecode, TypeAbi, Clone, Copy, PartialEq, Debug,
+)]
+pub enum Role {
+    RiskAdmin,
+    ListingAdmin,
+    PauseGuardian,
+    Treasury,
+}
+
+// Risk parameter changes the lending pool holds back in its timelock until their ETA
+#[derive(NestedEncode, NestedDecode, TopEncode, TopDecode, TypeAbi, Clone, PartialEq, Debug)]
+pub enum TimelockedChange<M: ManagedTypeApi> {
+    AssetLoanToValue {
+        asset: TokenIdentifier<M>,
+        loan_to_value: BigUint<M>,
+    },
+    AssetLiquidationThreshold {
+        asset: TokenIdentifier<M>,
+        threshold: BigUint<M>,
+    },
+    AssetLiquidationBonus {
+        asset: TokenIdentifier<M>,
+        liquidation_bonus: BigUint<M>,
+    },
+    Aggregator {
+        asset: TokenIdentifier<M>,
+        aggregator: ManagedAddress<M>,
+    },
+    PoolUpgrade {
+        asset: TokenIdentifier<M>,
+        r_base: BigUint<M>,
+        r_slope1: BigUint<M>,
+        r_slope2: BigUint<M>,
+        u_optimal: BigUint<M>,
+        reserve_factor: BigUint<M>,
+        liquidation_threshold: BigUint<M>,
+        rate_strategy: Option<InterestRateStrategy<M>>,
+    },
+    TimelockDelay {
+        delay: u64,
+    },
+    PriceAggregatorAddress {
+        address: ManagedAddress<M>,
+    },
+    AddPriceAggregatorAddress {
+        address: ManagedAddress<M>,
+    },
+    RemovePriceAggregatorAddress {
+        address: ManagedAddress<M>,
+    },
+    FallbackPriceSource {
+        address: ManagedAddress<M>,
+    },
+    MaxPriceStaleness {
+        max_staleness: u64,
+    },
+    MaxMissingPriceFeeds {
+        max_missing_feeds: usize,
+    },
+    EModeCategory {
+        category_id: u8,
+        loan_to_value: BigUint<M>,
+        liquidation_threshold: BigUint<M>,
+        liquidation_bonus: BigUint<M>,
+        price_source: Option<ManagedBuffer<M>>,
+    },
+    AssetEModeCategory {
+        asset: TokenIdentifier<M>,
+        category_id: u8,
+    },
+    AssetIsolated {
+        asset: TokenIdentifier<M>,
+        isolated: bool,
+    },
+}
+
+#[derive(NestedEncode, NestedDecode, TopEncode, TopDecode, TypeAbi, Clone, PartialEq, Debug)]
+pub struct QueuedChange<M: ManagedTypeApi> {
+    pub change: TimelockedChange<M>,
+    pub eta: u64,
+}
@@ -0,0 +1,93 @@
+multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+pub const RAY_DECIMALS: u32 = 27;
+// RAY / BP
+const BP_TO_RAY: u64 = 1_000_000_000_000_000_000;
+
+// Fixed-point number with 27 decimals, used for the pool indexes and scaled balances;
+// every conversion and operation rounds half up, unless it says otherwise
+#[derive(
+    ManagedVecItem,
+    NestedEncode,
+    NestedDecode,
+    TopEncode,
+    TopDecode,
+    TypeAbi,
+    Clone,
+    PartialEq,
+    Debug,
+)]
+pub struct Ray<M: ManagedTypeApi> {
+    pub raw: BigUint<M>,
+}
+
+impl<M: ManagedTypeApi> Ray<M> {
+    pub fn from_raw(raw: BigUint<M>) -> Self {
+        Ray { raw }
+    }
+
+    pub fn zero() -> Self {
+        Ray::from_raw(BigUint::zero())
+    }
+
+    pub fn one() -> Self {
+        Ray::from_raw(Self::unit())
+    }
+
+    pub fn from_amount(amount: &BigUint<M>) -> Self {
+        Ray::from_raw(amount * &Self::unit())
+    }
+
+    pub fn from_bp(value: &BigUint<M>) -> Self {
+        Ray::from_raw(value * BP_TO_RAY)
+    }
+
+    pub fn to_amount(&self) -> BigUint<M> {
+        let unit = Self::unit();
+        let half_unit = &unit / 2u64;
+
+        (&self.raw + &half_unit) / unit
+    }
+
+    pub fn to_amount_rounded_down(&self) -> BigUint<M> {
+        &self.raw / &Self::unit()
+    }
+
+    pub fn to_bp(&self) -> BigUint<M> {
+        (&self.raw + BP_TO_RAY / 2) / BP_TO_RAY
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw == 0
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let unit = Self::unit();
+        let half_unit = &unit / 2u64;
+
+        Ray::from_raw((&self.raw * &other.raw + half_unit) / unit)
+    }
+
+    pub fn div(&self, other: &Self) -> Self {
+        let half_other = &other.raw / 2u64;
+
+        Ray::from_raw((&self.raw * &Self::unit() + half_other) / &other.raw)
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        Ray::from_raw(&self.raw + &other.raw)
+    }
+
+    pub fn saturating_sub(&self, other: &Self) -> Self {
+        if self.raw > other.raw {
+            Ray::from_raw(&self.raw - &other.raw)
+        } else {
+            Ray::zero()
+        }
+    }
+
+    fn unit() -> BigUint<M> {
+        BigUint::from(10u64).pow(RAY_DECIMALS)
+    }
+}
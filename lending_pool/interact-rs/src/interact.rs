@@ -41,41 +41,87 @@ async fn main() {
         "deploy" => state.deploy().await,
         "registerAccountToken" => state.register_account_token().await,
         "registerDebtNFTToken" => state.register_debt_token().await,
+        "registerLendToken" => state.register_lend_token().await,
         "enterMarket" => state.enter_market().await,
         "exitMarket" => state.exit_market().await,
         "addCollateral" => state.add_collateral().await,
+        "supply" => state.supply().await,
+        "setCollateralEnabled" => state.set_collateral_enabled().await,
+        "setAccountEModeCategory" => state.set_account_e_mode_category().await,
+        "mintLendTokens" => state.mint_lend_tokens().await,
+        "redeemLendTokens" => state.redeem_lend_tokens().await,
         "removeCollateral" => state.remove_collateral().await,
         "borrow" => state.borrow().await,
         "borrowWithNFTs" => state.borrow_with_nfts().await,
         "repay" => state.repay().await,
         "repayNFT" => state.repay_nft_debt().await,
         "liquidate" => state.liquidate().await,
+        "writeOffBadDebt" => state.write_off_bad_debt().await,
         "updateCollateralWithInterest" => state.update_collateral_with_interest().await,
         "updateBorrowsWithDebt" => state.update_borrows_with_debt().await,
         "getLiqPoolTemplateAddress" => state.liq_pool_template_address().await,
         "createLiquidityPool" => state.create_liquidity_pool().await,
+        "setAssetDecimals" => state.set_asset_decimals().await,
         "upgradeLiquidityPool" => state.upgrade_liquidity_pool().await,
         "setAggregator" => state.set_aggregator().await,
+        "setSafetyModule" => state.set_safety_module().await,
+        "setBadDebtDustThreshold" => state.set_bad_debt_dust_threshold().await,
+        "setInterestApproximationTerms" => state.set_interest_approximation_terms().await,
+        "setTreasuryAddress" => state.set_treasury_address().await,
+        "sweepRevenueToTreasury" => state.sweep_revenue_to_treasury().await,
+        "sweepRevenueToSafetyModule" => state.sweep_revenue_to_safety_module().await,
         "setAssetLoanToValue" => state.set_asset_loan_to_value().await,
+        "setAssetSupplyCap" => state.set_asset_supply_cap().await,
+        "setAssetBorrowCap" => state.set_asset_borrow_cap().await,
         "setAssetLiquidationBonus" => state.set_asset_liquidation_bonus().await,
+        "setAssetIsolated" => state.set_asset_isolated().await,
+        "setIsolationBorrowable" => state.set_isolation_borrowable().await,
+        "setIsolationDebtCeiling" => state.set_isolation_debt_ceiling().await,
+        "setEModeCategory" => state.set_e_mode_category().await,
+        "setAssetEModeCategory" => state.set_asset_e_mode_category().await,
+        "pauseAsset" => state.pause_asset().await,
+        "unpauseAsset" => state.unpause_asset().await,
+        "setTimelockDelay" => state.set_timelock_delay().await,
+        "queueChange" => state.queue_change().await,
+        "executeChange" => state.execute_change().await,
+        "cancelChange" => state.cancel_change().await,
+        "getTimelockDelay" => state.get_timelock_delay().await,
+        "getQueuedChanges" => state.queued_changes().await,
         "addCollection" => state.add_collection().await,
         "getPoolAddress" => state.get_pool_address().await,
         "getPoolAllowed" => state.pools_allowed().await,
+        "getTreasuryAddress" => state.treasury_address().await,
         "getAssetLoanToValue" => state.asset_loan_to_value().await,
+        "getAssetSupplyCap" => state.asset_supply_cap().await,
+        "getAssetBorrowCap" => state.asset_borrow_cap().await,
         "getAssetLiquidationBonus" => state.asset_liquidation_bonus().await,
         "getAccountToken" => state.account_token().await,
         "getDebtNFT" => state.debt_nft_token().await,
         "getAccountPositions" => state.account_positions().await,
         "getDepositPositions" => state.deposit_positions().await,
         "getBorrowPositions" => state.borrow_positions().await,
+        "getNonCollateralDeposits" => state.non_collateral_deposits().await,
         "getNFTBorrowPositions" => state.nft_borrow_positions().await,
+        "getLendToken" => state.lend_token().await,
+        "getLendTokenAsset" => state.lend_token_asset().await,
         "getCollections" => state.collections().await,
         "getCollectionParam" => state.collection_params().await,
         "getCollateralAmountForToken" => state.get_collateral_amount_for_token().await,
+        "isCollateralEnabled" => state.is_collateral_enabled().await,
         "getTotalCollateralAvailable" => state.get_total_collateral_in_dollars().await,
         "getTotalBorrowInDollars" => state.get_total_borrow_in_dollars().await,
+        "getIsolationDebtCeiling" => state.isolation_debt_ceiling().await,
+        "getIsolatedDebt" => state.isolated_debt().await,
+        "getAccountIsolatedDebt" => state.account_isolated_debt().await,
+        "getAccountIsolatedDebtInDollars" => state.account_isolated_debt_in_dollars().await,
+        "getBadDebtDustThreshold" => state.bad_debt_dust_threshold().await,
+        "getEModeCategory" => state.e_mode_categories().await,
+        "getAssetEModeCategory" => state.asset_e_mode_category().await,
+        "getAccountEModeCategory" => state.account_e_mode_category().await,
+        "isAccountIsolated" => state.is_account_isolated().await,
+        "getIsolationRemainingDebt" => state.get_isolation_remaining_debt().await,
         "setPriceAggregatorAddress" => state.set_price_aggregator_address().await,
-        "getAggregatorAddress" => state.price_aggregator_address().await,
+        "getAggregatorAddresses" => state.price_aggregator_addresses().await,
         _ => panic!("unknown command: {}", &cmd),
     }
 }
@@ -176,6 +222,32 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn register_lend_token(&mut self) {
+        let egld_amount = BigUint::<DebugApi>::from(0u128);
+
+        let pool_asset_id = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let token_name = ManagedBuffer::new_from_bytes(&b""[..]);
+        let ticker = ManagedBuffer::new_from_bytes(&b""[..]);
+        let num_decimals = 0usize;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .register_lend_token(pool_asset_id, token_name, ticker, num_decimals)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+            .egld_value(egld_amount)
+
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn enter_market(&mut self) {
         let result: multiversx_sc_snippets::InteractorResult<EsdtTokenPayment<DebugApi>> = self
             .interactor
@@ -233,6 +305,126 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn supply(&mut self) {
+        let token_id = b"";
+        let token_nonce = 0u64;
+        let token_amount = BigUint::<DebugApi>::from(0u128);
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .supply()
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+            .esdt_transfer(token_id.to_vec(), token_nonce, token_amount)
+
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_collateral_enabled(&mut self) {
+        let token_id = b"";
+        let token_nonce = 0u64;
+        let token_amount = BigUint::<DebugApi>::from(0u128);
+
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let enabled = false;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_collateral_enabled(asset, enabled)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+            .esdt_transfer(token_id.to_vec(), token_nonce, token_amount)
+
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_account_e_mode_category(&mut self) {
+        let token_id = b"";
+        let token_nonce = 0u64;
+        let token_amount = BigUint::<DebugApi>::from(0u128);
+
+        let category_id = 0u8;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_account_e_mode_category(category_id)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+            .esdt_transfer(token_id.to_vec(), token_nonce, token_amount)
+
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn mint_lend_tokens(&mut self) {
+        let token_id = b"";
+        let token_nonce = 0u64;
+        let token_amount = BigUint::<DebugApi>::from(0u128);
+
+        let result: multiversx_sc_snippets::InteractorResult<EsdtTokenPayment<DebugApi>> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .mint_lend_tokens()
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+            .esdt_transfer(token_id.to_vec(), token_nonce, token_amount)
+
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn redeem_lend_tokens(&mut self) {
+        let token_id = b"";
+        let token_nonce = 0u64;
+        let token_amount = BigUint::<DebugApi>::from(0u128);
+
+        let result: multiversx_sc_snippets::InteractorResult<BigUint<DebugApi>> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .redeem_lend_tokens()
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+            .esdt_transfer(token_id.to_vec(), token_nonce, token_amount)
+
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn remove_collateral(&mut self) {
         let token_id = b"";
         let token_nonce = 0u64;
@@ -363,14 +555,13 @@ impl State {
         let token_amount = BigUint::<DebugApi>::from(0u128);
 
         let liquidatee_account_nonce = 0u64;
-        let liquidation_threshold = BigUint::<DebugApi>::from(0u128);
-        let token_to_liquidate = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let collateral_token_id = TokenIdentifier::from_esdt_bytes(&b""[..]);
 
         let result: multiversx_sc_snippets::InteractorResult<()> = self
             .interactor
             .sc_call_get_result(
                 self.contract
-                    .liquidate(liquidatee_account_nonce, liquidation_threshold, token_to_liquidate)
+                    .liquidate(liquidatee_account_nonce, collateral_token_id)
                     .into_blockchain_call()
                     .from(&self.wallet_address)
             .esdt_transfer(token_id.to_vec(), token_nonce, token_amount)
@@ -384,6 +575,25 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn write_off_bad_debt(&mut self) {
+        let account_nonce = 0u64;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .write_off_bad_debt(account_nonce)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn update_collateral_with_interest(&mut self) {
         let account_position = 0u64;
 
@@ -433,18 +643,40 @@ impl State {
 
     async fn create_liquidity_pool(&mut self) {
         let base_asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let decimals = 0u8;
         let r_base = BigUint::<DebugApi>::from(0u128);
         let r_slope1 = BigUint::<DebugApi>::from(0u128);
         let r_slope2 = BigUint::<DebugApi>::from(0u128);
         let u_optimal = BigUint::<DebugApi>::from(0u128);
         let reserve_factor = BigUint::<DebugApi>::from(0u128);
         let liquidation_threshold = BigUint::<DebugApi>::from(0u128);
+        let rate_strategy = OptionalValue::Some(InterestRateStrategy::<DebugApi>::JumpRate);
 
         let result: multiversx_sc_snippets::InteractorResult<ManagedAddress<DebugApi>> = self
             .interactor
             .sc_call_get_result(
                 self.contract
-                    .create_liquidity_pool(base_asset, r_base, r_slope1, r_slope2, u_optimal, reserve_factor, liquidation_threshold)
+                    .create_liquidity_pool(base_asset, decimals, r_base, r_slope1, r_slope2, u_optimal, reserve_factor, liquidation_threshold, rate_strategy)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_asset_decimals(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let decimals = 0u8;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_asset_decimals(asset, decimals)
                     .into_blockchain_call()
                     .from(&self.wallet_address)
                     .gas_limit(DEFAULT_GAS_LIMIT)
@@ -464,12 +696,13 @@ impl State {
         let u_optimal = BigUint::<DebugApi>::from(0u128);
         let reserve_factor = BigUint::<DebugApi>::from(0u128);
         let liquidation_threshold = BigUint::<DebugApi>::from(0u128);
+        let rate_strategy = OptionalValue::Some(InterestRateStrategy::<DebugApi>::JumpRate);
 
         let result: multiversx_sc_snippets::InteractorResult<()> = self
             .interactor
             .sc_call_get_result(
                 self.contract
-                    .upgrade_liquidity_pool(base_asset, r_base, r_slope1, r_slope2, u_optimal, reserve_factor, liquidation_threshold)
+                    .upgrade_liquidity_pool(base_asset, r_base, r_slope1, r_slope2, u_optimal, reserve_factor, liquidation_threshold, rate_strategy)
                     .into_blockchain_call()
                     .from(&self.wallet_address)
                     .gas_limit(DEFAULT_GAS_LIMIT)
@@ -501,15 +734,15 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
-    async fn set_asset_loan_to_value(&mut self) {
-        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
-        let loan_to_value = BigUint::<DebugApi>::from(0u128);
+    async fn set_safety_module(&mut self) {
+        let pool_asset_id = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let safety_module = bech32::decode("");
 
         let result: multiversx_sc_snippets::InteractorResult<()> = self
             .interactor
             .sc_call_get_result(
                 self.contract
-                    .set_asset_loan_to_value(asset, loan_to_value)
+                    .set_safety_module(pool_asset_id, safety_module)
                     .into_blockchain_call()
                     .from(&self.wallet_address)
                     .gas_limit(DEFAULT_GAS_LIMIT)
@@ -521,15 +754,15 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
-    async fn set_asset_liquidation_bonus(&mut self) {
-        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
-        let liq_bonus = BigUint::<DebugApi>::from(0u128);
+    async fn set_interest_approximation_terms(&mut self) {
+        let pool_asset_id = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let terms = 0u32;
 
         let result: multiversx_sc_snippets::InteractorResult<()> = self
             .interactor
             .sc_call_get_result(
                 self.contract
-                    .set_asset_liquidation_bonus(asset, liq_bonus)
+                    .set_interest_approximation_terms(pool_asset_id, terms)
                     .into_blockchain_call()
                     .from(&self.wallet_address)
                     .gas_limit(DEFAULT_GAS_LIMIT)
@@ -541,14 +774,14 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
-    async fn add_collection(&mut self) {
-        let params = PlaceholderInput;
+    async fn set_treasury_address(&mut self) {
+        let treasury_address = bech32::decode("");
 
         let result: multiversx_sc_snippets::InteractorResult<()> = self
             .interactor
             .sc_call_get_result(
                 self.contract
-                    .add_collection(params)
+                    .set_treasury_address(treasury_address)
                     .into_blockchain_call()
                     .from(&self.wallet_address)
                     .gas_limit(DEFAULT_GAS_LIMIT)
@@ -560,71 +793,494 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
-    async fn get_pool_address(&mut self) {
-        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+    async fn sweep_revenue_to_treasury(&mut self) {
+        let pool_asset_id = TokenIdentifier::from_esdt_bytes(&b""[..]);
 
-        let result_value: ManagedAddress<DebugApi> = self
+        let result: multiversx_sc_snippets::InteractorResult<BigUint<DebugApi>> = self
             .interactor
-            .vm_query(self.contract.get_pool_address(asset))
+            .sc_call_get_result(
+                self.contract
+                    .sweep_revenue_to_treasury(pool_asset_id)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
             .await;
+        let result_value = result.value();
 
         println!("Result: {:?}", result_value);
     }
 
-    async fn pools_allowed(&mut self) {
-        let result_value: MultiValueVec<ManagedAddress<DebugApi>> = self
+    async fn sweep_revenue_to_safety_module(&mut self) {
+        let pool_asset_id = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result: multiversx_sc_snippets::InteractorResult<BigUint<DebugApi>> = self
             .interactor
-            .vm_query(self.contract.pools_allowed())
+            .sc_call_get_result(
+                self.contract
+                    .sweep_revenue_to_safety_module(pool_asset_id)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
             .await;
+        let result_value = result.value();
 
         println!("Result: {:?}", result_value);
     }
 
-    async fn asset_loan_to_value(&mut self) {
+    async fn set_asset_loan_to_value(&mut self) {
         let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let loan_to_value = BigUint::<DebugApi>::from(0u128);
 
-        let result_value: BigUint<DebugApi> = self
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
             .interactor
-            .vm_query(self.contract.asset_loan_to_value(asset))
+            .sc_call_get_result(
+                self.contract
+                    .set_asset_loan_to_value(asset, loan_to_value)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
             .await;
+        let result_value = result.value();
 
         println!("Result: {:?}", result_value);
     }
 
-    async fn asset_liquidation_bonus(&mut self) {
+    async fn set_asset_supply_cap(&mut self) {
         let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let cap = OptionalValue::Some(BigUint::<DebugApi>::from(0u128));
 
-        let result_value: BigUint<DebugApi> = self
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
             .interactor
-            .vm_query(self.contract.asset_liquidation_bonus(asset))
+            .sc_call_get_result(
+                self.contract
+                    .set_asset_supply_cap(asset, cap)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
             .await;
+        let result_value = result.value();
 
         println!("Result: {:?}", result_value);
     }
 
-    async fn account_token(&mut self) {
-        let result_value: TokenIdentifier<DebugApi> = self
-            .interactor
-            .vm_query(self.contract.account_token())
-            .await;
-
-        println!("Result: {:?}", result_value);
-    }
+    async fn set_asset_borrow_cap(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let cap = OptionalValue::Some(BigUint::<DebugApi>::from(0u128));
 
-    async fn debt_nft_token(&mut self) {
-        let result_value: TokenIdentifier<DebugApi> = self
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
             .interactor
-            .vm_query(self.contract.debt_nft_token())
+            .sc_call_get_result(
+                self.contract
+                    .set_asset_borrow_cap(asset, cap)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
             .await;
+        let result_value = result.value();
 
         println!("Result: {:?}", result_value);
     }
 
-    async fn account_positions(&mut self) {
-        let result_value: MultiValueVec<u64> = self
-            .interactor
-            .vm_query(self.contract.account_positions())
-            .await;
+    async fn set_asset_liquidation_bonus(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let liq_bonus = BigUint::<DebugApi>::from(0u128);
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_asset_liquidation_bonus(asset, liq_bonus)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_asset_isolated(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let isolated = false;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_asset_isolated(asset, isolated)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_isolation_borrowable(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let borrowable = false;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_isolation_borrowable(asset, borrowable)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_bad_debt_dust_threshold(&mut self) {
+        let threshold = BigUint::<DebugApi>::from(0u128);
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_bad_debt_dust_threshold(threshold)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_isolation_debt_ceiling(&mut self) {
+        let ceiling = BigUint::<DebugApi>::from(0u128);
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_isolation_debt_ceiling(ceiling)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_e_mode_category(&mut self) {
+        let category_id = 0u8;
+        let loan_to_value = BigUint::<DebugApi>::from(0u128);
+        let liquidation_threshold = BigUint::<DebugApi>::from(0u128);
+        let liquidation_bonus = BigUint::<DebugApi>::from(0u128);
+        let price_source = OptionalValue::Some(ManagedBuffer::new_from_bytes(&b""[..]));
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_e_mode_category(category_id, loan_to_value, liquidation_threshold, liquidation_bonus, price_source)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_asset_e_mode_category(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let category_id = 0u8;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_asset_e_mode_category(asset, category_id)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn pause_asset(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let actions = PlaceholderInput;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .pause_asset(asset, actions)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn unpause_asset(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let actions = PlaceholderInput;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .unpause_asset(asset, actions)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_timelock_delay(&mut self) {
+        let delay = 0u64;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_timelock_delay(delay)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn queue_change(&mut self) {
+        let change = PlaceholderInput;
+
+        let result: multiversx_sc_snippets::InteractorResult<u64> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .queue_change(change)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn execute_change(&mut self) {
+        let change_id = 0u64;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .execute_change(change_id)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn cancel_change(&mut self) {
+        let change_id = 0u64;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .cancel_change(change_id)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn get_timelock_delay(&mut self) {
+        let result_value: u64 = self
+            .interactor
+            .vm_query(self.contract.get_timelock_delay())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn queued_changes(&mut self) {
+        let result_value: MultiValueVec<MultiValue2<u64, QueuedChange<DebugApi>>> = self
+            .interactor
+            .vm_query(self.contract.queued_changes())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn add_collection(&mut self) {
+        let params = PlaceholderInput;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .add_collection(params)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn get_pool_address(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: ManagedAddress<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.get_pool_address(asset))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn pools_allowed(&mut self) {
+        let result_value: MultiValueVec<ManagedAddress<DebugApi>> = self
+            .interactor
+            .vm_query(self.contract.pools_allowed())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn treasury_address(&mut self) {
+        let result_value: ManagedAddress<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.treasury_address())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn asset_loan_to_value(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.asset_loan_to_value(asset))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn asset_supply_cap(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.asset_supply_cap(asset))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn asset_borrow_cap(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.asset_borrow_cap(asset))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn asset_liquidation_bonus(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.asset_liquidation_bonus(asset))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn account_token(&mut self) {
+        let result_value: TokenIdentifier<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.account_token())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn debt_nft_token(&mut self) {
+        let result_value: TokenIdentifier<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.debt_nft_token())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn account_positions(&mut self) {
+        let result_value: MultiValueVec<u64> = self
+            .interactor
+            .vm_query(self.contract.account_positions())
+            .await;
 
         println!("Result: {:?}", result_value);
     }
@@ -640,6 +1296,39 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn non_collateral_deposits(&mut self) {
+        let owner_nonce = 0u64;
+
+        let result_value: MultiValueVec<TokenIdentifier<DebugApi>> = self
+            .interactor
+            .vm_query(self.contract.non_collateral_deposits(owner_nonce))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn lend_token(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: TokenIdentifier<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.lend_token(asset))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn lend_token_asset(&mut self) {
+        let lend_token = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: TokenIdentifier<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.lend_token_asset(lend_token))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn borrow_positions(&mut self) {
         let owner_nonce = 0u64;
 
@@ -694,6 +1383,18 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn is_collateral_enabled(&mut self) {
+        let account_position = 0u64;
+        let token_id = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: bool = self
+            .interactor
+            .vm_query(self.contract.is_collateral_enabled(account_position, token_id))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn get_total_collateral_in_dollars(&mut self) {
         let account_position = 0u64;
 
@@ -716,6 +1417,110 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn isolation_debt_ceiling(&mut self) {
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.isolation_debt_ceiling())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn isolated_debt(&mut self) {
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.isolated_debt())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn account_isolated_debt(&mut self) {
+        let account_nonce = 0u64;
+        let token_id = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.account_isolated_debt(account_nonce, &token_id))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn account_isolated_debt_in_dollars(&mut self) {
+        let account_nonce = 0u64;
+        let token_id = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.account_isolated_debt_in_dollars(account_nonce, &token_id))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn bad_debt_dust_threshold(&mut self) {
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.bad_debt_dust_threshold())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn e_mode_categories(&mut self) {
+        let category_id = 0u8;
+
+        let result_value: EModeCategory<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.e_mode_categories(category_id))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn asset_e_mode_category(&mut self) {
+        let asset = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: u8 = self
+            .interactor
+            .vm_query(self.contract.asset_e_mode_category(asset))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn account_e_mode_category(&mut self) {
+        let owner_nonce = 0u64;
+
+        let result_value: u8 = self
+            .interactor
+            .vm_query(self.contract.account_e_mode_category(owner_nonce))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn is_account_isolated(&mut self) {
+        let account_position = 0u64;
+
+        let result_value: bool = self
+            .interactor
+            .vm_query(self.contract.is_account_isolated(account_position))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn get_isolation_remaining_debt(&mut self) {
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.get_isolation_remaining_debt())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn set_price_aggregator_address(&mut self) {
         let address = bech32::decode("");
 
@@ -735,10 +1540,10 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
-    async fn price_aggregator_address(&mut self) {
-        let result_value: ManagedAddress<DebugApi> = self
+    async fn price_aggregator_addresses(&mut self) {
+        let result_value: MultiValueVec<ManagedAddress<DebugApi>> = self
             .interactor
-            .vm_query(self.contract.price_aggregator_address())
+            .vm_query(self.contract.price_aggregator_addresses())
             .await;
 
         println!("Result: {:?}", result_value);
@@ -0,0 +1,234 @@
+multiversx_sc::imports!();
+
+use common_structs::{EModeCategory, TimelockedChange};
+
+// Account events carry the account's health factor after the action, scaled by BP
+#[multiversx_sc::module]
+pub trait EventsModule {
+    // The token is the one paid in, either the asset or its lend token
+    #[event("deposit")]
+    fn deposit_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] account_nonce: u64,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] amount: &BigUint,
+        #[indexed] as_collateral: bool,
+        health_factor: &BigUint,
+    );
+
+    #[event("withdraw")]
+    fn withdraw_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] account_nonce: u64,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] amount: &BigUint,
+        health_factor: &BigUint,
+    );
+
+    #[event("borrow")]
+    fn borrow_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] account_nonce: u64,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] amount: &BigUint,
+        health_factor: &BigUint,
+    );
+
+    // The amount is the payment; the pool refunds whatever exceeded the debt
+    #[event("repay")]
+    fn repay_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] account_nonce: u64,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] amount: &BigUint,
+        health_factor: &BigUint,
+    );
+
+    #[event("liquidation")]
+    fn liquidation_event(
+        &self,
+        #[indexed] liquidator: &ManagedAddress,
+        #[indexed] account_nonce: u64,
+        #[indexed] debt_asset: &TokenIdentifier,
+        #[indexed] repaid_amount: &BigUint,
+        #[indexed] collateral_asset: &TokenIdentifier,
+        #[indexed] seized_amount: &BigUint,
+        health_factor: &BigUint,
+    );
+
+    #[event("collateral_enabled")]
+    fn collateral_enabled_event(
+        &self,
+        #[indexed] account_nonce: u64,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] enabled: bool,
+        health_factor: &BigUint,
+    );
+
+    #[event("account_e_mode_category")]
+    fn account_e_mode_category_event(
+        &self,
+        #[indexed] account_nonce: u64,
+        #[indexed] category_id: u8,
+        health_factor: &BigUint,
+    );
+
+    #[event("lend_tokens_minted")]
+    fn lend_tokens_minted_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] amount: &BigUint,
+        lend_amount: &BigUint,
+    );
+
+    #[event("lend_tokens_redeemed")]
+    fn lend_tokens_redeemed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] lend_amount: &BigUint,
+        amount: &BigUint,
+    );
+
+    #[event("nft_borrow")]
+    fn nft_borrow_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] debt_nft_nonce: u64,
+        #[indexed] collection: &TokenIdentifier,
+        #[indexed] nonce: u64,
+        #[indexed] asset: &TokenIdentifier,
+        amount: &BigUint,
+    );
+
+    // A zero remaining debt means the loan is closed and the NFT returned
+    #[event("nft_repay")]
+    fn nft_repay_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] debt_nft_nonce: u64,
+        #[indexed] asset: &TokenIdentifier,
+        remaining_debt: &BigUint,
+    );
+
+    #[event("nft_auction_started")]
+    fn nft_auction_started_event(
+        &self,
+        #[indexed] debt_nft_nonce: u64,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] start_price: &BigUint,
+        #[indexed] end_price: &BigUint,
+        end_timestamp: u64,
+    );
+
+    #[event("nft_auction_bid")]
+    fn nft_auction_bid_event(
+        &self,
+        #[indexed] bidder: &ManagedAddress,
+        #[indexed] debt_nft_nonce: u64,
+        #[indexed] price: &BigUint,
+        surplus: &BigUint,
+    );
+
+    #[event("nft_borrow_cap_reached")]
+    fn nft_borrow_cap_reached_event(
+        &self,
+        #[indexed] collection: &TokenIdentifier,
+        #[indexed] nonce: u64,
+        cap: &BigUint,
+    );
+
+    #[event("collection_borrow_cap_reached")]
+    fn collection_borrow_cap_reached_event(
+        &self,
+        #[indexed] collection: &TokenIdentifier,
+        #[indexed] asset: &TokenIdentifier,
+        cap: &BigUint,
+    );
+
+    #[event("bad_debt_written_off")]
+    fn bad_debt_written_off_event(
+        &self,
+        #[indexed] account_nonce: u64,
+        #[indexed] token_id: &TokenIdentifier,
+        #[indexed] bad_debt: &BigUint,
+        covered_amount: &BigUint,
+    );
+
+    #[event("nft_bad_debt_written_off")]
+    fn nft_bad_debt_written_off_event(
+        &self,
+        #[indexed] debt_nft_nonce: u64,
+        #[indexed] token_id: &TokenIdentifier,
+        #[indexed] bad_debt: &BigUint,
+        covered_amount: &BigUint,
+    );
+
+    #[event("liquidity_pool_created")]
+    fn liquidity_pool_created_event(
+        &self,
+        #[indexed] asset: &TokenIdentifier,
+        address: &ManagedAddress,
+    );
+
+    #[event("asset_risk_params")]
+    fn asset_risk_params_event(
+        &self,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] loan_to_value: &BigUint,
+        #[indexed] liquidation_threshold: &BigUint,
+        liquidation_bonus: &BigUint,
+    );
+
+    // A missing cap leaves the asset uncapped
+    #[event("asset_caps")]
+    fn asset_caps_event(
+        &self,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] supply_cap: &Option<BigUint>,
+        borrow_cap: &Option<BigUint>,
+    );
+
+    #[event("asset_isolation")]
+    fn asset_isolation_event(
+        &self,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] isolated: bool,
+        borrowable_in_isolation: bool,
+    );
+
+    #[event("isolation_debt_ceiling")]
+    fn isolation_debt_ceiling_event(&self, ceiling: &BigUint);
+
+    #[event("e_mode_category")]
+    fn e_mode_category_event(
+        &self,
+        #[indexed] category_id: u8,
+        category: &EModeCategory<Self::Api>,
+    );
+
+    #[event("asset_e_mode_category")]
+    fn asset_e_mode_category_event(&self, #[indexed] asset: &TokenIdentifier, category_id: u8);
+
+    #[event("change_queued")]
+    fn change_queued_event(
+        &self,
+        #[indexed] change_id: u64,
+        #[indexed] eta: u64,
+        change: &TimelockedChange<Self::Api>,
+    );
+
+    #[event("change_executed")]
+    fn change_executed_event(&self, #[indexed] change_id: u64);
+
+    #[event("change_cancelled")]
+    fn change_cancelled_event(&self, #[indexed] change_id: u64);
+
+    #[event("timelock_delay")]
+    fn timelock_delay_event(&self, delay: u64);
+}
@@ -3,9 +3,13 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
+use common_structs::InterestRateStrategy;
+
 pub mod liq_pool_proxy {
     multiversx_sc::imports!();
 
+    use common_structs::InterestRateStrategy;
+
     #[multiversx_sc::proxy]
     pub trait LiqPoolProxy {
         #[init]
@@ -18,6 +22,7 @@ pub mod liq_pool_proxy {
             u_optimal: BigUint,
             reserve_factor: BigUint,
             liquidation_threshold: BigUint,
+            rate_strategy: OptionalValue<InterestRateStrategy<Self::Api>>,
         );
     }
 }
@@ -33,6 +38,7 @@ pub trait FactoryModule {
         u_optimal: BigUint,
         reserve_factor: BigUint,
         liquidation_threshold: BigUint,
+        rate_strategy: OptionalValue<InterestRateStrategy<Self::Api>>,
     ) -> ManagedAddress {
         require!(
             !self.liq_pool_template_address().is_empty(),
@@ -49,6 +55,7 @@ pub trait FactoryModule {
                 u_optimal,
                 reserve_factor,
                 liquidation_threshold,
+                rate_strategy,
             )
             .deploy_from_source::<()>(
                 &self.liq_pool_template_address().get(),
@@ -68,6 +75,7 @@ pub trait FactoryModule {
         u_optimal: BigUint,
         reserve_factor: BigUint,
         liquidation_threshold: BigUint,
+        rate_strategy: OptionalValue<InterestRateStrategy<Self::Api>>,
     ) {
         require!(
             !self.liq_pool_template_address().is_empty(),
@@ -83,6 +91,7 @@ pub trait FactoryModule {
                 u_optimal,
                 reserve_factor,
                 liquidation_threshold,
+                rate_strategy,
             )
             .upgrade_from_source(
                 &self.liq_pool_template_address().get(),
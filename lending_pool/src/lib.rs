@@ -3,11 +3,14 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
+pub mod events;
 pub mod factory;
 mod math;
+pub mod nft_liquidation;
 mod proxy;
 pub mod router;
 pub mod storage;
+pub mod timelock;
 pub mod utils;
 
 pub use common_structs::*;
@@ -18,20 +21,26 @@ use multiversx_sc::codec::Empty;
 
 #[multiversx_sc::contract]
 pub trait LendingPool:
-    factory::FactoryModule
+    events::EventsModule
+    + factory::FactoryModule
     + router::RouterModule
     + common_checks::ChecksModule
     + common_tokens::AccountTokenModule
     + proxy::ProxyModule
     + storage::LendingStorageModule
+    + timelock::TimelockModule
     + utils::LendingUtilsModule
     + math::LendingMathModule
+    + nft_liquidation::NftLiquidationModule
     + price_aggregator_proxy::PriceAggregatorModule
+    + common_permissions::PermissionsModule
+    + common_pause::PauseModule
     + multiversx_sc_modules::default_issue_callbacks::DefaultIssueCallbacksModule
 {
     #[init]
     fn init(&self, lp_template_address: ManagedAddress) {
         self.liq_pool_template_address().set(&lp_template_address);
+        self.migrate_price_aggregator_address();
     }
 
     #[only_owner]
@@ -64,6 +73,53 @@ pub trait LendingPool:
         );
     }
 
+    // One fungible lend token per pool, minted by this contract for the pool's suppliers
+    #[payable("EGLD")]
+    #[endpoint(registerLendToken)]
+    fn register_lend_token(
+        &self,
+        pool_asset_id: TokenIdentifier,
+        token_name: ManagedBuffer,
+        ticker: ManagedBuffer,
+        num_decimals: usize,
+    ) {
+        self.require_role(Role::ListingAdmin);
+
+        self.require_asset_supported(&pool_asset_id);
+
+        let payment_amount = self.call_value().egld_value();
+        self.lend_token(&pool_asset_id).issue_and_set_all_roles(
+            payment_amount.clone_value(),
+            token_name,
+            ticker,
+            num_decimals,
+            Some(<Self as LendingPool>::callbacks(self).lend_token_issue_callback(pool_asset_id)),
+        );
+    }
+
+    #[callback]
+    fn lend_token_issue_callback(
+        &self,
+        pool_asset_id: TokenIdentifier,
+        #[call_result] result: ManagedAsyncCallResult<TokenIdentifier>,
+    ) {
+        match result {
+            ManagedAsyncCallResult::Ok(lend_token) => {
+                self.lend_token(&pool_asset_id)
+                    .set_token_id(lend_token.clone());
+                self.lend_token_asset(&lend_token).set(&pool_asset_id);
+
+                let _: IgnoreValue = self
+                    .liquidity_pool_proxy(self.get_pool_address(&pool_asset_id))
+                    .set_lend_token(lend_token)
+                    .execute_on_dest_context();
+            }
+            ManagedAsyncCallResult::Err(_) => {
+                self.lend_token(&pool_asset_id).clear();
+            }
+        }
+    }
+
     #[endpoint(enterMarket)]
     fn enter_market(&self) -> EsdtTokenPayment {
         let caller = self.blockchain().get_caller();
@@ -91,37 +147,164 @@ pub trait LendingPool:
     #[payable("*")]
     #[endpoint(addCollateral)]
     fn add_collateral(&self) {
+        self.deposit_into_account(true);
+    }
+
+    // Earns the deposit rate without counting toward the account's borrowing power
+    #[payable("*")]
+    #[endpoint]
+    fn supply(&self) {
+        self.deposit_into_account(false);
+    }
+
+    #[payable("*")]
+    #[endpoint(setCollateralEnabled)]
+    fn set_collateral_enabled(&self, asset: TokenIdentifier, enabled: bool) {
+        let (nft_account_token_id, nft_account_nonce, nft_account_amount) =
+            self.call_value().single_esdt().into_tuple();
+        let initial_caller = self.blockchain().get_caller();
+
+        self.lending_account_in_the_market(nft_account_nonce);
+        self.lending_account_token_valid(nft_account_token_id.clone());
+        require!(
+            self.deposit_positions(nft_account_nonce)
+                .contains_key(&asset),
+            "no deposit for this asset"
+        );
+
+        if enabled {
+            self.non_collateral_deposits(nft_account_nonce)
+                .swap_remove(&asset);
+        } else {
+            self.non_collateral_deposits(nft_account_nonce)
+                .insert(asset.clone());
+        }
+        self.update_account_price_rounds(nft_account_nonce);
+        let health_factor = self.get_account_health_factor(nft_account_nonce);
+        require!(
+            enabled || health_factor >= BP,
+            "health factor too low to disable collateral"
+        );
+        self.collateral_enabled_event(nft_account_nonce, &asset, enabled, &health_factor);
+
+        // Return NFT to owner
+        self.send().direct_esdt(
+            &initial_caller,
+            &nft_account_token_id,
+            nft_account_nonce,
+            &nft_account_amount,
+        );
+    }
+
+    // A zero category leaves e-mode; the account's debt must all be in the new category
+    #[payable("*")]
+    #[endpoint(setAccountEModeCategory)]
+    fn set_account_e_mode_category(&self, category_id: u8) {
+        let (nft_account_token_id, nft_account_nonce, nft_account_amount) =
+            self.call_value().single_esdt().into_tuple();
+        let initial_caller = self.blockchain().get_caller();
+
+        self.lending_account_in_the_market(nft_account_nonce);
+        self.lending_account_token_valid(nft_account_token_id.clone());
+        require!(
+            category_id == 0 || !self.e_mode_categories(category_id).is_empty(),
+            "invalid e-mode category"
+        );
+        if category_id != 0 {
+            for token_id in self.borrow_positions(nft_account_nonce).keys() {
+                require!(
+                    self.asset_e_mode_category(&token_id).get() == category_id,
+                    "borrowed assets must belong to the e-mode category"
+                );
+            }
+        }
+
+        self.account_e_mode_category(nft_account_nonce)
+            .set(category_id);
+        self.update_account_price_rounds(nft_account_nonce);
+        let health_factor = self.get_account_health_factor(nft_account_nonce);
+        require!(
+            health_factor >= BP,
+            "health factor too low to change the e-mode category"
+        );
+        self.account_e_mode_category_event(nft_account_nonce, category_id, &health_factor);
+
+        // Return NFT to owner
+        self.send().direct_esdt(
+            &initial_caller,
+            &nft_account_token_id,
+            nft_account_nonce,
+            &nft_account_amount,
+        );
+    }
+
+    // New supply-only positions stay out of the collateral, existing positions keep their setting
+    fn deposit_into_account(&self, as_collateral: bool) {
+        self.require_not_paused(PausableAction::Supply);
+
         let [nft_account_token, collateral_payment] = self.call_value().multi_esdt();
         let (nft_account_token_id, nft_account_nonce, nft_account_amount) =
             nft_account_token.into_tuple();
         let (collateral_token_id, collateral_nonce, collateral_amount) =
             collateral_payment.into_tuple();
-        let pool_address = self.get_pool_address(&collateral_token_id);
+        // Lend tokens are collateral of their pool's asset
+        let is_lend_token = !self.lend_token_asset(&collateral_token_id).is_empty();
+        let collateral_asset = if is_lend_token {
+            self.lend_token_asset(&collateral_token_id).get()
+        } else {
+            collateral_token_id.clone()
+        };
+        let pool_address = self.get_pool_address(&collateral_asset);
         let initial_caller = self.blockchain().get_caller();
 
-        self.require_asset_supported(&collateral_token_id);
+        self.require_asset_supported(&collateral_asset);
         self.lending_account_in_the_market(nft_account_nonce);
         self.lending_account_token_valid(nft_account_token_id.clone());
         self.require_amount_greater_than_zero(&collateral_amount);
         self.require_non_zero_address(&initial_caller);
 
+        let is_new_position = !self
+            .deposit_positions(nft_account_nonce)
+            .contains_key(&collateral_asset);
         let initial_or_new_deposit_position = self.get_existing_or_new_deposit_position_for_token(
             nft_account_nonce,
-            collateral_token_id.clone(),
+            collateral_asset.clone(),
         );
 
-        let return_deposit_position = self
-            .liquidity_pool_proxy(pool_address)
-            .add_collateral(initial_or_new_deposit_position)
-            .with_esdt_transfer((
-                collateral_token_id.clone(),
-                collateral_nonce,
-                collateral_amount,
-            ))
-            .execute_on_dest_context();
+        let return_deposit_position = if is_lend_token {
+            self.lend_token(&collateral_asset).burn(&collateral_amount);
+
+            self.liquidity_pool_proxy(pool_address)
+                .add_lend_token_collateral(&collateral_amount, initial_or_new_deposit_position)
+                .execute_on_dest_context()
+        } else {
+            self.liquidity_pool_proxy(pool_address)
+                .add_collateral(initial_or_new_deposit_position)
+                .with_esdt_transfer((
+                    collateral_token_id.clone(),
+                    collateral_nonce,
+                    collateral_amount.clone(),
+                ))
+                .execute_on_dest_context()
+        };
 
         self.deposit_positions(nft_account_nonce)
-            .insert(collateral_token_id, return_deposit_position);
+            .insert(collateral_asset.clone(), return_deposit_position);
+        if as_collateral {
+            self.non_collateral_deposits(nft_account_nonce)
+                .swap_remove(&collateral_asset);
+        } else if is_new_position {
+            self.non_collateral_deposits(nft_account_nonce)
+                .insert(collateral_asset);
+        }
+        self.deposit_event(
+            &initial_caller,
+            nft_account_nonce,
+            &collateral_token_id,
+            &collateral_amount,
+            as_collateral,
+            &self.get_account_health_factor(nft_account_nonce),
+        );
 
         // Return NFT to owner
         self.send().direct_esdt(
@@ -132,9 +315,63 @@ pub trait LendingPool:
         );
     }
 
+    // Supplies liquidity for transferable lend tokens, outside of any lending account
+    #[payable("*")]
+    #[endpoint(mintLendTokens)]
+    fn mint_lend_tokens(&self) -> EsdtTokenPayment {
+        self.require_not_paused(PausableAction::Supply);
+
+        let (asset, amount) = self.call_value().single_fungible_esdt();
+        let initial_caller = self.blockchain().get_caller();
+
+        self.require_asset_supported(&asset);
+        self.require_amount_greater_than_zero(&amount);
+        require!(
+            !self.lend_token(&asset).is_empty(),
+            "no lend token issued for this asset"
+        );
+
+        let lend_amount: BigUint = self
+            .liquidity_pool_proxy(self.get_pool_address(&asset))
+            .supply_for_lend_tokens()
+            .with_esdt_transfer((asset.clone(), 0, amount.clone()))
+            .execute_on_dest_context();
+
+        self.lend_tokens_minted_event(&initial_caller, &asset, &amount, &lend_amount);
+        self.lend_token(&asset)
+            .mint_and_send(&initial_caller, lend_amount)
+    }
+
+    #[payable("*")]
+    #[endpoint(redeemLendTokens)]
+    fn redeem_lend_tokens(&self) -> BigUint {
+        self.require_not_paused(PausableAction::Withdraw);
+
+        let (lend_token, lend_amount) = self.call_value().single_fungible_esdt();
+        let initial_caller = self.blockchain().get_caller();
+
+        require!(
+            !self.lend_token_asset(&lend_token).is_empty(),
+            "invalid lend token"
+        );
+        let asset = self.lend_token_asset(&lend_token).get();
+
+        self.lend_token(&asset).burn(&lend_amount);
+
+        let amount: BigUint = self
+            .liquidity_pool_proxy(self.get_pool_address(&asset))
+            .redeem_lend_tokens(&initial_caller, &lend_amount)
+            .execute_on_dest_context();
+
+        self.lend_tokens_redeemed_event(&initial_caller, &asset, &lend_amount, &amount);
+        amount
+    }
+
     #[payable("*")]
     #[endpoint(removeCollateral)]
     fn remove_collateral(&self, withdraw_token_id: TokenIdentifier, amount: BigUint) {
+        self.require_not_paused(PausableAction::Withdraw);
+
         let (nft_account_token_id, nft_account_nonce, nft_account_amount) =
             self.call_value().single_esdt().into_tuple();
         let initial_caller = self.blockchain().get_caller();
@@ -159,13 +396,15 @@ pub trait LendingPool:
             Some(dp) => {
                 let deposit_position: DepositPosition<<Self as ContractBase>::Api> = self
                     .liquidity_pool_proxy(pool_address)
-                    .remove_collateral(&initial_caller, amount, dp)
+                    .remove_collateral(&initial_caller, &amount, dp)
                     .execute_on_dest_context();
 
                 if deposit_position.amount == 0 {
                     dep_pos_map.remove(&withdraw_token_id);
+                    self.non_collateral_deposits(nft_account_nonce)
+                        .swap_remove(&withdraw_token_id);
                 } else {
-                    dep_pos_map.insert(withdraw_token_id, deposit_position);
+                    dep_pos_map.insert(withdraw_token_id.clone(), deposit_position);
                 }
             }
             None => panic!(
@@ -173,6 +412,13 @@ pub trait LendingPool:
                 withdraw_token_id
             ),
         };
+        self.withdraw_event(
+            &initial_caller,
+            nft_account_nonce,
+            &withdraw_token_id,
+            &amount,
+            &self.get_account_health_factor(nft_account_nonce),
+        );
         // Return NFT to owner
         self.send().direct_esdt(
             &initial_caller,
@@ -185,11 +431,23 @@ pub trait LendingPool:
     #[payable("*")]
     #[endpoint]
     fn borrow(&self, asset_to_borrow: TokenIdentifier, amount: BigUint) {
+        self.require_not_paused(PausableAction::Borrow);
+
         let (nft_account_token_id, nft_account_nonce, nft_account_amount) =
             self.call_value().single_esdt().into_tuple();
         let initial_caller = self.blockchain().get_caller();
         let borrow_token_pool_address = self.get_pool_address(&asset_to_borrow);
-        let loan_to_value = self.get_loan_to_value_exists_and_non_zero(&asset_to_borrow);
+        // Accounts in e-mode only borrow assets of their category
+        if self
+            .get_account_e_mode_category(nft_account_nonce)
+            .is_some()
+        {
+            require!(
+                self.get_e_mode_category_for_asset(nft_account_nonce, &asset_to_borrow)
+                    .is_some(),
+                "asset not in the account's e-mode category"
+            );
+        }
 
         self.require_asset_supported(&asset_to_borrow);
         self.lending_account_in_the_market(nft_account_nonce);
@@ -197,20 +455,38 @@ pub trait LendingPool:
         self.require_amount_greater_than_zero(&amount);
         self.require_non_zero_address(&initial_caller);
 
-        self.update_collateral_with_interest(nft_account_nonce);
-        self.update_borrows_with_debt(nft_account_nonce);
-
-        let collateral_in_dollars = self.get_total_collateral_in_dollars(nft_account_nonce);
-        let borrowed_amount_in_dollars = self.get_total_borrow_in_dollars(nft_account_nonce);
-        let amount_to_borrow_in_dollars =
-            amount.clone() * self.get_token_price_data(&asset_to_borrow).price;
+        self.update_account_price_rounds(nft_account_nonce);
+        self.update_account_token_price_rounds(nft_account_nonce, &asset_to_borrow);
+        let mut pool_indexes = ManagedVec::new();
+        let borrowable_collateral_in_dollars =
+            self.compute_borrowable_collateral_in_dollars(nft_account_nonce, &mut pool_indexes);
+        let borrowed_amount_in_dollars =
+            self.compute_borrow_in_dollars(nft_account_nonce, &mut pool_indexes);
+        let amount_to_borrow_in_dollars = amount.clone()
+            * self
+                .get_account_token_price_data(nft_account_nonce, &asset_to_borrow)
+                .price;
 
         require!(
-            collateral_in_dollars * loan_to_value
-                > (borrowed_amount_in_dollars + amount_to_borrow_in_dollars),
+            borrowable_collateral_in_dollars
+                > (borrowed_amount_in_dollars + &amount_to_borrow_in_dollars),
             "Not enough collateral available for this loan!"
         );
 
+        if self.is_account_isolated(nft_account_nonce) {
+            require!(
+                self.isolation_borrowable_assets()
+                    .contains(&asset_to_borrow),
+                "asset not borrowable in isolation mode"
+            );
+            self.increase_isolated_debt(
+                nft_account_nonce,
+                &asset_to_borrow,
+                &amount,
+                &amount_to_borrow_in_dollars,
+            );
+        }
+
         let initial_borrow_position = self.get_existing_or_new_borrow_position_for_token(
             nft_account_nonce,
             asset_to_borrow.clone(),
@@ -218,7 +494,7 @@ pub trait LendingPool:
 
         let borrow_position: BorrowPosition<Self::Api> = self
             .liquidity_pool_proxy(borrow_token_pool_address)
-            .borrow(&initial_caller, amount, initial_borrow_position)
+            .borrow(&initial_caller, &amount, initial_borrow_position)
             .execute_on_dest_context();
 
         if borrow_position.amount == 0 {
@@ -228,8 +504,15 @@ pub trait LendingPool:
         } else {
             // Update BorrowPosition if it's not empty
             self.borrow_positions(nft_account_nonce)
-                .insert(asset_to_borrow, borrow_position);
+                .insert(asset_to_borrow.clone(), borrow_position);
         }
+        self.borrow_event(
+            &initial_caller,
+            nft_account_nonce,
+            &asset_to_borrow,
+            &amount,
+            &self.get_account_health_factor(nft_account_nonce),
+        );
 
         // Return NFT account to owner
         self.send().direct_esdt(
@@ -247,6 +530,8 @@ pub trait LendingPool:
         asset_to_borrow: TokenIdentifier<Self::Api>,
         amount: BigUint<Self::Api>,
     ) -> ManagedVec<EsdtTokenPayment<Self::Api>> {
+        self.require_not_paused(PausableAction::Borrow);
+
         let payments = self.call_value().all_esdt_transfers();
         let initial_caller = self.blockchain().get_caller();
         let borrow_token_pool_address = self.get_pool_address(&asset_to_borrow);
@@ -255,12 +540,12 @@ pub trait LendingPool:
         self.require_amount_greater_than_zero(&amount);
         self.require_non_zero_address(&initial_caller);
 
+        self.update_nft_loan_price_rounds(&asset_to_borrow);
         let map_collections = self.collections();
 
         let mut payments_out: ManagedVec<EsdtTokenPayment<Self::Api>> = ManagedVec::new();
         let borrow_amount_usd = self.get_token_price_data(&asset_to_borrow).price;
-        let amount_to_borrow_in_dollars = borrow_amount_usd * amount.clone();
-        let egld_usd_price = self.get_egld_price_data().price;
+        let amount_to_borrow_in_dollars = &borrow_amount_usd * &amount;
         let mut total_collateral_nfts = BigUint::zero();
         let mut borrow_positions: ManagedVec<BorrowPosition<Self::Api>> = ManagedVec::new();
         let mut original_total_amount = amount.clone();
@@ -268,7 +553,22 @@ pub trait LendingPool:
             let collection_exists = map_collections.contains(&payment.token_identifier);
             require!(collection_exists, "Collection is not allowed as collateral");
             let collection_params = self.collection_params(&payment.token_identifier).get();
-            let max_borrow = (collection_params.floor * &payment.amount) * collection_params.ltv;
+            self.update_collection_floor(&payment.token_identifier);
+            let floor = self.get_collection_floor(&payment.token_identifier);
+            // Floors and max_borrow are in EGLD, the borrowed amounts in the borrowed token
+            let ltv_borrow = floor * &payment.amount * collection_params.ltv / BP;
+            let nft_borrow_cap = collection_params.max_borrow * &payment.amount;
+            let max_borrow_in_egld = if ltv_borrow > nft_borrow_cap {
+                self.nft_borrow_cap_reached_event(
+                    &payment.token_identifier,
+                    payment.token_nonce,
+                    &nft_borrow_cap,
+                );
+                nft_borrow_cap
+            } else {
+                ltv_borrow
+            };
+            let max_borrow = self.convert_egld_to_tokens(&max_borrow_in_egld, &asset_to_borrow);
             if max_borrow <= original_total_amount {
                 total_collateral_nfts += &max_borrow;
                 // reduce the amount to borrow with the amount borrowed from the NFT
@@ -280,7 +580,7 @@ pub trait LendingPool:
                     max_borrow,
                     0,
                     self.blockchain().get_block_round(),
-                    BigUint::from(BP),
+                    Ray::zero(),
                     Option::Some(payment),
                 ));
             } else {
@@ -294,7 +594,7 @@ pub trait LendingPool:
                         original_total_amount,
                         0,
                         self.blockchain().get_block_round(),
-                        BigUint::from(BP),
+                        Ray::zero(),
                         Option::Some(payment),
                     ));
 
@@ -306,10 +606,19 @@ pub trait LendingPool:
         }
 
         require!(
-            total_collateral_nfts * egld_usd_price > amount_to_borrow_in_dollars,
+            total_collateral_nfts * borrow_amount_usd > amount_to_borrow_in_dollars,
             "Not enough collateral available for this loan!"
         );
 
+        for position in &borrow_positions {
+            let collection = &position.nft.as_ref().unwrap().token_identifier;
+            self.increase_collection_borrowed_amount(
+                collection,
+                &position.token_id,
+                &position.amount,
+            );
+        }
+
         let borrow_positions: ManagedVec<BorrowPosition<Self::Api>> = self
             .liquidity_pool_proxy(borrow_token_pool_address)
             .borrow_bulk_nfts(&initial_caller, amount, borrow_positions)
@@ -334,6 +643,14 @@ pub trait LendingPool:
                 &nft_data.uris,
             );
 
+            self.nft_borrow_event(
+                &initial_caller,
+                nft_nonce,
+                &real_nft.token_identifier,
+                real_nft.token_nonce,
+                &asset_to_borrow,
+                &last_position.amount,
+            );
             self.nft_borrow_positions(nft_nonce).set(last_position);
             payments_out.push(EsdtTokenPayment::new(
                 debt_token.clone(),
@@ -349,6 +666,8 @@ pub trait LendingPool:
     #[payable("*")]
     #[endpoint]
     fn repay(&self) {
+        self.require_not_paused(PausableAction::Repay);
+
         let [nft_account_token, payment_repay] = self.call_value().multi_esdt();
         let (nft_account_token_id, nft_account_nonce, nft_account_amount) =
             nft_account_token.into_tuple();
@@ -367,18 +686,25 @@ pub trait LendingPool:
             .get(&repay_token_id)
         {
             Some(bp) => {
+                let initial_scaled_amount = bp.scaled_amount.clone();
                 let borrow_position: BorrowPosition<Self::Api> = self
                     .liquidity_pool_proxy(asset_address)
                     .repay(&initial_caller, bp)
-                    .with_esdt_transfer((repay_token_id.clone(), repay_nonce, repay_amount))
+                    .with_esdt_transfer((repay_token_id.clone(), repay_nonce, repay_amount.clone()))
                     .execute_on_dest_context();
+                self.decrease_isolated_debt(
+                    nft_account_nonce,
+                    &repay_token_id,
+                    &initial_scaled_amount,
+                    &borrow_position.scaled_amount,
+                );
 
                 // Update BorrowPosition
                 self.borrow_positions(nft_account_nonce)
                     .remove(&repay_token_id);
                 if borrow_position.amount != 0 {
                     self.borrow_positions(nft_account_nonce)
-                        .insert(repay_token_id, borrow_position);
+                        .insert(repay_token_id.clone(), borrow_position);
                 }
             }
             None => panic!(
@@ -386,6 +712,13 @@ pub trait LendingPool:
                 repay_token_id
             ),
         };
+        self.repay_event(
+            &initial_caller,
+            nft_account_nonce,
+            &repay_token_id,
+            &repay_amount,
+            &self.get_account_health_factor(nft_account_nonce),
+        );
         // Return NFT to owner
         self.send().direct_esdt(
             &initial_caller,
@@ -425,9 +758,16 @@ pub trait LendingPool:
         for debt_nft in nft_tokens.iter() {
             let (debt_token, debt_nonce, _) = debt_nft.clone().into_tuple();
             self.debt_nft_token().require_same_token(&debt_token);
-            let debt_data = self
-                .debt_nft_token()
-                .get_token_attributes::<BorrowPosition<Self::Api>>(debt_nonce);
+            require!(
+                !self.nft_borrow_positions(debt_nonce).is_empty(),
+                "no NFT loan for this debt token"
+            );
+            // The NFT attributes keep the position as first borrowed, the stored one is current
+            let debt_data = self.nft_borrow_positions(debt_nonce).get();
+            require!(
+                self.nft_auctions(debt_nonce).is_empty() && debt_data.nft.is_some(),
+                "NFT collateral is being liquidated"
+            );
             require!(
                 debt_data.token_id == *repay_token_id,
                 "Repayment token must be the same as the debt token"
@@ -440,6 +780,8 @@ pub trait LendingPool:
     #[payable("*")]
     #[endpoint(repayNFT)]
     fn repay_nft_debt(&self) -> ManagedVec<EsdtTokenPayment<Self::Api>> {
+        self.require_not_paused(PausableAction::Repay);
+
         let all_tokens = self.call_value().all_esdt_transfers();
         let (repay_token_id, repay_nonce, repay_amount, nft_tokens) =
             self.get_repay_and_nft_info(all_tokens);
@@ -458,12 +800,27 @@ pub trait LendingPool:
         > = self
             .liquidity_pool_proxy(asset_address)
             .repay_nfts(&initial_caller, vec_borrow_positions)
-            .with_esdt_transfer((repay_token_id, repay_nonce, repay_amount))
+            .with_esdt_transfer((repay_token_id.clone(), repay_nonce, repay_amount))
             .execute_on_dest_context();
 
         let mut payments_out: ManagedVec<EsdtTokenPayment<Self::Api>> = ManagedVec::new();
         for positions in processed_positions.into_iter() {
             let (token, borrow_pos) = positions.into_tuple();
+            self.nft_repay_event(
+                &initial_caller,
+                token.token_nonce,
+                &repay_token_id,
+                &borrow_pos.amount,
+            );
+            let previous_amount = self.nft_borrow_positions(token.token_nonce).get().amount;
+            if previous_amount > borrow_pos.amount {
+                let collection = &borrow_pos.nft.as_ref().unwrap().token_identifier;
+                self.decrease_collection_borrowed_amount(
+                    collection,
+                    &repay_token_id,
+                    &(previous_amount - &borrow_pos.amount),
+                );
+            }
             if borrow_pos.amount != 0 {
                 self.nft_borrow_positions(token.token_nonce).set(borrow_pos);
                 payments_out.push(token);
@@ -485,97 +842,206 @@ pub trait LendingPool:
 
     #[payable("*")]
     #[endpoint(liquidate)]
-    fn liquidate(
-        &self,
-        liquidatee_account_nonce: u64,
-        liquidation_threshold: BigUint,
-        token_to_liquidate: TokenIdentifier,
-    ) {
-        let (liquidator_asset_token_id, liquidator_asset_amount) =
-            self.call_value().single_fungible_esdt();
-        let bp = BigUint::from(BP);
+    fn liquidate(&self, liquidatee_account_nonce: u64, collateral_token_id: TokenIdentifier) {
+        self.require_not_paused(PausableAction::Liquidate);
 
+        let (debt_token_id, debt_payment_amount) = self.call_value().single_fungible_esdt();
         let initial_caller = self.blockchain().get_caller();
 
         // Liquidatee is in the market; Liquidator doesn't have to be in the Lending Protocol
         self.lending_account_in_the_market(liquidatee_account_nonce);
-        self.require_asset_supported(&liquidator_asset_token_id);
-        self.require_amount_greater_than_zero(&liquidator_asset_amount);
+        self.require_asset_supported(&debt_token_id);
+        self.require_asset_supported(&collateral_token_id);
+        self.require_amount_greater_than_zero(&debt_payment_amount);
         self.require_non_zero_address(&initial_caller);
-        require!(
-            token_to_liquidate == liquidator_asset_token_id,
-            "Token sent is not the same as the liquidation token!"
-        );
 
+        let liq_bonus = match (
+            self.get_e_mode_category_for_asset(liquidatee_account_nonce, &collateral_token_id),
+            self.get_e_mode_category_for_asset(liquidatee_account_nonce, &debt_token_id),
+        ) {
+            (Some(category), Some(_)) => category.liquidation_bonus,
+            _ => self.get_liquidation_bonus_non_zero(&collateral_token_id),
+        };
+        self.update_account_price_rounds(liquidatee_account_nonce);
+        let health_factor = self.get_account_health_factor(liquidatee_account_nonce);
+        require!(health_factor < BP, "health not low enough for liquidation");
+
+        let borrow_position = match self
+            .borrow_positions(liquidatee_account_nonce)
+            .get(&debt_token_id)
+        {
+            Some(bp) => bp,
+            None => sc_panic!("Liquidatee user doesn't have this token as debt"),
+        };
+
+        let debt_pool_address = self.get_pool_address(&debt_token_id);
+        let borrow_position_with_debt: BorrowPosition<Self::Api> = self
+            .liquidity_pool_proxy(debt_pool_address.clone())
+            .update_borrows_with_debt(borrow_position)
+            .execute_on_dest_context();
+        let repaid_amount = BigUint::min(
+            debt_payment_amount.clone(),
+            borrow_position_with_debt.amount.clone(),
+        );
         require!(
-            liquidation_threshold <= MAX_THRESHOLD,
+            repaid_amount <= &borrow_position_with_debt.amount * MAX_THRESHOLD / BP,
             MAX_THRESHOLD_ERROR_MSG
         );
 
-        let liq_bonus = self.get_liquidation_bonus_non_zero(&liquidator_asset_token_id);
-        let total_collateral_in_dollars =
-            self.get_total_collateral_in_dollars(liquidatee_account_nonce);
-        let borrowed_value_in_dollars = self.get_total_borrow_in_dollars(liquidatee_account_nonce);
-
-        let health_factor = self.compute_health_factor(
-            &total_collateral_in_dollars,
-            &borrowed_value_in_dollars,
-            &liquidation_threshold,
+        // repaid value (1 + liq_bonus), paid out in collateral tokens
+        let (amount_to_send, repaid_amount) = self.compute_amount_to_seize(
+            liquidatee_account_nonce,
+            &debt_token_id,
+            &repaid_amount,
+            &collateral_token_id,
+            &liq_bonus,
         );
-        require!(health_factor < BP, "health not low enough for liquidation");
+        require!(repaid_amount > 0, "collateral too small to liquidate");
 
-        let liquidator_asset_data = self.get_token_price_data(&liquidator_asset_token_id);
-        let liquidator_asset_value_in_dollars =
-            liquidator_asset_amount.clone() * liquidator_asset_data.price;
+        // Whatever the seized collateral doesn't pay for goes back to the liquidator
+        let refund_amount = &debt_payment_amount - &repaid_amount;
+        if refund_amount > 0 {
+            self.send()
+                .direct_esdt(&initial_caller, &debt_token_id, 0, &refund_amount);
+        }
 
-        let amount_needed_for_liquidation = borrowed_value_in_dollars * liquidation_threshold / &bp;
-        require!(
-            liquidator_asset_value_in_dollars >= amount_needed_for_liquidation,
-            "insufficient funds for liquidation"
+        let initial_scaled_amount = borrow_position_with_debt.scaled_amount.clone();
+        let borrow_position: BorrowPosition<Self::Api> = self
+            .liquidity_pool_proxy(debt_pool_address)
+            .repay(&initial_caller, borrow_position_with_debt)
+            .with_esdt_transfer((debt_token_id.clone(), 0, repaid_amount.clone()))
+            .execute_on_dest_context();
+        self.decrease_isolated_debt(
+            liquidatee_account_nonce,
+            &debt_token_id,
+            &initial_scaled_amount,
+            &borrow_position.scaled_amount,
         );
 
-        // amount_liquidated (1 + liq_bonus)
-        let amount_to_return_to_liquidator_in_dollars =
-            (liquidator_asset_amount * (&bp + &liq_bonus)) / bp;
+        if borrow_position.amount == 0 {
+            self.borrow_positions(liquidatee_account_nonce)
+                .remove(&debt_token_id);
+        } else {
+            self.borrow_positions(liquidatee_account_nonce)
+                .insert(debt_token_id.clone(), borrow_position);
+        }
+
+        let collateral_pool_address = self.get_pool_address(&collateral_token_id);
+        let deposit_position = self
+            .deposit_positions(liquidatee_account_nonce)
+            .get(&collateral_token_id)
+            .unwrap();
+
+        let deposit_position: DepositPosition<Self::Api> = self
+            .liquidity_pool_proxy(collateral_pool_address)
+            .seize_collateral(&initial_caller, &amount_to_send, deposit_position)
+            .execute_on_dest_context();
+
+        if deposit_position.amount == 0 {
+            self.deposit_positions(liquidatee_account_nonce)
+                .remove(&collateral_token_id);
+        } else {
+            self.deposit_positions(liquidatee_account_nonce)
+                .insert(collateral_token_id.clone(), deposit_position);
+        }
 
-        // Go through all DepositPositions and send amount_to_return_in_dollars to Liquidator
-        let amount_to_send = self.compute_amount_in_tokens(
+        if self.is_bad_debt(liquidatee_account_nonce) {
+            self.write_off_account_debt(liquidatee_account_nonce, &initial_caller);
+        }
+        self.liquidation_event(
+            &initial_caller,
             liquidatee_account_nonce,
-            token_to_liquidate.clone(),
-            amount_to_return_to_liquidator_in_dollars,
+            &debt_token_id,
+            &repaid_amount,
+            &collateral_token_id,
+            &amount_to_send,
+            &self.get_account_health_factor(liquidatee_account_nonce),
         );
+    }
 
-        let asset_address = self.get_pool_address(&token_to_liquidate);
+    // For accounts left with dust that is too small to liquidate; the caller gets the dust
+    #[endpoint(writeOffBadDebt)]
+    fn write_off_bad_debt(&self, account_nonce: u64) {
+        self.require_not_paused(PausableAction::Liquidate);
 
-        let _: IgnoreValue = self
-            .liquidity_pool_proxy(asset_address)
-            .send_tokens(&initial_caller, &amount_to_send)
-            .execute_on_dest_context();
+        self.lending_account_in_the_market(account_nonce);
+        self.update_account_price_rounds(account_nonce);
+        require!(
+            self.is_bad_debt(account_nonce),
+            "account has no debt or still has collateral to liquidate"
+        );
+
+        self.write_off_account_debt(account_nonce, &self.blockchain().get_caller());
     }
 
+    // Stores the positions with the interest accrued since they were last updated
     #[endpoint(updateCollateralWithInterest)]
     fn update_collateral_with_interest(&self, account_position: u64) {
-        let deposit_positions = self.deposit_positions(account_position);
-
-        for dp in deposit_positions.values() {
-            let asset_address = self.get_pool_address(&dp.token_id);
-            let _: IgnoreValue = self
+        for (token_id, dp) in self.deposit_positions(account_position).iter() {
+            let asset_address = self.get_pool_address(&token_id);
+            let dp: DepositPosition<Self::Api> = self
                 .liquidity_pool_proxy(asset_address)
                 .update_collateral_with_interest(dp)
                 .execute_on_dest_context();
+            self.deposit_positions(account_position)
+                .insert(token_id, dp);
         }
     }
 
     #[endpoint(updateBorrowsWithDebt)]
     fn update_borrows_with_debt(&self, account_position: u64) {
-        let borrow_positions = self.borrow_positions(account_position);
-
-        for bp in borrow_positions.values() {
-            let asset_address = self.get_pool_address(&bp.token_id);
-            let _: IgnoreValue = self
+        for (token_id, bp) in self.borrow_positions(account_position).iter() {
+            let asset_address = self.get_pool_address(&token_id);
+            let bp: BorrowPosition<Self::Api> = self
                 .liquidity_pool_proxy(asset_address)
                 .update_borrows_with_debt(bp)
                 .execute_on_dest_context();
+            self.borrow_positions(account_position).insert(token_id, bp);
+        }
+    }
+
+    // Debt left once the collateral is seized down to dust can never be liquidated, so each
+    // pool writes it off and gets whatever the safety module covers; the collateral dust goes
+    // to the caller
+    fn write_off_account_debt(&self, account_nonce: u64, caller: &ManagedAddress) {
+        for (token_id, borrow_position) in self.borrow_positions(account_nonce).iter() {
+            let pool_address = self.get_pool_address(&token_id);
+            let scaled_amount = borrow_position.scaled_amount.clone();
+            let (bad_debt, covered_amount) = self
+                .liquidity_pool_proxy(pool_address)
+                .write_off_bad_debt(borrow_position)
+                .execute_on_dest_context::<MultiValue2<BigUint, BigUint>>()
+                .into_tuple();
+
+            self.decrease_isolated_debt(account_nonce, &token_id, &scaled_amount, &Ray::zero());
+            self.bad_debt_written_off_event(account_nonce, &token_id, &bad_debt, &covered_amount);
+        }
+        self.borrow_positions(account_nonce).clear();
+
+        let mut seized_tokens: ManagedVec<TokenIdentifier> = ManagedVec::new();
+        for (token_id, deposit_position) in self.deposit_positions(account_nonce).iter() {
+            if !self.is_collateral_enabled(account_nonce, &token_id) {
+                continue;
+            }
+
+            seized_tokens.push(token_id.clone());
+            let pool_address = self.get_pool_address(&token_id);
+            let deposit_position: DepositPosition<Self::Api> = self
+                .liquidity_pool_proxy(pool_address.clone())
+                .update_collateral_with_interest(deposit_position)
+                .execute_on_dest_context();
+            if deposit_position.amount == 0 {
+                continue;
+            }
+
+            let dust_amount = deposit_position.amount.clone();
+            let _: IgnoreValue = self
+                .liquidity_pool_proxy(pool_address)
+                .seize_collateral(caller, &dust_amount, deposit_position)
+                .execute_on_dest_context();
+        }
+        for token_id in seized_tokens.iter() {
+            self.deposit_positions(account_nonce).remove(&token_id);
         }
     }
 
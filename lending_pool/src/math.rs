@@ -4,15 +4,17 @@ use common_structs::BP;
 
 #[multiversx_sc::module]
 pub trait LendingMathModule {
+    // Health factor with BP precision; an account without debt is reported as u64::MAX
     fn compute_health_factor(
         &self,
-        collateral_value_in_dollars: &BigUint,
+        weighted_collateral_in_dollars: &BigUint,
         borrowed_value_in_dollars: &BigUint,
-        liquidation_threshold: &BigUint,
     ) -> BigUint {
-        let allowed_collateral_in_dollars = collateral_value_in_dollars * liquidation_threshold;
-        let health_factor = &allowed_collateral_in_dollars / borrowed_value_in_dollars;
+        if borrowed_value_in_dollars == &0 {
+            // Built from bytes since BigUint::from(u64) goes through an i64 cast
+            return BigUint::from_bytes_be(&u64::MAX.to_be_bytes());
+        }
 
-        health_factor / BP
+        weighted_collateral_in_dollars * BP / borrowed_value_in_dollars
     }
 }
@@ -0,0 +1,300 @@
+multiversx_sc::imports!();
+
+use crate::{events, factory, math, proxy, router, storage, utils};
+
+use common_structs::{BorrowPosition, NftAuction, PausableAction, Role, BP};
+use liquidity_pool::liquidity::ProxyTrait as _;
+
+#[multiversx_sc::module]
+pub trait NftLiquidationModule:
+    events::EventsModule
+    + router::RouterModule
+    + factory::FactoryModule
+    + proxy::ProxyModule
+    + common_checks::ChecksModule
+    + common_tokens::AccountTokenModule
+    + storage::LendingStorageModule
+    + utils::LendingUtilsModule
+    + math::LendingMathModule
+    + price_aggregator_proxy::PriceAggregatorModule
+    + common_permissions::PermissionsModule
+    + common_pause::PauseModule
+{
+    #[endpoint(setNftAuctionParams)]
+    fn set_nft_auction_params(
+        &self,
+        duration: u64,
+        start_price_ratio: BigUint,
+        end_price_ratio: BigUint,
+    ) {
+        self.require_role(Role::RiskAdmin);
+
+        require!(duration > 0, "auction duration can not be zero");
+        require!(
+            start_price_ratio >= end_price_ratio,
+            "start price ratio must be greater than end price ratio"
+        );
+
+        self.nft_auction_duration().set(duration);
+        self.nft_auction_start_price_ratio().set(&start_price_ratio);
+        self.nft_auction_end_price_ratio().set(&end_price_ratio);
+    }
+
+    // Puts the NFT locked by an underwater debt NFT up for a Dutch auction
+    #[endpoint(startNftAuction)]
+    fn start_nft_auction(&self, debt_nft_nonce: u64) {
+        self.require_not_paused(PausableAction::Liquidate);
+
+        require!(
+            !self.nft_borrow_positions(debt_nft_nonce).is_empty(),
+            "no NFT loan for this debt token"
+        );
+        require!(
+            self.nft_auctions(debt_nft_nonce).is_empty(),
+            "auction already started"
+        );
+        require!(
+            !self.nft_auction_duration().is_empty(),
+            "NFT auction parameters are not set"
+        );
+
+        let borrow_position = self.get_nft_borrow_position_with_debt(debt_nft_nonce);
+        let nft = match &borrow_position.nft {
+            Some(nft) => nft,
+            None => sc_panic!("NFT collateral already liquidated"),
+        };
+        self.update_collection_floor(&nft.token_identifier);
+        self.update_nft_loan_price_rounds(&borrow_position.token_id);
+        require!(
+            self.compute_nft_loan_health_factor(&borrow_position) < BP,
+            "health not low enough for liquidation"
+        );
+
+        let collateral_value = self.get_nft_collateral_value_in_tokens(&borrow_position);
+        let start_timestamp = self.blockchain().get_block_timestamp();
+        let auction = NftAuction::new(
+            borrow_position.token_id,
+            &collateral_value * &self.nft_auction_start_price_ratio().get() / BP,
+            collateral_value * self.nft_auction_end_price_ratio().get() / BP,
+            start_timestamp,
+            start_timestamp + self.nft_auction_duration().get(),
+        );
+
+        self.nft_auction_started_event(
+            debt_nft_nonce,
+            &auction.token_id,
+            &auction.start_price,
+            &auction.end_price,
+            auction.end_timestamp,
+        );
+        self.nft_auctions(debt_nft_nonce).set(auction);
+    }
+
+    // Buys the auctioned NFT at the current price; proceeds repay the liquidity pool
+    #[payable("*")]
+    #[endpoint(bidNftAuction)]
+    fn bid_nft_auction(&self, debt_nft_nonce: u64) -> EsdtTokenPayment {
+        self.require_not_paused(PausableAction::Liquidate);
+
+        let (bid_token_id, bid_amount) = self.call_value().single_fungible_esdt();
+        let bidder = self.blockchain().get_caller();
+
+        require!(
+            !self.nft_auctions(debt_nft_nonce).is_empty(),
+            "no auction for this debt token"
+        );
+
+        let auction = self.nft_auctions(debt_nft_nonce).take();
+        let price = auction.get_price(self.blockchain().get_block_timestamp());
+        require!(bid_token_id == auction.token_id, "invalid bid token");
+        require!(bid_amount >= price, "bid too low");
+
+        if bid_amount > price {
+            self.send()
+                .direct_esdt(&bidder, &bid_token_id, 0, &(&bid_amount - &price));
+        }
+
+        let borrow_position = self.nft_borrow_positions(debt_nft_nonce).get();
+        let collateral_nft = borrow_position.nft.clone().unwrap();
+        self.decrease_collection_borrowed_amount(
+            &collateral_nft.token_identifier,
+            &borrow_position.token_id,
+            &borrow_position.amount,
+        );
+        let debt_nft = EsdtTokenPayment::new(
+            self.debt_nft_token().get_token_id(),
+            debt_nft_nonce,
+            BigUint::from(1u32),
+        );
+        let mut positions = MultiValueEncoded::new();
+        positions.push(MultiValue2::from((debt_nft, borrow_position)));
+
+        // The pool sends back to this contract whatever exceeds the debt
+        let sc_address = self.blockchain().get_sc_address();
+        let token_identifier = EgldOrEsdtTokenIdentifier::esdt(bid_token_id.clone());
+        let balance_before = self.blockchain().get_sc_balance(&token_identifier, 0);
+        let pool_address = self.get_pool_address(&bid_token_id);
+        let processed_positions: MultiValueEncoded<
+            MultiValue2<EsdtTokenPayment<Self::Api>, BorrowPosition<Self::Api>>,
+        > = self
+            .liquidity_pool_proxy(pool_address)
+            .repay_nfts(&sc_address, positions)
+            .with_esdt_transfer((bid_token_id, 0, price.clone()))
+            .execute_on_dest_context();
+        let surplus =
+            self.blockchain().get_sc_balance(&token_identifier, 0) + &price - balance_before;
+
+        for position in processed_positions.into_iter() {
+            let (_, mut remaining_position) = position.into_tuple();
+            if remaining_position.amount == 0 {
+                // Kept until the debt NFT holder claims the surplus
+                remaining_position.nft = None;
+                self.nft_borrow_positions(debt_nft_nonce)
+                    .set(remaining_position);
+                continue;
+            }
+
+            // Any debt left after the auction is no longer backed by collateral
+            let (bad_debt, covered_amount) = self
+                .liquidity_pool_proxy(self.get_pool_address(&remaining_position.token_id))
+                .write_off_bad_debt(remaining_position)
+                .execute_on_dest_context::<MultiValue2<BigUint, BigUint>>()
+                .into_tuple();
+            self.nft_bad_debt_written_off_event(
+                debt_nft_nonce,
+                &auction.token_id,
+                &bad_debt,
+                &covered_amount,
+            );
+            self.nft_borrow_positions(debt_nft_nonce).clear();
+        }
+        self.nft_auction_surplus(debt_nft_nonce).set(&surplus);
+        self.nft_auction_bid_event(&bidder, debt_nft_nonce, &price, &surplus);
+
+        self.send().direct_esdt(
+            &bidder,
+            &collateral_nft.token_identifier,
+            collateral_nft.token_nonce,
+            &collateral_nft.amount,
+        );
+
+        collateral_nft
+    }
+
+    // The debt NFT holder burns it to collect what the auction raised above the debt
+    #[payable("*")]
+    #[endpoint(claimNftAuctionSurplus)]
+    fn claim_nft_auction_surplus(&self) -> BigUint {
+        let (debt_token_id, debt_nft_nonce, debt_amount) =
+            self.call_value().single_esdt().into_tuple();
+        let caller = self.blockchain().get_caller();
+
+        self.debt_nft_token().require_same_token(&debt_token_id);
+        require!(
+            !self.nft_borrow_positions(debt_nft_nonce).is_empty(),
+            "no NFT loan for this debt token"
+        );
+
+        let borrow_position = self.nft_borrow_positions(debt_nft_nonce).get();
+        require!(
+            borrow_position.nft.is_none(),
+            "NFT collateral was not liquidated"
+        );
+        require!(borrow_position.amount == 0, "debt not fully covered");
+
+        let surplus = self.nft_auction_surplus(debt_nft_nonce).take();
+        self.nft_borrow_positions(debt_nft_nonce).clear();
+        self.debt_nft_token().nft_burn(debt_nft_nonce, &debt_amount);
+
+        if surplus > 0 {
+            self.send()
+                .direct_esdt(&caller, &borrow_position.token_id, 0, &surplus);
+        }
+
+        surplus
+    }
+
+    #[view(getNftAuctionPrice)]
+    fn get_nft_auction_price(&self, debt_nft_nonce: u64) -> BigUint {
+        require!(
+            !self.nft_auctions(debt_nft_nonce).is_empty(),
+            "no auction for this debt token"
+        );
+
+        self.nft_auctions(debt_nft_nonce)
+            .get()
+            .get_price(self.blockchain().get_block_timestamp())
+    }
+
+    #[view(getNftLoanHealthFactor)]
+    fn get_nft_loan_health_factor(&self, debt_nft_nonce: u64) -> BigUint {
+        require!(
+            !self.nft_borrow_positions(debt_nft_nonce).is_empty(),
+            "no NFT loan for this debt token"
+        );
+
+        let borrow_position = self.nft_borrow_positions(debt_nft_nonce).get();
+        self.compute_nft_loan_health_factor(&borrow_position)
+    }
+
+    // The stored position is left untouched, the pool accrues interest again on repayment
+    fn get_nft_borrow_position_with_debt(&self, debt_nft_nonce: u64) -> BorrowPosition<Self::Api> {
+        let borrow_position = self.nft_borrow_positions(debt_nft_nonce).get();
+        let pool_address = self.get_pool_address(&borrow_position.token_id);
+
+        self.liquidity_pool_proxy(pool_address)
+            .update_borrows_with_debt(borrow_position)
+            .execute_on_dest_context()
+    }
+
+    // Collection floor weighted by the collection's liquidation threshold, against the debt,
+    // both in the borrowed token
+    fn compute_nft_loan_health_factor(
+        &self,
+        borrow_position: &BorrowPosition<Self::Api>,
+    ) -> BigUint {
+        let nft = match &borrow_position.nft {
+            Some(nft) => nft,
+            None => return BigUint::zero(),
+        };
+
+        let collection_params = self.collection_params(&nft.token_identifier).get();
+        let weighted_collateral_in_tokens = self
+            .get_nft_collateral_value_in_tokens(borrow_position)
+            * collection_params.liquidation_threshold
+            / BP;
+
+        self.compute_health_factor(&weighted_collateral_in_tokens, &borrow_position.amount)
+    }
+
+    // Market value of the locked NFT expressed in the borrowed token
+    fn get_nft_collateral_value_in_tokens(
+        &self,
+        borrow_position: &BorrowPosition<Self::Api>,
+    ) -> BigUint {
+        let nft = borrow_position.nft.as_ref().unwrap();
+        let floor = self.get_collection_floor(&nft.token_identifier);
+
+        self.convert_egld_to_tokens(&(floor * &nft.amount), &borrow_position.token_id)
+    }
+
+    #[view(getNftAuction)]
+    #[storage_mapper("nft_auctions")]
+    fn nft_auctions(&self, debt_nft_nonce: u64) -> SingleValueMapper<NftAuction<Self::Api>>;
+
+    #[view(getNftAuctionSurplus)]
+    #[storage_mapper("nft_auction_surplus")]
+    fn nft_auction_surplus(&self, debt_nft_nonce: u64) -> SingleValueMapper<BigUint>;
+
+    #[view(getNftAuctionDuration)]
+    #[storage_mapper("nft_auction_duration")]
+    fn nft_auction_duration(&self) -> SingleValueMapper<u64>;
+
+    #[view(getNftAuctionStartPriceRatio)]
+    #[storage_mapper("nft_auction_start_price_ratio")]
+    fn nft_auction_start_price_ratio(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getNftAuctionEndPriceRatio)]
+    #[storage_mapper("nft_auction_end_price_ratio")]
+    fn nft_auction_end_price_ratio(&self) -> SingleValueMapper<BigUint>;
+}
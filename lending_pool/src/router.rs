@@ -3,33 +3,48 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
-use crate::storage;
+use crate::{events, storage};
 
 use super::factory;
 use super::proxy;
-use common_structs::CollectionParams;
+use common_structs::{
+    CollectionOracleParams, CollectionParams, EModeCategory, InterestRateStrategy, PausableAction,
+    Role, BP,
+};
 
+use common_pause::ProxyTrait as _;
+use liquidity_pool::liquidity::ProxyTrait as _;
 use price_aggregator_proxy::ProxyTrait as _;
 
+// ESDT tokens have at most 18 decimals; zero can't be told apart from an unset entry
+pub const MAX_ASSET_DECIMALS: u8 = 18;
+
 #[multiversx_sc::module]
 pub trait RouterModule:
-    proxy::ProxyModule
+    events::EventsModule
+    + proxy::ProxyModule
     + factory::FactoryModule
     + common_checks::ChecksModule
+    + common_permissions::PermissionsModule
+    + common_pause::PauseModule
     + storage::LendingStorageModule
 {
-    #[only_owner]
+    // Without a rate strategy the pool follows the jump-rate model of the given slopes
     #[endpoint(createLiquidityPool)]
     fn create_liquidity_pool(
         &self,
         base_asset: TokenIdentifier,
+        decimals: u8,
         r_base: BigUint,
         r_slope1: BigUint,
         r_slope2: BigUint,
         u_optimal: BigUint,
         reserve_factor: BigUint,
         liquidation_threshold: BigUint,
+        rate_strategy: OptionalValue<InterestRateStrategy<Self::Api>>,
     ) -> ManagedAddress {
+        self.require_role(Role::ListingAdmin);
+
         require!(
             !self.pools_map().contains_key(&base_asset),
             "asset already supported"
@@ -38,6 +53,11 @@ pub trait RouterModule:
             base_asset.is_valid_esdt_identifier(),
             "invalid ticker provided"
         );
+        self.require_valid_asset_decimals(decimals);
+        require!(
+            liquidation_threshold <= BP,
+            "liquidation threshold can not exceed 100%"
+        );
 
         let address = self.create_pool(
             base_asset.clone(),
@@ -46,16 +66,47 @@ pub trait RouterModule:
             r_slope2,
             u_optimal,
             reserve_factor,
-            liquidation_threshold,
+            liquidation_threshold.clone(),
+            rate_strategy,
         );
 
         self.require_non_zero_address(&address);
 
-        self.pools_map().insert(base_asset, address.clone());
+        self.asset_decimals(&base_asset).set(decimals);
+        self.asset_liquidation_threshold(&base_asset)
+            .set(&liquidation_threshold);
+        self.pools_map().insert(base_asset.clone(), address.clone());
         self.pools_allowed().insert(address.clone());
+
+        self.liquidity_pool_created_event(&base_asset, &address);
         address
     }
 
+    // Pools created before decimals were recorded need them set once
+    #[endpoint(setAssetDecimals)]
+    fn set_asset_decimals(&self, asset: TokenIdentifier, decimals: u8) {
+        self.require_role(Role::ListingAdmin);
+
+        require!(
+            self.pools_map().contains_key(&asset),
+            "no pool found for this asset"
+        );
+        require!(
+            self.asset_decimals(&asset).is_empty(),
+            "asset decimals already set"
+        );
+        self.require_valid_asset_decimals(decimals);
+
+        self.asset_decimals(&asset).set(decimals);
+    }
+
+    fn require_valid_asset_decimals(&self, decimals: u8) {
+        require!(
+            decimals > 0 && decimals <= MAX_ASSET_DECIMALS,
+            "invalid asset decimals"
+        );
+    }
+
     #[only_owner]
     #[endpoint(upgradeLiquidityPool)]
     fn upgrade_liquidity_pool(
@@ -67,6 +118,32 @@ pub trait RouterModule:
         u_optimal: BigUint,
         reserve_factor: BigUint,
         liquidation_threshold: BigUint,
+        rate_strategy: OptionalValue<InterestRateStrategy<Self::Api>>,
+    ) {
+        self.require_not_timelocked();
+
+        self.update_liquidity_pool(
+            base_asset,
+            r_base,
+            r_slope1,
+            r_slope2,
+            u_optimal,
+            reserve_factor,
+            liquidation_threshold,
+            rate_strategy,
+        );
+    }
+
+    fn update_liquidity_pool(
+        &self,
+        base_asset: TokenIdentifier,
+        r_base: BigUint,
+        r_slope1: BigUint,
+        r_slope2: BigUint,
+        u_optimal: BigUint,
+        reserve_factor: BigUint,
+        liquidation_threshold: BigUint,
+        rate_strategy: OptionalValue<InterestRateStrategy<Self::Api>>,
     ) {
         require!(
             self.pools_map().contains_key(&base_asset),
@@ -76,19 +153,29 @@ pub trait RouterModule:
         let pool_address = self.get_pool_address(&base_asset);
         self.upgrade_pool(
             pool_address,
-            base_asset,
+            base_asset.clone(),
             r_base,
             r_slope1,
             r_slope2,
             u_optimal,
             reserve_factor,
-            liquidation_threshold,
+            liquidation_threshold.clone(),
+            rate_strategy,
         );
+        self.asset_liquidation_threshold(&base_asset)
+            .set(&liquidation_threshold);
+        self.emit_asset_risk_params_event(&base_asset);
     }
 
-    #[only_owner]
     #[endpoint(setAggregator)]
     fn set_aggregator(&self, pool_asset_id: TokenIdentifier, aggregator: ManagedAddress) {
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.update_aggregator(pool_asset_id, aggregator);
+    }
+
+    fn update_aggregator(&self, pool_asset_id: TokenIdentifier, aggregator: ManagedAddress) {
         let pool_address = self.get_pool_address(&pool_asset_id);
 
         let _: IgnoreValue = self
@@ -98,24 +185,369 @@ pub trait RouterModule:
     }
 
     #[only_owner]
+    #[endpoint(setSafetyModule)]
+    fn set_safety_module(&self, pool_asset_id: TokenIdentifier, safety_module: ManagedAddress) {
+        let pool_address = self.get_pool_address(&pool_asset_id);
+
+        let _: IgnoreValue = self
+            .liquidity_pool_proxy(pool_address)
+            .set_safety_module(safety_module)
+            .execute_on_dest_context();
+    }
+
+    #[endpoint(setBadDebtDustThreshold)]
+    fn set_bad_debt_dust_threshold(&self, threshold: BigUint) {
+        self.require_role(Role::RiskAdmin);
+
+        self.bad_debt_dust_threshold().set(&threshold);
+    }
+
+    #[endpoint(setInterestApproximationTerms)]
+    fn set_interest_approximation_terms(&self, pool_asset_id: TokenIdentifier, terms: u32) {
+        self.require_role(Role::RiskAdmin);
+
+        let pool_address = self.get_pool_address(&pool_asset_id);
+
+        let _: IgnoreValue = self
+            .liquidity_pool_proxy(pool_address)
+            .set_interest_approximation_terms(terms)
+            .execute_on_dest_context();
+    }
+
+    #[only_owner]
+    #[endpoint(setTreasuryAddress)]
+    fn set_treasury_address(&self, treasury_address: ManagedAddress) {
+        self.require_non_zero_address(&treasury_address);
+
+        self.treasury_address().set(&treasury_address);
+    }
+
+    #[endpoint(sweepRevenueToTreasury)]
+    fn sweep_revenue_to_treasury(&self, pool_asset_id: TokenIdentifier) -> BigUint {
+        self.require_role(Role::Treasury);
+
+        require!(
+            !self.treasury_address().is_empty(),
+            "no treasury address set"
+        );
+
+        let pool_address = self.get_pool_address(&pool_asset_id);
+        self.liquidity_pool_proxy(pool_address)
+            .sweep_protocol_revenue(OptionalValue::Some(self.treasury_address().get()))
+            .execute_on_dest_context()
+    }
+
+    #[endpoint(sweepRevenueToSafetyModule)]
+    fn sweep_revenue_to_safety_module(&self, pool_asset_id: TokenIdentifier) -> BigUint {
+        self.require_role(Role::Treasury);
+
+        let pool_address = self.get_pool_address(&pool_asset_id);
+        self.liquidity_pool_proxy(pool_address)
+            .sweep_protocol_revenue(OptionalValue::<ManagedAddress>::None)
+            .execute_on_dest_context()
+    }
+
     #[endpoint(setAssetLoanToValue)]
     fn set_asset_loan_to_value(&self, asset: TokenIdentifier, loan_to_value: BigUint) {
-        self.asset_loan_to_value(&asset).set(&loan_to_value);
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.update_asset_loan_to_value(&asset, &loan_to_value);
+    }
+
+    fn update_asset_loan_to_value(&self, asset: &TokenIdentifier, loan_to_value: &BigUint) {
+        self.asset_loan_to_value(asset).set(loan_to_value);
+        self.emit_asset_risk_params_event(asset);
+    }
+
+    // Recorded here and enforced by the asset's pool; without a cap the asset is uncapped
+    #[endpoint(setAssetSupplyCap)]
+    fn set_asset_supply_cap(&self, asset: TokenIdentifier, cap: OptionalValue<BigUint>) {
+        self.require_role(Role::RiskAdmin);
+
+        let pool_address = self.get_pool_address(&asset);
+        match &cap {
+            OptionalValue::Some(cap) => self.asset_supply_cap(&asset).set(cap),
+            OptionalValue::None => self.asset_supply_cap(&asset).clear(),
+        }
+        self.emit_asset_caps_event(&asset);
+
+        let _: IgnoreValue = self
+            .liquidity_pool_proxy(pool_address)
+            .set_supply_cap(cap)
+            .execute_on_dest_context();
+    }
+
+    #[endpoint(setAssetBorrowCap)]
+    fn set_asset_borrow_cap(&self, asset: TokenIdentifier, cap: OptionalValue<BigUint>) {
+        self.require_role(Role::RiskAdmin);
+
+        let pool_address = self.get_pool_address(&asset);
+        match &cap {
+            OptionalValue::Some(cap) => self.asset_borrow_cap(&asset).set(cap),
+            OptionalValue::None => self.asset_borrow_cap(&asset).clear(),
+        }
+        self.emit_asset_caps_event(&asset);
+
+        let _: IgnoreValue = self
+            .liquidity_pool_proxy(pool_address)
+            .set_borrow_cap(cap)
+            .execute_on_dest_context();
     }
 
-    #[only_owner]
     #[endpoint(setAssetLiquidationBonus)]
     fn set_asset_liquidation_bonus(&self, asset: TokenIdentifier, liq_bonus: BigUint) {
-        self.asset_liquidation_bonus(&asset).set(&liq_bonus);
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.update_asset_liquidation_bonus(&asset, &liq_bonus);
+    }
+
+    fn update_asset_liquidation_bonus(&self, asset: &TokenIdentifier, liq_bonus: &BigUint) {
+        self.asset_liquidation_bonus(asset).set(liq_bonus);
+        self.emit_asset_risk_params_event(asset);
+    }
+
+    #[endpoint(setAssetLiquidationThreshold)]
+    fn set_asset_liquidation_threshold(&self, asset: TokenIdentifier, threshold: BigUint) {
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.update_asset_liquidation_threshold(&asset, &threshold);
+    }
+
+    fn update_asset_liquidation_threshold(&self, asset: &TokenIdentifier, threshold: &BigUint) {
+        require!(
+            *threshold <= BP,
+            "liquidation threshold can not exceed 100%"
+        );
+
+        self.asset_liquidation_threshold(asset).set(threshold);
+        self.emit_asset_risk_params_event(asset);
+    }
+
+    // Collateral in an isolated asset only backs whitelisted borrows, up to the isolation debt ceiling
+    #[endpoint(setAssetIsolated)]
+    fn set_asset_isolated(&self, asset: TokenIdentifier, isolated: bool) {
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.update_asset_isolated(&asset, isolated);
+    }
+
+    fn update_asset_isolated(&self, asset: &TokenIdentifier, isolated: bool) {
+        if isolated {
+            self.isolated_assets().insert(asset.clone());
+        } else {
+            self.isolated_assets().swap_remove(asset);
+        }
+        self.emit_asset_isolation_event(asset);
+    }
+
+    #[endpoint(setIsolationBorrowable)]
+    fn set_isolation_borrowable(&self, asset: TokenIdentifier, borrowable: bool) {
+        self.require_role(Role::RiskAdmin);
+
+        if borrowable {
+            self.isolation_borrowable_assets().insert(asset.clone());
+        } else {
+            self.isolation_borrowable_assets().swap_remove(&asset);
+        }
+        self.emit_asset_isolation_event(&asset);
+    }
+
+    #[endpoint(setIsolationDebtCeiling)]
+    fn set_isolation_debt_ceiling(&self, ceiling: BigUint) {
+        self.require_role(Role::RiskAdmin);
+
+        self.isolation_debt_ceiling().set(&ceiling);
+        self.isolation_debt_ceiling_event(&ceiling);
+    }
+
+    #[endpoint(setEModeCategory)]
+    fn set_e_mode_category(
+        &self,
+        category_id: u8,
+        loan_to_value: BigUint,
+        liquidation_threshold: BigUint,
+        liquidation_bonus: BigUint,
+        price_source: OptionalValue<ManagedBuffer>,
+    ) {
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.update_e_mode_category(
+            category_id,
+            loan_to_value,
+            liquidation_threshold,
+            liquidation_bonus,
+            price_source.into_option(),
+        );
+    }
+
+    fn update_e_mode_category(
+        &self,
+        category_id: u8,
+        loan_to_value: BigUint,
+        liquidation_threshold: BigUint,
+        liquidation_bonus: BigUint,
+        price_source: Option<ManagedBuffer>,
+    ) {
+        require!(category_id != 0, "invalid e-mode category");
+        require!(
+            liquidation_threshold <= BP,
+            "liquidation threshold can not exceed 100%"
+        );
+        require!(
+            loan_to_value <= liquidation_threshold,
+            "loan to value can not exceed the liquidation threshold"
+        );
+
+        let category = EModeCategory::new(
+            loan_to_value,
+            liquidation_threshold,
+            liquidation_bonus,
+            price_source,
+        );
+        self.e_mode_categories(category_id).set(&category);
+        self.e_mode_category_event(category_id, &category);
+    }
+
+    // A zero category takes the asset out of e-mode
+    #[endpoint(setAssetEModeCategory)]
+    fn set_asset_e_mode_category(&self, asset: TokenIdentifier, category_id: u8) {
+        self.require_role(Role::RiskAdmin);
+        self.require_not_timelocked();
+
+        self.update_asset_e_mode_category(&asset, category_id);
+    }
+
+    fn update_asset_e_mode_category(&self, asset: &TokenIdentifier, category_id: u8) {
+        require!(
+            category_id == 0 || !self.e_mode_categories(category_id).is_empty(),
+            "invalid e-mode category"
+        );
+
+        self.asset_e_mode_category(asset).set(category_id);
+        self.asset_e_mode_category_event(asset, category_id);
+    }
+
+    // Pauses the actions in the asset's pool only, all of them when none is given; liquidations
+    // also stop with repay paused on the debt asset
+    #[endpoint(pauseAsset)]
+    fn pause_asset(&self, asset: TokenIdentifier, actions: MultiValueEncoded<PausableAction>) {
+        self.require_role(Role::PauseGuardian);
+
+        let pool_address = self.get_pool_address(&asset);
+        let _: IgnoreValue = self
+            .liquidity_pool_proxy(pool_address)
+            .pause(actions)
+            .execute_on_dest_context();
     }
 
     #[only_owner]
+    #[endpoint(unpauseAsset)]
+    fn unpause_asset(&self, asset: TokenIdentifier, actions: MultiValueEncoded<PausableAction>) {
+        let pool_address = self.get_pool_address(&asset);
+        let _: IgnoreValue = self
+            .liquidity_pool_proxy(pool_address)
+            .unpause(actions)
+            .execute_on_dest_context();
+    }
+
     #[endpoint(addCollection)]
     fn add_collection(&self, params: &CollectionParams<Self::Api>) {
+        self.require_role(Role::ListingAdmin);
+
         self.collections().insert(params.token.clone());
         self.collection_params(&params.token).set(params);
     }
 
+    #[endpoint(setCollectionBorrowCap)]
+    fn set_collection_borrow_cap(
+        &self,
+        token: TokenIdentifier,
+        asset: TokenIdentifier,
+        cap: BigUint,
+    ) {
+        self.require_role(Role::RiskAdmin);
+
+        require!(
+            self.collections().contains(&token),
+            "Collection is not allowed as collateral"
+        );
+        require!(
+            self.pools_map().contains_key(&asset),
+            "no pool found for this asset"
+        );
+
+        self.collection_borrow_cap(&token, &asset).set(&cap);
+    }
+
+    // The floor set through addCollection stays as fallback when the oracle is stale or off
+    #[endpoint(setCollectionOracle)]
+    fn set_collection_oracle(
+        &self,
+        token: TokenIdentifier,
+        ticker: ManagedBuffer,
+        max_staleness: u64,
+        max_deviation: BigUint,
+    ) {
+        self.require_role(Role::RiskAdmin);
+
+        require!(
+            self.collections().contains(&token),
+            "Collection is not allowed as collateral"
+        );
+        require!(max_staleness > 0, "max staleness can not be zero");
+
+        self.collection_oracle_params(&token)
+            .set(CollectionOracleParams::new(
+                ticker,
+                max_staleness,
+                max_deviation,
+            ));
+        self.collection_last_floor(&token).clear();
+        self.collection_last_floor_timestamp(&token).clear();
+    }
+
+    #[endpoint(removeCollectionOracle)]
+    fn remove_collection_oracle(&self, token: TokenIdentifier) {
+        self.require_role(Role::RiskAdmin);
+
+        self.collection_oracle_params(&token).clear();
+        self.collection_last_floor(&token).clear();
+        self.collection_last_floor_timestamp(&token).clear();
+    }
+
+    // Parameter events carry the asset's whole current setting, not only the changed value
+    fn emit_asset_risk_params_event(&self, asset: &TokenIdentifier) {
+        self.asset_risk_params_event(
+            asset,
+            &self.asset_loan_to_value(asset).get(),
+            &self.asset_liquidation_threshold(asset).get(),
+            &self.asset_liquidation_bonus(asset).get(),
+        );
+    }
+
+    fn emit_asset_caps_event(&self, asset: &TokenIdentifier) {
+        let supply_cap_mapper = self.asset_supply_cap(asset);
+        let borrow_cap_mapper = self.asset_borrow_cap(asset);
+        self.asset_caps_event(
+            asset,
+            &(!supply_cap_mapper.is_empty()).then(|| supply_cap_mapper.get()),
+            &(!borrow_cap_mapper.is_empty()).then(|| borrow_cap_mapper.get()),
+        );
+    }
+
+    fn emit_asset_isolation_event(&self, asset: &TokenIdentifier) {
+        self.asset_isolation_event(
+            asset,
+            self.isolated_assets().contains(asset),
+            self.isolation_borrowable_assets().contains(asset),
+        );
+    }
+
     #[view(getPoolAddress)]
     fn get_pool_address(&self, asset: &TokenIdentifier) -> ManagedAddress {
         match self.pools_map().get(asset) {
@@ -154,10 +586,22 @@ pub trait RouterModule:
     #[storage_mapper("pool_allowed")]
     fn pools_allowed(&self) -> SetMapper<ManagedAddress>;
 
+    #[view(getTreasuryAddress)]
+    #[storage_mapper("treasury_address")]
+    fn treasury_address(&self) -> SingleValueMapper<ManagedAddress>;
+
     #[view(getAssetLoanToValue)]
     #[storage_mapper("asset_loan_to_value")]
     fn asset_loan_to_value(&self, asset: &TokenIdentifier) -> SingleValueMapper<BigUint>;
 
+    #[view(getAssetSupplyCap)]
+    #[storage_mapper("asset_supply_cap")]
+    fn asset_supply_cap(&self, asset: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    #[view(getAssetBorrowCap)]
+    #[storage_mapper("asset_borrow_cap")]
+    fn asset_borrow_cap(&self, asset: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
     #[view(getAssetLiquidationBonus)]
     #[storage_mapper("asset_liquidation_bonus")]
     fn asset_liquidation_bonus(&self, asset: &TokenIdentifier) -> SingleValueMapper<BigUint>;
@@ -1,6 +1,8 @@
 multiversx_sc::imports!();
 
-use common_structs::{BorrowPosition, CollectionParams, DepositPosition};
+use common_structs::{
+    BorrowPosition, CollectionOracleParams, CollectionParams, DepositPosition, EModeCategory,
+};
 
 #[multiversx_sc::module]
 pub trait LendingStorageModule {
@@ -18,10 +20,24 @@ pub trait LendingStorageModule {
         owner_nonce: u64,
     ) -> MapMapper<TokenIdentifier, BorrowPosition<Self::Api>>;
 
+    // Deposits of the account that earn interest without backing its borrows
+    #[view(getNonCollateralDeposits)]
+    #[storage_mapper("non_collateral_deposits")]
+    fn non_collateral_deposits(&self, owner_nonce: u64) -> UnorderedSetMapper<TokenIdentifier>;
+
     #[view(getNFTBorrowPositions)]
     #[storage_mapper("nft_borrow_positions")]
     fn nft_borrow_positions(&self, nft_nonce: u64) -> SingleValueMapper<BorrowPosition<Self::Api>>;
 
+    // Decimals of the asset's token, to convert amounts between assets
+    #[view(getAssetDecimals)]
+    #[storage_mapper("asset_decimals")]
+    fn asset_decimals(&self, asset: &TokenIdentifier) -> SingleValueMapper<u8>;
+
+    #[view(getAssetLiquidationThreshold)]
+    #[storage_mapper("asset_liquidation_threshold")]
+    fn asset_liquidation_threshold(&self, asset: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
     #[view(getCollections)]
     #[storage_mapper("collections")]
     fn collections(&self) -> UnorderedSetMapper<TokenIdentifier>;
@@ -32,4 +48,99 @@ pub trait LendingStorageModule {
         &self,
         token: &TokenIdentifier,
     ) -> SingleValueMapper<CollectionParams<Self::Api>>;
+
+    #[view(getCollectionOracleParams)]
+    #[storage_mapper("collection_oracle_params")]
+    fn collection_oracle_params(
+        &self,
+        token: &TokenIdentifier,
+    ) -> SingleValueMapper<CollectionOracleParams<Self::Api>>;
+
+    #[view(getCollectionLastFloor)]
+    #[storage_mapper("collection_last_floor")]
+    fn collection_last_floor(&self, token: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    #[view(getCollectionLastFloorTimestamp)]
+    #[storage_mapper("collection_last_floor_timestamp")]
+    fn collection_last_floor_timestamp(&self, token: &TokenIdentifier) -> SingleValueMapper<u64>;
+
+    // Caps and totals are kept per borrowed asset, in that asset's units
+    #[view(getCollectionBorrowCap)]
+    #[storage_mapper("collection_borrow_cap")]
+    fn collection_borrow_cap(
+        &self,
+        token: &TokenIdentifier,
+        asset: &TokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
+    #[view(getCollectionBorrowedAmount)]
+    #[storage_mapper("collection_borrowed_amount")]
+    fn collection_borrowed_amount(
+        &self,
+        token: &TokenIdentifier,
+        asset: &TokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
+    #[view(getIsolatedAssets)]
+    #[storage_mapper("isolated_assets")]
+    fn isolated_assets(&self) -> UnorderedSetMapper<TokenIdentifier>;
+
+    #[view(getIsolationBorrowableAssets)]
+    #[storage_mapper("isolation_borrowable_assets")]
+    fn isolation_borrowable_assets(&self) -> UnorderedSetMapper<TokenIdentifier>;
+
+    // Dollar value that isolated accounts may borrow in total, in the units of getTotalBorrowInDollars
+    #[view(getIsolationDebtCeiling)]
+    #[storage_mapper("isolation_debt_ceiling")]
+    fn isolation_debt_ceiling(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getIsolatedDebt)]
+    #[storage_mapper("isolated_debt")]
+    fn isolated_debt(&self) -> SingleValueMapper<BigUint>;
+
+    // Amount of the asset the account borrowed while isolated, and the dollar value it added to
+    // the isolated debt, released as that amount is repaid
+    #[view(getAccountIsolatedDebt)]
+    #[storage_mapper("account_isolated_debt")]
+    fn account_isolated_debt(
+        &self,
+        account_nonce: u64,
+        token_id: &TokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
+    #[view(getAccountIsolatedDebtInDollars)]
+    #[storage_mapper("account_isolated_debt_in_dollars")]
+    fn account_isolated_debt_in_dollars(
+        &self,
+        account_nonce: u64,
+        token_id: &TokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
+    // Accounts whose deposits are worth no more than this, in the units of
+    // getTotalCollateralInDollars, get their remaining debt written off
+    #[view(getBadDebtDustThreshold)]
+    #[storage_mapper("bad_debt_dust_threshold")]
+    fn bad_debt_dust_threshold(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getEModeCategory)]
+    #[storage_mapper("e_mode_categories")]
+    fn e_mode_categories(&self, category_id: u8) -> SingleValueMapper<EModeCategory<Self::Api>>;
+
+    // Zero when the asset is in no category
+    #[view(getAssetEModeCategory)]
+    #[storage_mapper("asset_e_mode_category")]
+    fn asset_e_mode_category(&self, asset: &TokenIdentifier) -> SingleValueMapper<u8>;
+
+    // Zero when the account has not opted into a category
+    #[view(getAccountEModeCategory)]
+    #[storage_mapper("account_e_mode_category")]
+    fn account_e_mode_category(&self, owner_nonce: u64) -> SingleValueMapper<u8>;
+
+    #[view(getLendToken)]
+    #[storage_mapper("lend_token")]
+    fn lend_token(&self, asset: &TokenIdentifier) -> FungibleTokenMapper<Self::Api>;
+
+    #[view(getLendTokenAsset)]
+    #[storage_mapper("lend_token_asset")]
+    fn lend_token_asset(&self, lend_token: &TokenIdentifier) -> SingleValueMapper<TokenIdentifier>;
 }
@@ -0,0 +1,185 @@
+#![allow(clippy::too_many_arguments)]
+
+multiversx_sc::imports!();
+
+use crate::{events, factory, proxy, router, storage};
+use common_structs::{QueuedChange, Role, TimelockedChange};
+
+pub const MAX_TIMELOCK_DELAY: u64 = 30 * 24 * 60 * 60;
+
+#[multiversx_sc::module]
+pub trait TimelockModule:
+    router::RouterModule
+    + events::EventsModule
+    + proxy::ProxyModule
+    + factory::FactoryModule
+    + common_checks::ChecksModule
+    + common_permissions::PermissionsModule
+    + common_pause::PauseModule
+    + storage::LendingStorageModule
+    + price_aggregator_proxy::PriceAggregatorModule
+{
+    // Raising the delay applies at once, lowering it has to wait out the current delay
+    #[only_owner]
+    #[endpoint(setTimelockDelay)]
+    fn set_timelock_delay(&self, delay: u64) {
+        require!(
+            delay >= self.timelock_delay().get(),
+            "the timelock delay can only be lowered through the timelock"
+        );
+
+        self.update_timelock_delay(delay);
+    }
+
+    #[endpoint(queueChange)]
+    fn queue_change(&self, change: TimelockedChange<Self::Api>) -> u64 {
+        self.require_change_role(&change);
+
+        let eta = self.blockchain().get_block_timestamp() + self.timelock_delay().get();
+        let change_id = self.last_change_id().update(|id| {
+            *id += 1;
+            *id
+        });
+
+        self.change_queued_event(change_id, eta, &change);
+        self.queued_changes()
+            .insert(change_id, QueuedChange { change, eta });
+
+        change_id
+    }
+
+    #[endpoint(executeChange)]
+    fn execute_change(&self, change_id: u64) {
+        let queued_change = self.get_queued_change(change_id);
+        self.require_change_role(&queued_change.change);
+        require!(
+            self.blockchain().get_block_timestamp() >= queued_change.eta,
+            "change is still timelocked"
+        );
+
+        self.queued_changes().remove(&change_id);
+        self.change_executed_event(change_id);
+        self.apply_change(queued_change.change);
+    }
+
+    #[endpoint(cancelChange)]
+    fn cancel_change(&self, change_id: u64) {
+        let queued_change = self.get_queued_change(change_id);
+        self.require_change_role(&queued_change.change);
+
+        self.queued_changes().remove(&change_id);
+        self.change_cancelled_event(change_id);
+    }
+
+    fn apply_change(&self, change: TimelockedChange<Self::Api>) {
+        match change {
+            TimelockedChange::AssetLoanToValue {
+                asset,
+                loan_to_value,
+            } => self.update_asset_loan_to_value(&asset, &loan_to_value),
+            TimelockedChange::AssetLiquidationThreshold { asset, threshold } => {
+                self.update_asset_liquidation_threshold(&asset, &threshold)
+            }
+            TimelockedChange::AssetLiquidationBonus {
+                asset,
+                liquidation_bonus,
+            } => self.update_asset_liquidation_bonus(&asset, &liquidation_bonus),
+            TimelockedChange::Aggregator { asset, aggregator } => {
+                self.update_aggregator(asset, aggregator)
+            }
+            TimelockedChange::PoolUpgrade {
+                asset,
+                r_base,
+                r_slope1,
+                r_slope2,
+                u_optimal,
+                reserve_factor,
+                liquidation_threshold,
+                rate_strategy,
+            } => self.update_liquidity_pool(
+                asset,
+                r_base,
+                r_slope1,
+                r_slope2,
+                u_optimal,
+                reserve_factor,
+                liquidation_threshold,
+                rate_strategy.into(),
+            ),
+            TimelockedChange::TimelockDelay { delay } => self.update_timelock_delay(delay),
+            TimelockedChange::PriceAggregatorAddress { address } => {
+                self.update_price_aggregator_address(address)
+            }
+            TimelockedChange::AddPriceAggregatorAddress { address } => {
+                self.insert_price_aggregator_address(address)
+            }
+            TimelockedChange::RemovePriceAggregatorAddress { address } => {
+                self.price_aggregator_addresses().swap_remove(&address);
+            }
+            TimelockedChange::FallbackPriceSource { address } => {
+                self.update_fallback_price_source(&address)
+            }
+            TimelockedChange::MaxPriceStaleness { max_staleness } => {
+                self.update_max_price_staleness(max_staleness)
+            }
+            TimelockedChange::MaxMissingPriceFeeds { max_missing_feeds } => {
+                self.max_missing_price_feeds().set(max_missing_feeds)
+            }
+            TimelockedChange::EModeCategory {
+                category_id,
+                loan_to_value,
+                liquidation_threshold,
+                liquidation_bonus,
+                price_source,
+            } => self.update_e_mode_category(
+                category_id,
+                loan_to_value,
+                liquidation_threshold,
+                liquidation_bonus,
+                price_source,
+            ),
+            TimelockedChange::AssetEModeCategory { asset, category_id } => {
+                self.update_asset_e_mode_category(&asset, category_id)
+            }
+            TimelockedChange::AssetIsolated { asset, isolated } => {
+                self.update_asset_isolated(&asset, isolated)
+            }
+        }
+    }
+
+    #[view(getTimelockDelay)]
+    fn get_timelock_delay(&self) -> u64 {
+        self.timelock_delay().get()
+    }
+
+    fn update_timelock_delay(&self, delay: u64) {
+        require!(delay <= MAX_TIMELOCK_DELAY, "timelock delay too long");
+
+        self.timelock_delay().set(delay);
+        self.timelock_delay_event(delay);
+    }
+
+    // Changes need the same caller as their direct endpoint
+    fn require_change_role(&self, change: &TimelockedChange<Self::Api>) {
+        match change {
+            TimelockedChange::PoolUpgrade { .. } | TimelockedChange::TimelockDelay { .. } => {
+                self.blockchain().check_caller_is_owner()
+            }
+            _ => self.require_role(Role::RiskAdmin),
+        }
+    }
+
+    fn get_queued_change(&self, change_id: u64) -> QueuedChange<Self::Api> {
+        match self.queued_changes().get(&change_id) {
+            Some(queued_change) => queued_change,
+            None => sc_panic!("no queued change with this id"),
+        }
+    }
+
+    #[view(getQueuedChanges)]
+    #[storage_mapper("queued_changes")]
+    fn queued_changes(&self) -> MapMapper<u64, QueuedChange<Self::Api>>;
+
+    #[storage_mapper("last_change_id")]
+    fn last_change_id(&self) -> SingleValueMapper<u64>;
+}
@@ -1,19 +1,24 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
-use crate::{math, storage};
+use crate::{events, factory, math, proxy, router, storage};
 
 use common_structs::*;
-use price_aggregator_proxy::AggregatorResult;
-
-const TOKEN_ID_SUFFIX_LEN: usize = 7; // "dash" + 6 random bytes
-const DOLLAR_TICKER: &[u8] = b"USD";
+use liquidity_pool::liq_utils::ProxyTrait as _;
+use price_aggregator_proxy::{AggregatorResult, DOLLAR_TICKER};
 
 #[multiversx_sc::module]
 pub trait LendingUtilsModule:
-    math::LendingMathModule
+    events::EventsModule
+    + math::LendingMathModule
+    + router::RouterModule
+    + proxy::ProxyModule
+    + factory::FactoryModule
     + storage::LendingStorageModule
     + price_aggregator_proxy::PriceAggregatorModule
+    + common_checks::ChecksModule
+    + common_permissions::PermissionsModule
+    + common_pause::PauseModule
 {
     fn get_token_price_data(&self, token_id: &TokenIdentifier) -> AggregatorResult<Self::Api> {
         let from_ticker = self.get_token_ticker(token_id);
@@ -22,10 +27,92 @@ pub trait LendingUtilsModule:
 
         match result {
             Some(r) => r,
-            None => sc_panic!("failed to get token price"),
+            None => sc_panic!("price feed unavailable or stale"),
         }
     }
 
+    // Assets of the account's e-mode category follow the category price source when it has one
+    fn get_account_token_price_data(
+        &self,
+        account_position: u64,
+        token_id: &TokenIdentifier,
+    ) -> AggregatorResult<Self::Api> {
+        let price_source = match self.get_e_mode_category_for_asset(account_position, token_id) {
+            Some(EModeCategory {
+                price_source: Some(ticker),
+                ..
+            }) => ticker,
+            _ => return self.get_token_price_data(token_id),
+        };
+
+        match self
+            .get_full_result_for_pair(price_source, ManagedBuffer::new_from_bytes(DOLLAR_TICKER))
+        {
+            Some(r) => r,
+            None => sc_panic!("price feed unavailable or stale"),
+        }
+    }
+
+    // Records the oracle rounds the account's positions are priced at, see update_price_rounds
+    fn update_account_price_rounds(&self, account_position: u64) {
+        for token_id in self.deposit_positions(account_position).keys() {
+            self.update_account_token_price_rounds(account_position, &token_id);
+        }
+        for token_id in self.borrow_positions(account_position).keys() {
+            self.update_account_token_price_rounds(account_position, &token_id);
+        }
+    }
+
+    fn update_account_token_price_rounds(&self, account_position: u64, token_id: &TokenIdentifier) {
+        let from_ticker = match self.get_e_mode_category_for_asset(account_position, token_id) {
+            Some(EModeCategory {
+                price_source: Some(ticker),
+                ..
+            }) => ticker,
+            _ => self.get_token_ticker(token_id),
+        };
+
+        self.update_price_rounds(from_ticker, ManagedBuffer::new_from_bytes(DOLLAR_TICKER));
+    }
+
+    // NFT loans are priced through EGLD and the borrowed token
+    fn update_nft_loan_price_rounds(&self, token_id: &TokenIdentifier) {
+        self.update_price_rounds(
+            ManagedBuffer::new_from_bytes(EgldOrEsdtTokenIdentifier::EGLD_REPRESENTATION),
+            ManagedBuffer::new_from_bytes(DOLLAR_TICKER),
+        );
+        self.update_price_rounds(
+            self.get_token_ticker(token_id),
+            ManagedBuffer::new_from_bytes(DOLLAR_TICKER),
+        );
+    }
+
+    fn get_account_e_mode_category(
+        &self,
+        account_position: u64,
+    ) -> Option<EModeCategory<Self::Api>> {
+        let category_id = self.account_e_mode_category(account_position).get();
+        if category_id == 0 {
+            return None;
+        }
+
+        Some(self.e_mode_categories(category_id).get())
+    }
+
+    // The account's category, when the asset belongs to it
+    fn get_e_mode_category_for_asset(
+        &self,
+        account_position: u64,
+        token_id: &TokenIdentifier,
+    ) -> Option<EModeCategory<Self::Api>> {
+        let category_id = self.account_e_mode_category(account_position).get();
+        if category_id == 0 || self.asset_e_mode_category(token_id).get() != category_id {
+            return None;
+        }
+
+        Some(self.e_mode_categories(category_id).get())
+    }
+
     fn get_egld_price_data(&self) -> AggregatorResult<Self::Api> {
         let result = self.get_full_result_for_pair(
             ManagedBuffer::new_from_bytes(EgldOrEsdtTokenIdentifier::EGLD_REPRESENTATION),
@@ -34,21 +121,100 @@ pub trait LendingUtilsModule:
 
         match result {
             Some(r) => r,
-            None => sc_panic!("failed to get token price"),
+            None => sc_panic!("price feed unavailable or stale"),
         }
     }
 
-    fn get_token_ticker(&self, token_id: &TokenIdentifier) -> ManagedBuffer {
-        let as_buffer = token_id.clone().into_managed_buffer();
-        let ticker_start_index = 0;
-        let ticker_end_index = as_buffer.len() - TOKEN_ID_SUFFIX_LEN;
+    // Collection floor in EGLD, read from the collection/EGLD feed when the oracle floor is
+    // accepted, else the owner-set floor
+    #[view(getCollectionFloor)]
+    fn get_collection_floor(&self, token_id: &TokenIdentifier) -> BigUint {
+        match self.get_accepted_oracle_floor(token_id) {
+            Some(floor) => floor,
+            None => self.collection_params(token_id).get().floor,
+        }
+    }
 
-        let result = as_buffer.copy_slice(ticker_start_index, ticker_end_index);
+    // Keeps the accepted oracle floor as the reference for the deviation bound
+    fn update_collection_floor(&self, token_id: &TokenIdentifier) {
+        if !self.collection_oracle_params(token_id).is_empty() {
+            self.update_price_rounds(
+                self.collection_oracle_params(token_id).get().ticker,
+                ManagedBuffer::new_from_bytes(EgldOrEsdtTokenIdentifier::EGLD_REPRESENTATION),
+            );
+        }
+        if let Some(floor) = self.get_accepted_oracle_floor(token_id) {
+            self.collection_last_floor(token_id).set(&floor);
+            self.collection_last_floor_timestamp(token_id)
+                .set(self.blockchain().get_block_timestamp());
+        }
+    }
 
-        match result {
-            Some(r) => r,
-            None => sc_panic!("failed to get token ticker"),
+    // The feed is accepted when fresh and within the allowed deviation from the reference; the
+    // reference only holds for max_staleness seconds, so a lasting move is accepted once it expires
+    fn get_accepted_oracle_floor(&self, token_id: &TokenIdentifier) -> Option<BigUint> {
+        if self.collection_oracle_params(token_id).is_empty() {
+            return None;
         }
+
+        let oracle_params = self.collection_oracle_params(token_id).get();
+        let result = self.get_full_result_for_pair(
+            oracle_params.ticker,
+            ManagedBuffer::new_from_bytes(EgldOrEsdtTokenIdentifier::EGLD_REPRESENTATION),
+        )?;
+
+        let current_timestamp = self.blockchain().get_block_timestamp();
+        if result.timestamp + oracle_params.max_staleness < current_timestamp {
+            return None;
+        }
+
+        let floor = result.price * BigUint::from(10u64).pow(EGLD_DECIMALS)
+            / BigUint::from(10u64).pow(result.decimals as u32);
+        let last_floor_mapper = self.collection_last_floor(token_id);
+        let reference_expiry =
+            self.collection_last_floor_timestamp(token_id).get() + oracle_params.max_staleness;
+        if !last_floor_mapper.is_empty() && reference_expiry >= current_timestamp {
+            let last_floor = last_floor_mapper.get();
+            let difference = if floor > last_floor {
+                &floor - &last_floor
+            } else {
+                &last_floor - &floor
+            };
+            if difference * BP > oracle_params.max_deviation * last_floor {
+                return None;
+            }
+        }
+
+        Some(floor)
+    }
+
+    // One whole token of the asset, in its smallest units
+    fn get_asset_unit(&self, token_id: &TokenIdentifier) -> BigUint {
+        require!(
+            !self.asset_decimals(token_id).is_empty(),
+            "asset decimals not set"
+        );
+
+        BigUint::from(10u64).pow(self.asset_decimals(token_id).get() as u32)
+    }
+
+    // EGLD amounts, like collection floors, converted to the given token through their prices
+    fn convert_egld_to_tokens(&self, egld_amount: &BigUint, token_id: &TokenIdentifier) -> BigUint {
+        let egld_data = self.get_egld_price_data();
+        let token_data = self.get_token_price_data(token_id);
+
+        egld_amount * &egld_data.price * self.get_asset_unit(token_id)
+            / (token_data.price * BigUint::from(10u64).pow(EGLD_DECIMALS))
+    }
+
+    fn get_liquidation_threshold_non_zero(&self, token_id: &TokenIdentifier) -> BigUint {
+        let liquidation_threshold = self.asset_liquidation_threshold(token_id).get();
+        require!(
+            liquidation_threshold > 0,
+            "no liquidation_threshold present for asset"
+        );
+
+        liquidation_threshold
     }
 
     // Returns the collateral position for the user or a new DepositPosition if the user didn't add collateral previously
@@ -67,7 +233,7 @@ pub trait LendingUtilsModule:
                 BigUint::zero(),
                 account_position,
                 self.blockchain().get_block_round(),
-                BigUint::from(BP),
+                Ray::zero(),
             ),
         }
     }
@@ -84,7 +250,7 @@ pub trait LendingUtilsModule:
                 BigUint::zero(),
                 account_position,
                 self.blockchain().get_block_round(),
-                BigUint::from(BP),
+                Ray::zero(),
                 Option::None,
             ),
         }
@@ -98,53 +264,393 @@ pub trait LendingUtilsModule:
         token_id: TokenIdentifier,
     ) -> BigUint {
         match self.deposit_positions(account_position).get(&token_id) {
-            Some(dp) => dp.amount,
+            Some(dp) => self.get_current_deposit_amount(&dp, &mut ManagedVec::new()),
             None => BigUint::zero(),
         }
     }
 
+    #[view(isCollateralEnabled)]
+    fn is_collateral_enabled(&self, account_position: u64, token_id: &TokenIdentifier) -> bool {
+        !self
+            .non_collateral_deposits(account_position)
+            .contains(token_id)
+    }
+
+    // Borrow and supply indexes of the asset's pool, with the interest accrued up to now; each
+    // pool is queried once and its indexes are kept in `pool_indexes` for the rest of the call
+    fn get_pool_indexes(
+        &self,
+        token_id: &TokenIdentifier,
+        pool_indexes: &mut ManagedVec<PoolIndexes<Self::Api>>,
+    ) -> PoolIndexes<Self::Api> {
+        for indexes in pool_indexes.iter() {
+            if &indexes.token_id == token_id {
+                return indexes;
+            }
+        }
+
+        let pool_address = self.get_pool_address(token_id);
+        let (borrow_index, supply_index) = self
+            .liquidity_pool_proxy(pool_address)
+            .get_current_indexes()
+            .execute_on_dest_context::<MultiValue2<Ray<Self::Api>, Ray<Self::Api>>>()
+            .into_tuple();
+
+        let indexes = PoolIndexes {
+            token_id: token_id.clone(),
+            borrow_index,
+            supply_index,
+        };
+        pool_indexes.push(indexes.clone());
+
+        indexes
+    }
+
+    // Stored amounts are only as recent as the last operation on the position, so positions
+    // are valued from their scaled amount at the current index
+    fn get_current_deposit_amount(
+        &self,
+        dp: &DepositPosition<Self::Api>,
+        pool_indexes: &mut ManagedVec<PoolIndexes<Self::Api>>,
+    ) -> BigUint {
+        let indexes = self.get_pool_indexes(&dp.token_id, pool_indexes);
+
+        dp.scaled_amount.mul(&indexes.supply_index).to_amount()
+    }
+
+    fn get_current_debt_amount(
+        &self,
+        bp: &BorrowPosition<Self::Api>,
+        pool_indexes: &mut ManagedVec<PoolIndexes<Self::Api>>,
+    ) -> BigUint {
+        let indexes = self.get_pool_indexes(&bp.token_id, pool_indexes);
+
+        bp.scaled_amount.mul(&indexes.borrow_index).to_amount()
+    }
+
     #[inline]
     #[view(getTotalCollateralAvailable)]
     fn get_total_collateral_in_dollars(&self, account_position: u64) -> BigUint {
         let mut deposited_amount_in_dollars = BigUint::zero();
         let deposit_positions = self.deposit_positions(account_position);
+        let mut pool_indexes = ManagedVec::new();
 
         for dp in deposit_positions.values() {
-            let dp_data = self.get_token_price_data(&dp.token_id);
-            deposited_amount_in_dollars += dp.amount * dp_data.price;
+            if !self.is_collateral_enabled(account_position, &dp.token_id) {
+                continue;
+            }
+
+            let dp_data = self.get_account_token_price_data(account_position, &dp.token_id);
+            deposited_amount_in_dollars +=
+                self.get_current_deposit_amount(&dp, &mut pool_indexes) * dp_data.price;
         }
 
         deposited_amount_in_dollars
     }
 
+    // Only collateral backs the debt; supply-only deposits can't be seized and stay with the account
+    fn is_bad_debt(&self, account_position: u64) -> bool {
+        !self.borrow_positions(account_position).is_empty()
+            && self.get_total_collateral_in_dollars(account_position)
+                <= self.bad_debt_dust_threshold().get()
+    }
+
+    // Sum of each collateral's value weighted by its own loan to value, or by the LTV of the
+    // account's e-mode category for the assets in it
+    #[view(getTotalBorrowableCollateralInDollars)]
+    fn get_total_borrowable_collateral_in_dollars(&self, account_position: u64) -> BigUint {
+        self.compute_borrowable_collateral_in_dollars(account_position, &mut ManagedVec::new())
+    }
+
+    fn compute_borrowable_collateral_in_dollars(
+        &self,
+        account_position: u64,
+        pool_indexes: &mut ManagedVec<PoolIndexes<Self::Api>>,
+    ) -> BigUint {
+        let mut borrowable_collateral_in_dollars = BigUint::zero();
+        let deposit_positions = self.deposit_positions(account_position);
+
+        for dp in deposit_positions.values() {
+            if !self.is_collateral_enabled(account_position, &dp.token_id) {
+                continue;
+            }
+
+            let dp_data = self.get_account_token_price_data(account_position, &dp.token_id);
+            let loan_to_value =
+                match self.get_e_mode_category_for_asset(account_position, &dp.token_id) {
+                    Some(category) => category.loan_to_value,
+                    None => self.get_loan_to_value_exists_and_non_zero(&dp.token_id),
+                };
+            borrowable_collateral_in_dollars +=
+                self.get_current_deposit_amount(&dp, pool_indexes) * dp_data.price * loan_to_value
+                    / BP;
+        }
+
+        borrowable_collateral_in_dollars
+    }
+
+    // Sum of each collateral's value weighted by its own liquidation threshold, or by the
+    // threshold of the account's e-mode category for the assets in it
+    #[view(getTotalWeightedCollateralInDollars)]
+    fn get_total_weighted_collateral_in_dollars(&self, account_position: u64) -> BigUint {
+        self.compute_weighted_collateral_in_dollars(account_position, &mut ManagedVec::new())
+    }
+
+    fn compute_weighted_collateral_in_dollars(
+        &self,
+        account_position: u64,
+        pool_indexes: &mut ManagedVec<PoolIndexes<Self::Api>>,
+    ) -> BigUint {
+        let mut weighted_collateral_in_dollars = BigUint::zero();
+        let deposit_positions = self.deposit_positions(account_position);
+
+        for dp in deposit_positions.values() {
+            if !self.is_collateral_enabled(account_position, &dp.token_id) {
+                continue;
+            }
+
+            let dp_data = self.get_account_token_price_data(account_position, &dp.token_id);
+            let liquidation_threshold =
+                match self.get_e_mode_category_for_asset(account_position, &dp.token_id) {
+                    Some(category) => category.liquidation_threshold,
+                    None => self.get_liquidation_threshold_non_zero(&dp.token_id),
+                };
+            weighted_collateral_in_dollars += self.get_current_deposit_amount(&dp, pool_indexes)
+                * dp_data.price
+                * liquidation_threshold
+                / BP;
+        }
+
+        weighted_collateral_in_dollars
+    }
+
+    #[view(getAccountHealthFactor)]
+    fn get_account_health_factor(&self, account_position: u64) -> BigUint {
+        let mut pool_indexes = ManagedVec::new();
+        let weighted_collateral_in_dollars =
+            self.compute_weighted_collateral_in_dollars(account_position, &mut pool_indexes);
+        let borrowed_value_in_dollars =
+            self.compute_borrow_in_dollars(account_position, &mut pool_indexes);
+
+        self.compute_health_factor(&weighted_collateral_in_dollars, &borrowed_value_in_dollars)
+    }
+
     #[view(getTotalBorrowInDollars)]
     fn get_total_borrow_in_dollars(&self, account_position: u64) -> BigUint {
+        self.compute_borrow_in_dollars(account_position, &mut ManagedVec::new())
+    }
+
+    fn compute_borrow_in_dollars(
+        &self,
+        account_position: u64,
+        pool_indexes: &mut ManagedVec<PoolIndexes<Self::Api>>,
+    ) -> BigUint {
         let mut total_borrow_in_dollars = BigUint::zero();
         let borrow_positions = self.borrow_positions(account_position);
 
         for bp in borrow_positions.values() {
-            let bp_data = self.get_token_price_data(&bp.token_id);
-            total_borrow_in_dollars += bp.amount * bp_data.price;
+            let bp_data = self.get_account_token_price_data(account_position, &bp.token_id);
+            total_borrow_in_dollars +=
+                self.get_current_debt_amount(&bp, pool_indexes) * bp_data.price;
         }
 
         total_borrow_in_dollars
     }
 
-    fn compute_amount_in_tokens(
+    // Collateral tokens worth the repaid debt plus the liquidation bonus, and the repaid amount;
+    // when the deposit can't cover them, all of it is seized and the repaid amount shrinks to match
+    fn compute_amount_to_seize(
         &self,
         liquidatee_account_nonce: u64,
-        token_to_liquidate: TokenIdentifier,
-        amount_to_return_to_liquidator_in_dollars: BigUint,
-    ) -> BigUint {
+        debt_token_id: &TokenIdentifier,
+        repaid_amount: &BigUint,
+        collateral_token_id: &TokenIdentifier,
+        liq_bonus: &BigUint,
+    ) -> (BigUint, BigUint) {
+        let deposited_amount = match self
+            .deposit_positions(liquidatee_account_nonce)
+            .get(collateral_token_id)
+        {
+            Some(dp)
+                if self.is_collateral_enabled(liquidatee_account_nonce, collateral_token_id) =>
+            {
+                self.get_current_deposit_amount(&dp, &mut ManagedVec::new())
+            }
+            _ => sc_panic!("Liquidatee user doesn't have this token as collateral"),
+        };
+
+        let debt_data = self.get_account_token_price_data(liquidatee_account_nonce, debt_token_id);
+        let collateral_data =
+            self.get_account_token_price_data(liquidatee_account_nonce, collateral_token_id);
+
+        // Prices are per whole token, so amounts are scaled by each token's own decimals
+        let debt_unit = self.get_asset_unit(debt_token_id);
+        let collateral_unit = self.get_asset_unit(collateral_token_id);
+
+        let repaid_value_with_bonus = repaid_amount * &debt_data.price * (liq_bonus + BP) / BP;
+        let amount_to_seize =
+            repaid_value_with_bonus * &collateral_unit / (&collateral_data.price * &debt_unit);
+        if amount_to_seize <= deposited_amount {
+            return (amount_to_seize, repaid_amount.clone());
+        }
+
+        let capped_repaid_amount = &deposited_amount * &collateral_data.price * debt_unit * BP
+            / (debt_data.price * (liq_bonus + BP) * collateral_unit);
+        (deposited_amount, capped_repaid_amount)
+    }
+
+    // None when the collection has no aggregate cap and is only limited per NFT
+    #[view(getCollectionRemainingCapacity)]
+    fn get_collection_remaining_capacity(
+        &self,
+        collection: &TokenIdentifier,
+        asset: &TokenIdentifier,
+    ) -> OptionalValue<BigUint> {
+        if self.collection_borrow_cap(collection, asset).is_empty() {
+            return OptionalValue::None;
+        }
+
+        let cap = self.collection_borrow_cap(collection, asset).get();
+        let borrowed = self.collection_borrowed_amount(collection, asset).get();
+        if borrowed >= cap {
+            OptionalValue::Some(BigUint::zero())
+        } else {
+            OptionalValue::Some(cap - borrowed)
+        }
+    }
+
+    // An account is isolated while any of its enabled collateral is an isolated asset
+    #[view(isAccountIsolated)]
+    fn is_account_isolated(&self, account_position: u64) -> bool {
+        self.deposit_positions(account_position)
+            .keys()
+            .any(|token_id| {
+                self.isolated_assets().contains(&token_id)
+                    && self.is_collateral_enabled(account_position, &token_id)
+            })
+    }
+
+    #[view(getIsolationRemainingDebt)]
+    fn get_isolation_remaining_debt(&self) -> BigUint {
+        let ceiling = self.isolation_debt_ceiling().get();
+        let debt = self.isolated_debt().get();
+        if debt >= ceiling {
+            BigUint::zero()
+        } else {
+            ceiling - debt
+        }
+    }
+
+    fn increase_isolated_debt(
+        &self,
+        account_position: u64,
+        token_id: &TokenIdentifier,
+        amount: &BigUint,
+        amount_in_dollars: &BigUint,
+    ) {
+        let debt = self.isolated_debt().update(|total| {
+            *total += amount_in_dollars;
+            total.clone()
+        });
         require!(
-            self.deposit_positions(liquidatee_account_nonce)
-                .contains_key(&token_to_liquidate),
-            "Liquidatee user doesn't have this token as collateral"
+            debt <= self.isolation_debt_ceiling().get(),
+            "isolation debt ceiling exceeded"
         );
 
-        let token_data = self.get_token_price_data(&token_to_liquidate);
-        (&amount_to_return_to_liquidator_in_dollars * BP / &token_data.price)
-            * BigUint::from(10u64).pow(token_data.decimals as u32)
-            / BP
+        self.account_isolated_debt(account_position, token_id)
+            .update(|total| *total += amount);
+        self.account_isolated_debt_in_dollars(account_position, token_id)
+            .update(|total| *total += amount_in_dollars);
+    }
+
+    // Releases the share of the ceiling the repaid principal took when it was borrowed, whether
+    // or not the account is still isolated; prices since then don't matter. As in the pool, the
+    // principal part is pro rata to the scaled debt cleared
+    fn decrease_isolated_debt(
+        &self,
+        account_position: u64,
+        token_id: &TokenIdentifier,
+        initial_scaled_amount: &Ray<Self::Api>,
+        remaining_scaled_amount: &Ray<Self::Api>,
+    ) {
+        let isolated_amount = self.account_isolated_debt(account_position, token_id).get();
+        if isolated_amount == 0 {
+            return;
+        }
+
+        let repaid_principal = if remaining_scaled_amount.is_zero() {
+            isolated_amount.clone()
+        } else {
+            &isolated_amount
+                * &initial_scaled_amount
+                    .saturating_sub(remaining_scaled_amount)
+                    .raw
+                / &initial_scaled_amount.raw
+        };
+
+        let isolated_in_dollars = self
+            .account_isolated_debt_in_dollars(account_position, token_id)
+            .get();
+        let released_in_dollars = if repaid_principal >= isolated_amount {
+            self.account_isolated_debt(account_position, token_id)
+                .clear();
+            self.account_isolated_debt_in_dollars(account_position, token_id)
+                .clear();
+            isolated_in_dollars
+        } else {
+            let released_in_dollars = &isolated_in_dollars * &repaid_principal / &isolated_amount;
+            self.account_isolated_debt(account_position, token_id)
+                .set(isolated_amount - repaid_principal);
+            self.account_isolated_debt_in_dollars(account_position, token_id)
+                .set(isolated_in_dollars - &released_in_dollars);
+            released_in_dollars
+        };
+
+        self.isolated_debt().update(|total| {
+            if *total > released_in_dollars {
+                *total -= released_in_dollars;
+            } else {
+                *total = BigUint::zero();
+            }
+        });
+    }
+
+    fn increase_collection_borrowed_amount(
+        &self,
+        collection: &TokenIdentifier,
+        asset: &TokenIdentifier,
+        amount: &BigUint,
+    ) {
+        let borrowed = self
+            .collection_borrowed_amount(collection, asset)
+            .update(|total| {
+                *total += amount;
+                total.clone()
+            });
+        if self.collection_borrow_cap(collection, asset).is_empty() {
+            return;
+        }
+
+        let cap = self.collection_borrow_cap(collection, asset).get();
+        require!(borrowed <= cap, "collection borrow cap exceeded");
+        if borrowed == cap {
+            self.collection_borrow_cap_reached_event(collection, asset, &cap);
+        }
+    }
+
+    fn decrease_collection_borrowed_amount(
+        &self,
+        collection: &TokenIdentifier,
+        asset: &TokenIdentifier,
+        amount: &BigUint,
+    ) {
+        self.collection_borrowed_amount(collection, asset)
+            .update(|total| {
+                if *total > *amount {
+                    *total -= amount;
+                } else {
+                    *total = BigUint::zero();
+                }
+            });
     }
 }
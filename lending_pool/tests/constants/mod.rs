@@ -11,6 +11,7 @@ pub const EGLD_PRICE_IN_DOLLARS: u64 = 20_000; // $200
 pub const EGLD_PRICE_DROPPED_IN_DOLLARS: u64 = 14_000; // $140
 pub const USDC_PRICE_IN_DOLLARS: u64 = 100; // $1
 pub const PRICE_DECIMALS: usize = 2;
+pub const TOKEN_DECIMALS: u8 = 18;
 pub const R_BASE: u64 = 0;
 pub const R_SLOPE1: u64 = 40_000_000;
 pub const R_SLOPE2: u64 = 1_000_000_000;
@@ -20,7 +21,9 @@ pub const LIQ_THRESOLD: u64 = 700_000_000;
 pub const ACCOUNT_TOKEN: &[u8] = b"LACC-abcdef";
 pub const ACCOUNT_TICKER: &[u8] = b"LACC";
 pub const DEBT_NFT_TOKEN: &[u8] = b"XDEBT-abcdef";
+pub const USDC_LEND_TOKEN: &[u8] = b"LUSDC-abcdef";
 pub const APE_TOKEN: &[u8] = b"APE-abcdef";
+pub const APE_TICKER: &[u8] = b"APE";
 pub const APE_LTV: u64 = 500_000_000; // 50%
 pub const COW_TOKEN: &[u8] = b"COW-abcdef";
 pub const COW_LTV: u64 = 600_000_000; // 50%
@@ -36,3 +39,8 @@ pub const LENDING_POOL_WASM_PATH: &'static str = "output/lending-pool.wasm";
 // liquidity pool constants
 
 pub const LIQUIDITY_POOL_WASM_PATH: &'static str = "../liquidity_pool/output/liquidity-pool.wasm";
+
+// safety module constants
+
+pub const SAFETY_MODULE_WASM_PATH: &str = "../safety_module/output/safety-module.wasm";
+pub const SLASHING_WINDOW: u64 = 24 * 60 * 60;
@@ -1,4 +1,6 @@
-use common_structs::CollectionParams;
+use aggregator_mock::PriceAggregatorMock;
+use common_permissions::PermissionsModule;
+use common_structs::{CollectionParams, Ray, Role, SECONDS_PER_YEAR};
 use lending_pool::{
     router::RouterModule, storage::LendingStorageModule, AccountTokenModule, BorrowPosition,
     DepositPosition, LendingPool, BP,
@@ -8,11 +10,11 @@ use liquidity_pool::{liq_storage::StorageModule, liquidity::LiquidityModule};
 use multiversx_sc::storage::mappers::StorageTokenWrapper;
 use multiversx_sc::types::{EsdtTokenPayment, ManagedVec};
 use multiversx_sc::{
-    codec::Empty,
+    codec::{multi_types::OptionalValue, Empty},
     types::{Address, BigUint, EsdtLocalRole},
 };
 use multiversx_sc_scenario::{
-    managed_address, managed_biguint, managed_token_id, rust_biguint,
+    managed_address, managed_biguint, managed_buffer, managed_token_id, rust_biguint,
     whitebox::{BlockchainStateWrapper, ContractObjWrapper},
     DebugApi,
 };
@@ -20,11 +22,12 @@ use price_aggregator_proxy::PriceAggregatorModule;
 
 use crate::constants::{
     APE_FLOOR, APE_LTV, APE_TOKEN, COW_FLOOR, COW_LTV, COW_TOKEN, DEBT_NFT_TOKEN, MAX_BORROW,
+    USDC_LEND_TOKEN,
 };
 use crate::{
     constants::{
         ACCOUNT_TOKEN, EGLD_TOKEN_ID, LIQ_THRESOLD, RESERVE_FACTOR, R_BASE, R_SLOPE1, R_SLOPE2,
-        USDC_TOKEN_ID, U_OPTIMAL,
+        TOKEN_DECIMALS, USDC_TOKEN_ID, U_OPTIMAL,
     },
     setup::*,
 };
@@ -52,6 +55,11 @@ where
         ContractObjWrapper<liquidity_pool::ContractObj<DebugApi>, LiquidityPoolObjBuilder>,
 }
 
+// Scaled balance of a position that entered with `amount` at the BP-scaled `index`
+pub fn scaled_amount(amount: u64, index: u64) -> Ray<DebugApi> {
+    Ray::from_amount(&managed_biguint!(amount)).div(&Ray::from_bp(&managed_biguint!(index)))
+}
+
 impl<LendingPoolObjBuilder, LiquidityPoolObjBuilder, PriceAggregatorObjBuilder>
     LendingSetup<LendingPoolObjBuilder, LiquidityPoolObjBuilder, PriceAggregatorObjBuilder>
 where
@@ -106,7 +114,11 @@ where
                         managed_biguint!(U_OPTIMAL),
                         managed_biguint!(RESERVE_FACTOR),
                         managed_biguint!(LIQ_THRESOLD),
+                        OptionalValue::None,
                     );
+                    // The pool belongs to the lending pool, the test owner configures it directly
+                    sc.grant_role(Role::RiskAdmin, managed_address!(&owner_addr));
+                    sc.set_lend_token(managed_token_id!(USDC_LEND_TOKEN));
                     sc.set_price_aggregator_address(managed_address!(
                         &price_aggregator_wrapper.address_ref()
                     ));
@@ -126,12 +138,22 @@ where
                     );
                     sc.debt_nft_token()
                         .set_if_empty(managed_token_id!(DEBT_NFT_TOKEN));
+                    sc.lend_token(&managed_token_id!(USDC_TOKEN_ID))
+                        .set_token_id(managed_token_id!(USDC_LEND_TOKEN));
+                    sc.lend_token_asset(&managed_token_id!(USDC_LEND_TOKEN))
+                        .set(managed_token_id!(USDC_TOKEN_ID));
                     sc.pools_allowed()
                         .insert(managed_address!(&liquidity_pool_usdc_wrapper.address_ref()));
+                    sc.asset_decimals(&managed_token_id!(USDC_TOKEN_ID))
+                        .set(TOKEN_DECIMALS);
                     sc.set_asset_liquidation_bonus(
                         managed_token_id!(USDC_TOKEN_ID),
                         managed_biguint!(BP / 20),
                     );
+                    sc.set_asset_liquidation_threshold(
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(LIQ_THRESOLD),
+                    );
                 },
             )
             .assert_ok();
@@ -157,7 +179,10 @@ where
                         managed_biguint!(U_OPTIMAL),
                         managed_biguint!(RESERVE_FACTOR),
                         managed_biguint!(LIQ_THRESOLD),
+                        OptionalValue::None,
                     );
+                    // The pool belongs to the lending pool, the test owner configures it directly
+                    sc.grant_role(Role::RiskAdmin, managed_address!(&owner_addr));
                     sc.set_price_aggregator_address(managed_address!(
                         &price_aggregator_wrapper.address_ref()
                     ));
@@ -178,10 +203,20 @@ where
                     sc.pools_allowed()
                         .insert(managed_address!(&liquidity_pool_egld_wrapper.address_ref()));
 
+                    sc.asset_decimals(&managed_token_id!(EGLD_TOKEN_ID))
+                        .set(TOKEN_DECIMALS);
                     sc.set_asset_liquidation_bonus(
                         managed_token_id!(EGLD_TOKEN_ID),
                         managed_biguint!(BP / 20),
                     );
+                    sc.set_asset_liquidation_threshold(
+                        managed_token_id!(EGLD_TOKEN_ID),
+                        managed_biguint!(LIQ_THRESOLD),
+                    );
+                    // Share the pools' aggregator so tests can move prices in one place
+                    sc.set_price_aggregator_address(managed_address!(
+                        &price_aggregator_wrapper.address_ref()
+                    ));
                 },
             )
             .assert_ok();
@@ -205,6 +240,12 @@ where
                 EsdtLocalRole::NftBurn,
             ],
         );
+
+        b_mock.set_esdt_local_roles(
+            lending_pool_wrapper.address_ref(),
+            USDC_LEND_TOKEN,
+            &[EsdtLocalRole::Mint, EsdtLocalRole::Burn],
+        );
         Self {
             owner_addr,
             first_user_addr,
@@ -268,6 +309,45 @@ where
             .assert_ok();
     }
 
+    pub fn set_price_feed(&mut self, from: &[u8], to: &[u8], price: u64) {
+        self.b_mock
+            .execute_tx(
+                &self.owner_addr,
+                &self.price_aggregator_wrapper,
+                &rust_biguint!(0),
+                |sc| {
+                    sc.set_latest_price_feed(
+                        managed_buffer!(from),
+                        managed_buffer!(to),
+                        managed_biguint!(price),
+                    );
+                },
+            )
+            .assert_ok();
+    }
+
+    // Scenarios count time in years; the round follows along since positions still record it
+    pub fn set_block_year(&mut self, year: u64) {
+        self.b_mock.set_block_round(year);
+        self.b_mock.set_block_timestamp(year * SECONDS_PER_YEAR);
+    }
+
+    // A single Taylor term accrues simple interest, which keeps the expected values round
+    pub fn use_simple_interest(&mut self) {
+        for token_id in [USDC_TOKEN_ID, EGLD_TOKEN_ID] {
+            self.b_mock
+                .execute_tx(
+                    &self.owner_addr,
+                    &self.lending_pool_wrapper,
+                    &rust_biguint!(0),
+                    |sc| {
+                        sc.set_interest_approximation_terms(managed_token_id!(token_id), 1);
+                    },
+                )
+                .assert_ok();
+        }
+    }
+
     pub fn exit_market(&mut self, user_addr: &Address, account_nonce: u64) {
         self.b_mock
             .execute_esdt_transfer(
@@ -315,7 +395,7 @@ where
                         managed_biguint!(initial_amount),
                         owner_nonce,
                         1,
-                        managed_biguint!(BP),
+                        Ray::from_amount(&managed_biguint!(initial_amount)),
                     ));
                 },
             )
@@ -368,7 +448,7 @@ where
                             managed_biguint!(initial_amount),
                             owner_nonce,
                             round,
-                            managed_biguint!(initial_supply_index),
+                            scaled_amount(initial_amount, initial_supply_index),
                         ),
                     );
                 },
@@ -423,7 +503,7 @@ where
                             managed_biguint!(initial_amount),
                             owner_nonce,
                             round,
-                            managed_biguint!(initial_borrow_index),
+                            scaled_amount(initial_amount, initial_borrow_index),
                             Option::None,
                         ),
                     );
@@ -505,7 +585,7 @@ where
                 amount: managed_biguint!(borrow_amount),
                 owner_nonce: 0,
                 round: round,
-                initial_borrow_index: managed_biguint!(initial_borrow_index),
+                scaled_amount: scaled_amount(borrow_amount, initial_borrow_index),
                 nft: Option::Some(EsdtTokenPayment {
                     token_identifier: managed_token_id!(APE_TOKEN),
                     token_nonce: 1,
@@ -574,7 +654,7 @@ where
                             managed_biguint!(initial_amount),
                             owner_nonce,
                             round,
-                            managed_biguint!(borrow_index),
+                            scaled_amount(initial_amount, borrow_index),
                             Option::None,
                         ),
                     );
@@ -641,7 +721,7 @@ where
                             managed_biguint!(1000),
                             liquidatee_nonce,
                             1,
-                            managed_biguint!(BP),
+                            Ray::from_amount(&managed_biguint!(1000)),
                         ),
                     );
 
@@ -649,20 +729,15 @@ where
                         managed_token_id!(USDC_TOKEN_ID),
                         BorrowPosition::new(
                             managed_token_id!(USDC_TOKEN_ID),
-                            managed_biguint!(600),
+                            managed_biguint!(800),
                             liquidatee_nonce,
                             2,
-                            BigUint::from(BP),
+                            Ray::from_amount(&managed_biguint!(800)),
                             Option::None,
                         ),
                     );
 
-                    let threshold = BigUint::from(BP / 2);
-                    sc.liquidate(
-                        liquidatee_nonce,
-                        threshold,
-                        managed_token_id!(USDC_TOKEN_ID),
-                    );
+                    sc.liquidate(liquidatee_nonce, managed_token_id!(USDC_TOKEN_ID));
                 },
             )
             .assert_ok();
@@ -744,7 +819,7 @@ where
 
         self.b_mock
             .execute_query(&liquidity_pool_wrapper, |sc| {
-                let borrow_index = sc.borrow_index().get();
+                let borrow_index = sc.borrow_index().get().to_bp();
                 assert_eq!(borrow_index, expected_value);
             })
             .assert_ok();
@@ -1,10 +1,27 @@
 use constants::*;
 
-use lending_pool::BP;
+use aggregator_mock::PriceAggregatorMock;
+use common_pause::PauseModule;
+use common_permissions::PermissionsModule;
+use lending_pool::{
+    factory::FactoryModule, nft_liquidation::NftLiquidationModule, router::RouterModule,
+    storage::LendingStorageModule, timelock::TimelockModule, utils::LendingUtilsModule,
+    BorrowPosition, CollectionParams, DepositPosition, InterestRateStrategy, LendingPool,
+    PausableAction, RateKink, Ray, Role, TimelockedChange, BP, SECONDS_PER_YEAR,
+};
 use lending_pool_interaction::LendingSetup;
-use liquidity_pool::liq_storage::StorageModule;
-use multiversx_sc::codec::Empty;
-use multiversx_sc_scenario::{managed_biguint, rust_biguint, DebugApi};
+use liquidity_pool::{
+    liq_storage::StorageModule, liq_utils::UtilsModule, liquidity::LiquidityModule, LiquidityPool,
+};
+use multiversx_sc::{
+    codec::{multi_types::OptionalValue, Empty},
+    types::{BigInt, BigUint, EsdtTokenPayment, ManagedVec, MultiValueEncoded},
+};
+use multiversx_sc_scenario::{
+    managed_address, managed_biguint, managed_buffer, managed_token_id, rust_biguint,
+    testing_framework::TxTokenTransfer, DebugApi,
+};
+use price_aggregator_proxy::PriceAggregatorModule;
 
 pub mod constants;
 pub mod lending_pool_interaction;
@@ -146,6 +163,84 @@ fn borrow_with_nft_test() {
     lending_setup.borrow_with_nft(&user_addr, EGLD_TOKEN_ID, 250, 750, 250, 1, 1_000_000_000);
 }
 
+#[test]
+fn repay_nft_in_parts_test() {
+    let _ = DebugApi::dummy();
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let user_addr = lending_setup.first_user_addr.clone();
+    let lender_addr = lending_setup.second_user_addr.clone();
+    let account_nonce = lending_setup.enter_market(&lender_addr);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&lender_addr, EGLD_TOKEN_ID, &rust_biguint!(1_000));
+    lending_setup.add_collateral(&lender_addr, EGLD_TOKEN_ID, 0, account_nonce, 1_000, 1_000);
+    lending_setup
+        .b_mock
+        .set_nft_balance(&user_addr, APE_TOKEN, 1, &rust_biguint!(1), &Empty {});
+    lending_setup.add_collections();
+    lending_setup.borrow_with_nft(&user_addr, EGLD_TOKEN_ID, 250, 750, 250, 1, 1_000_000_000);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&user_addr, EGLD_TOKEN_ID, &rust_biguint!(250));
+    for (repay_amount, debt_left) in [(100u64, 150u64), (150, 0)] {
+        let transfers = [
+            TxTokenTransfer {
+                token_identifier: EGLD_TOKEN_ID.to_vec(),
+                nonce: 0,
+                value: rust_biguint!(repay_amount),
+            },
+            TxTokenTransfer {
+                token_identifier: DEBT_NFT_TOKEN.to_vec(),
+                nonce: 1,
+                value: rust_biguint!(1),
+            },
+        ];
+        lending_setup
+            .b_mock
+            .execute_esdt_multi_transfer(
+                &user_addr,
+                &lending_setup.lending_pool_wrapper,
+                &transfers,
+                |sc| {
+                    sc.repay_nft_debt();
+
+                    // The second repayment settles against the stored 150, not the 250 the
+                    // debt NFT was minted with
+                    if debt_left == 0 {
+                        assert!(sc.nft_borrow_positions(1).is_empty());
+                    } else {
+                        assert_eq!(
+                            sc.nft_borrow_positions(1).get().amount,
+                            managed_biguint!(debt_left)
+                        );
+                    }
+                },
+            )
+            .assert_ok();
+    }
+
+    lending_setup.b_mock.check_nft_balance::<Empty>(
+        &user_addr,
+        APE_TOKEN,
+        1,
+        &rust_biguint!(1),
+        None,
+    );
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_egld_wrapper, |sc| {
+            assert_eq!(sc.reserves().get(), managed_biguint!(1_000));
+            assert_eq!(sc.borrowed_amount().get(), managed_biguint!(0));
+        })
+        .assert_ok();
+}
+
 #[test]
 fn repay_test() {
     let mut lending_setup = LendingSetup::deploy_lending(
@@ -153,135 +248,3636 @@ fn repay_test() {
         liquidity_pool::contract_obj,
         aggregator_mock::contract_obj,
     );
-    let user_addr = lending_setup.first_user_addr.clone();
-    let account_nonce = lending_setup.enter_market(&user_addr);
+    let user_addr = lending_setup.first_user_addr.clone();
+    let account_nonce = lending_setup.enter_market(&user_addr);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&user_addr, USDC_TOKEN_ID, &rust_biguint!(1_000));
+
+    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 0, account_nonce, 1_000, 1_000);
+    lending_setup.borrow(
+        &user_addr,
+        USDC_TOKEN_ID,
+        0,
+        account_nonce,
+        250,
+        750,
+        250,
+        1,
+        1_000_000_000,
+    );
+    lending_setup.repay(
+        &user_addr,
+        USDC_TOKEN_ID,
+        250,
+        account_nonce,
+        100,
+        850,
+        150,
+        1,
+        1_000_000_000,
+    );
+}
+
+#[test]
+fn add_collateral_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+
+    let user_addr = lending_setup.first_user_addr.clone();
+    let account_nonce = lending_setup.enter_market(&user_addr);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&user_addr, USDC_TOKEN_ID, &rust_biguint!(1_000));
+
+    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 0, account_nonce, 500, 500);
+    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 500, account_nonce, 200, 700);
+    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 700, account_nonce, 150, 850);
+}
+
+#[test]
+fn remove_collateral_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+
+    let user_addr = lending_setup.first_user_addr.clone();
+    let account_nonce = lending_setup.enter_market(&user_addr);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&user_addr, USDC_TOKEN_ID, &rust_biguint!(1_000));
+
+    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 0, account_nonce, 700, 700);
+    lending_setup.remove_collateral(
+        &user_addr,
+        USDC_TOKEN_ID,
+        700,
+        account_nonce,
+        300,
+        400,
+        1,
+        1_000_000_000,
+    );
+    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 400, account_nonce, 150, 550);
+}
+
+#[test]
+fn liquidate_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+
+    let liquidatee_user = lending_setup.first_user_addr.clone();
+    let liquidator_user = lending_setup.second_user_addr.clone();
+
+    let liquidatee_account_nonce = lending_setup.enter_market(&liquidatee_user);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&liquidatee_user, USDC_TOKEN_ID, &rust_biguint!(1_000));
+
+    lending_setup.add_collateral(
+        &liquidatee_user,
+        USDC_TOKEN_ID,
+        0,
+        liquidatee_account_nonce,
+        1_000,
+        1_000,
+    );
+    lending_setup.borrow(
+        &liquidatee_user,
+        USDC_TOKEN_ID,
+        0,
+        liquidatee_account_nonce,
+        600,
+        400,
+        600,
+        1,
+        1_000_000_000,
+    );
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&liquidator_user, USDC_TOKEN_ID, &rust_biguint!(300));
+
+    lending_setup.liquidate(
+        &liquidator_user,
+        &liquidatee_user,
+        liquidatee_account_nonce,
+        300,
+        315,
+        385,
+    );
+}
+
+#[test]
+fn liquidate_cross_asset_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+
+    let supplier_user = lending_setup.first_user_addr.clone();
+    let liquidatee_user = lending_setup.second_user_addr.clone();
+    let liquidator_user = lending_setup.third_user_addr.clone();
+
+    let supplier_account_nonce = lending_setup.enter_market(&supplier_user);
+    let liquidatee_account_nonce = lending_setup.enter_market(&liquidatee_user);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&supplier_user, USDC_TOKEN_ID, &rust_biguint!(200_000));
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&liquidatee_user, EGLD_TOKEN_ID, &rust_biguint!(1_000));
+
+    lending_setup.add_collateral(
+        &supplier_user,
+        USDC_TOKEN_ID,
+        0,
+        supplier_account_nonce,
+        200_000,
+        200_000,
+    );
+    lending_setup.add_collateral(
+        &liquidatee_user,
+        EGLD_TOKEN_ID,
+        0,
+        liquidatee_account_nonce,
+        1_000,
+        1_000,
+    );
+    lending_setup.borrow(
+        &liquidatee_user,
+        USDC_TOKEN_ID,
+        0,
+        liquidatee_account_nonce,
+        150_000,
+        50_000,
+        150_000,
+        1,
+        1_000_000_000,
+    );
+
+    // 1_000 WEGLD * $200 * 0.7 backing 150_000 USDC * $1
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &liquidatee_user,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.deposit_positions(liquidatee_account_nonce).insert(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    DepositPosition::new(
+                        managed_token_id!(EGLD_TOKEN_ID),
+                        managed_biguint!(1_000),
+                        liquidatee_account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(1_000)),
+                    ),
+                );
+                sc.borrow_positions(liquidatee_account_nonce).insert(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    BorrowPosition::new(
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(150_000),
+                        liquidatee_account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(150_000)),
+                        Option::None,
+                    ),
+                );
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&liquidator_user, USDC_TOKEN_ID, &rust_biguint!(80_000));
+
+    // Close factor: at most half of the debt can be repaid at once
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &liquidator_user,
+            &lending_setup.lending_pool_wrapper,
+            USDC_TOKEN_ID,
+            0,
+            &rust_biguint!(80_000),
+            |sc| {
+                sc.liquidate(liquidatee_account_nonce, managed_token_id!(EGLD_TOKEN_ID));
+            },
+        )
+        .assert_user_error("Cannot liquidate more than 50% of Liquidatee's position!");
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &liquidator_user,
+            &lending_setup.lending_pool_wrapper,
+            USDC_TOKEN_ID,
+            0,
+            &rust_biguint!(50_000),
+            |sc| {
+                sc.liquidate(liquidatee_account_nonce, managed_token_id!(EGLD_TOKEN_ID));
+
+                let borrow_position = sc
+                    .borrow_positions(liquidatee_account_nonce)
+                    .get(&managed_token_id!(USDC_TOKEN_ID))
+                    .unwrap();
+                assert_eq!(borrow_position.amount, managed_biguint!(100_000));
+
+                let deposit_position = sc
+                    .deposit_positions(liquidatee_account_nonce)
+                    .get(&managed_token_id!(EGLD_TOKEN_ID))
+                    .unwrap();
+                assert_eq!(deposit_position.amount, managed_biguint!(738));
+            },
+        )
+        .assert_ok();
+
+    // 50_000 USDC * $1 * 1.05 / $200 = 262.5 WEGLD
+    lending_setup
+        .b_mock
+        .check_esdt_balance(&liquidator_user, EGLD_TOKEN_ID, &rust_biguint!(262));
+    lending_setup.b_mock.check_esdt_balance(
+        &liquidator_user,
+        USDC_TOKEN_ID,
+        &rust_biguint!(30_000),
+    );
+
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
+            assert_eq!(sc.borrowed_amount().get(), managed_biguint!(100_000));
+            assert_eq!(sc.reserves().get(), managed_biguint!(100_000));
+        })
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_egld_wrapper, |sc| {
+            assert_eq!(sc.reserves().get(), managed_biguint!(738));
+            assert_eq!(sc.supplied_amount().get(), managed_biguint!(738));
+        })
+        .assert_ok();
+}
+
+#[test]
+fn asset_decimals_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+
+    // A pool created before decimals were recorded gets them set once
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.asset_decimals(&managed_token_id!(USDC_TOKEN_ID)).clear();
+                sc.set_asset_decimals(managed_token_id!(USDC_TOKEN_ID), 6);
+
+                assert_eq!(
+                    sc.asset_decimals(&managed_token_id!(USDC_TOKEN_ID)).get(),
+                    6
+                );
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_asset_decimals(managed_token_id!(USDC_TOKEN_ID), 18);
+            },
+        )
+        .assert_user_error("asset decimals already set");
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.asset_decimals(&managed_token_id!(EGLD_TOKEN_ID)).clear();
+                sc.set_asset_decimals(managed_token_id!(EGLD_TOKEN_ID), 19);
+            },
+        )
+        .assert_user_error("invalid asset decimals");
+}
+
+#[test]
+fn liquidate_with_accrued_interest_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+
+    let supplier_user = lending_setup.first_user_addr.clone();
+    let liquidatee_user = lending_setup.second_user_addr.clone();
+    let liquidator_user = lending_setup.third_user_addr.clone();
+
+    let supplier_account_nonce = lending_setup.enter_market(&supplier_user);
+    let liquidatee_account_nonce = lending_setup.enter_market(&liquidatee_user);
+    lending_setup.use_simple_interest();
+    lending_setup.set_block_year(1);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&supplier_user, USDC_TOKEN_ID, &rust_biguint!(200_000));
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&liquidatee_user, EGLD_TOKEN_ID, &rust_biguint!(1_000));
+
+    lending_setup.add_collateral(
+        &supplier_user,
+        USDC_TOKEN_ID,
+        0,
+        supplier_account_nonce,
+        200_000,
+        200_000,
+    );
+    lending_setup.add_collateral(
+        &liquidatee_user,
+        EGLD_TOKEN_ID,
+        0,
+        liquidatee_account_nonce,
+        1_000,
+        1_000,
+    );
+    lending_setup.borrow(
+        &liquidatee_user,
+        USDC_TOKEN_ID,
+        0,
+        liquidatee_account_nonce,
+        100_000,
+        100_000,
+        100_000,
+        1,
+        1_000_000_000,
+    );
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &liquidatee_user,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.deposit_positions(liquidatee_account_nonce).insert(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    DepositPosition::new(
+                        managed_token_id!(EGLD_TOKEN_ID),
+                        managed_biguint!(1_000),
+                        liquidatee_account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(1_000)),
+                    ),
+                );
+                sc.borrow_positions(liquidatee_account_nonce).insert(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    BorrowPosition::new(
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(100_000),
+                        liquidatee_account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(100_000)),
+                        Option::None,
+                    ),
+                );
+            },
+        )
+        .assert_ok();
+
+    // Two years at 2.5% bring the debt to 105_000, while the collateral drops to $140
+    lending_setup.set_block_year(3);
+    lending_setup.set_price_feed(EGLD_TICKER, DOLLAR_TICKER, EGLD_PRICE_DROPPED_IN_DOLLARS);
+    lending_setup.set_price_feed(USDC_TICKER, DOLLAR_TICKER, USDC_PRICE_IN_DOLLARS);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&liquidator_user, USDC_TOKEN_ID, &rust_biguint!(50_000));
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &liquidator_user,
+            &lending_setup.lending_pool_wrapper,
+            USDC_TOKEN_ID,
+            0,
+            &rust_biguint!(50_000),
+            |sc| {
+                sc.liquidate(liquidatee_account_nonce, managed_token_id!(EGLD_TOKEN_ID));
+
+                let borrow_position = sc
+                    .borrow_positions(liquidatee_account_nonce)
+                    .get(&managed_token_id!(USDC_TOKEN_ID))
+                    .unwrap();
+                assert_eq!(borrow_position.amount, managed_biguint!(55_000));
+            },
+        )
+        .assert_ok();
+
+    // The repayment clears 50_000 / 1.05 of the 100_000 scaled debt, so as much of the principal
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
+            assert_eq!(sc.borrowed_amount().get(), managed_biguint!(52_381));
+            assert_eq!(sc.reserves().get(), managed_biguint!(150_000));
+        })
+        .assert_ok();
+}
+
+#[test]
+fn bad_debt_covered_by_safety_module_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+
+    let supplier_user = lending_setup.first_user_addr.clone();
+    let liquidatee_user = lending_setup.second_user_addr.clone();
+    let liquidator_user = lending_setup.third_user_addr.clone();
+    let owner_addr = lending_setup.owner_addr.clone();
+    let wegld_pool_addr = lending_setup
+        .liquidity_pool_egld_wrapper
+        .address_ref()
+        .clone();
+
+    let safety_module_wrapper = setup::setup_safety_module(
+        &owner_addr,
+        &mut lending_setup.b_mock,
+        safety_module::contract_obj,
+        &wegld_pool_addr,
+        BP / 2,
+    );
+    lending_setup.b_mock.set_esdt_balance(
+        safety_module_wrapper.address_ref(),
+        EGLD_TOKEN_ID,
+        &rust_biguint!(50),
+    );
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_safety_module(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    managed_address!(safety_module_wrapper.address_ref()),
+                );
+            },
+        )
+        .assert_ok();
+
+    let supplier_account_nonce = lending_setup.enter_market(&supplier_user);
+    let liquidatee_account_nonce = lending_setup.enter_market(&liquidatee_user);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&supplier_user, EGLD_TOKEN_ID, &rust_biguint!(1_000));
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&liquidatee_user, USDC_TOKEN_ID, &rust_biguint!(20_000));
+
+    lending_setup.add_collateral(
+        &supplier_user,
+        EGLD_TOKEN_ID,
+        0,
+        supplier_account_nonce,
+        1_000,
+        1_000,
+    );
+    lending_setup.add_collateral(
+        &liquidatee_user,
+        USDC_TOKEN_ID,
+        0,
+        liquidatee_account_nonce,
+        20_000,
+        20_000,
+    );
+    lending_setup.borrow(
+        &liquidatee_user,
+        EGLD_TOKEN_ID,
+        0,
+        liquidatee_account_nonce,
+        70,
+        930,
+        70,
+        1,
+        1_000_000_000,
+    );
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &liquidatee_user,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.deposit_positions(liquidatee_account_nonce).insert(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    DepositPosition::new(
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(20_000),
+                        liquidatee_account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(20_000)),
+                    ),
+                );
+                sc.borrow_positions(liquidatee_account_nonce).insert(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    BorrowPosition::new(
+                        managed_token_id!(EGLD_TOKEN_ID),
+                        managed_biguint!(70),
+                        liquidatee_account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(70)),
+                        Option::None,
+                    ),
+                );
+            },
+        )
+        .assert_ok();
+
+    // USDC drops to $0.30: 20_000 USDC can't even pay for the repaid half plus the bonus
+    lending_setup.set_price_feed(USDC_TICKER, DOLLAR_TICKER, 30);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&liquidator_user, EGLD_TOKEN_ID, &rust_biguint!(35));
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &liquidator_user,
+            &lending_setup.lending_pool_wrapper,
+            EGLD_TOKEN_ID,
+            0,
+            &rust_biguint!(35),
+            |sc| {
+                sc.liquidate(liquidatee_account_nonce, managed_token_id!(USDC_TOKEN_ID));
+
+                assert!(sc.deposit_positions(liquidatee_account_nonce).is_empty());
+                assert!(sc.borrow_positions(liquidatee_account_nonce).is_empty());
+            },
+        )
+        .assert_ok();
+
+    // The whole deposit only pays for 20_000 * $0.30 / ($200 * 1.05) = 28 WEGLD, the other 7
+    // go back to the liquidator
+    lending_setup.b_mock.check_esdt_balance(
+        &liquidator_user,
+        USDC_TOKEN_ID,
+        &rust_biguint!(20_000),
+    );
+    lending_setup
+        .b_mock
+        .check_esdt_balance(&liquidator_user, EGLD_TOKEN_ID, &rust_biguint!(7));
+
+    // The remaining 42 WEGLD are written off, the module covers half of its 50 WEGLD
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_egld_wrapper, |sc| {
+            assert_eq!(sc.borrowed_amount().get(), managed_biguint!(0));
+            assert_eq!(sc.reserves().get(), managed_biguint!(983));
+
+            // The 17 WEGLD left uncovered are taken from the suppliers
+            assert_eq!(
+                sc.get_deposit_amount(&Ray::from_amount(&managed_biguint!(1_000))),
+                managed_biguint!(983)
+            );
+            assert_eq!(sc.supplied_amount().get(), managed_biguint!(983));
+        })
+        .assert_ok();
+    lending_setup.b_mock.check_esdt_balance(
+        safety_module_wrapper.address_ref(),
+        EGLD_TOKEN_ID,
+        &rust_biguint!(25),
+    );
+}
+
+#[test]
+fn write_off_dust_collateral_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+
+    let supplier_user = lending_setup.first_user_addr.clone();
+    let liquidatee_user = lending_setup.second_user_addr.clone();
+    let liquidator_user = lending_setup.third_user_addr.clone();
+    let owner_addr = lending_setup.owner_addr.clone();
+
+    let supplier_account_nonce = lending_setup.enter_market(&supplier_user);
+    let liquidatee_account_nonce = lending_setup.enter_market(&liquidatee_user);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&supplier_user, EGLD_TOKEN_ID, &rust_biguint!(1_000));
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&liquidatee_user, USDC_TOKEN_ID, &rust_biguint!(20_000));
+
+    lending_setup.add_collateral(
+        &supplier_user,
+        EGLD_TOKEN_ID,
+        0,
+        supplier_account_nonce,
+        1_000,
+        1_000,
+    );
+    lending_setup.add_collateral(
+        &liquidatee_user,
+        USDC_TOKEN_ID,
+        0,
+        liquidatee_account_nonce,
+        20_000,
+        20_000,
+    );
+    lending_setup.borrow(
+        &liquidatee_user,
+        EGLD_TOKEN_ID,
+        0,
+        liquidatee_account_nonce,
+        70,
+        930,
+        70,
+        1,
+        1_000_000_000,
+    );
+
+    // Only 1 USDC is left as collateral, next to a supply-only EGLD deposit
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &liquidatee_user,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.deposit_positions(liquidatee_account_nonce).insert(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    DepositPosition::new(
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(1),
+                        liquidatee_account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(1)),
+                    ),
+                );
+                sc.deposit_positions(liquidatee_account_nonce).insert(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    DepositPosition::new(
+                        managed_token_id!(EGLD_TOKEN_ID),
+                        managed_biguint!(100),
+                        liquidatee_account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(100)),
+                    ),
+                );
+                sc.non_collateral_deposits(liquidatee_account_nonce)
+                    .insert(managed_token_id!(EGLD_TOKEN_ID));
+                sc.borrow_positions(liquidatee_account_nonce).insert(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    BorrowPosition::new(
+                        managed_token_id!(EGLD_TOKEN_ID),
+                        managed_biguint!(70),
+                        liquidatee_account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(70)),
+                        Option::None,
+                    ),
+                );
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&liquidator_user, EGLD_TOKEN_ID, &rust_biguint!(35));
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &liquidator_user,
+            &lending_setup.lending_pool_wrapper,
+            EGLD_TOKEN_ID,
+            0,
+            &rust_biguint!(35),
+            |sc| {
+                sc.liquidate(liquidatee_account_nonce, managed_token_id!(USDC_TOKEN_ID));
+            },
+        )
+        .assert_user_error("collateral too small to liquidate");
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &liquidator_user,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.write_off_bad_debt(liquidatee_account_nonce);
+            },
+        )
+        .assert_user_error("account has no debt or still has collateral to liquidate");
+
+    // $1 of collateral counts as dust
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_bad_debt_dust_threshold(BigUint::from(10u64).pow(18));
+                let mut actions = MultiValueEncoded::new();
+                actions.push(PausableAction::Liquidate);
+                sc.pause(actions);
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &liquidator_user,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.write_off_bad_debt(liquidatee_account_nonce);
+            },
+        )
+        .assert_user_error("action is paused");
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let mut actions = MultiValueEncoded::new();
+                actions.push(PausableAction::Liquidate);
+                sc.unpause(actions);
+            },
+        )
+        .assert_ok();
+
+    // The supply-only deposit never backed the debt, it is neither counted nor seized
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &liquidator_user,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.write_off_bad_debt(liquidatee_account_nonce);
+
+                assert_eq!(sc.deposit_positions(liquidatee_account_nonce).len(), 1);
+                assert!(sc
+                    .deposit_positions(liquidatee_account_nonce)
+                    .contains_key(&managed_token_id!(EGLD_TOKEN_ID)));
+                assert!(sc.borrow_positions(liquidatee_account_nonce).is_empty());
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .check_esdt_balance(&liquidator_user, USDC_TOKEN_ID, &rust_biguint!(1));
+
+    // Without a safety module the whole 70 WEGLD are taken from the suppliers
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_egld_wrapper, |sc| {
+            assert_eq!(sc.borrowed_amount().get(), managed_biguint!(0));
+            assert_eq!(
+                sc.get_deposit_amount(&Ray::from_amount(&managed_biguint!(1_000))),
+                managed_biguint!(930)
+            );
+        })
+        .assert_ok();
+}
+
+#[test]
+fn account_health_factor_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let user_addr = lending_setup.first_user_addr.clone();
+    let account_nonce = lending_setup.enter_market(&user_addr);
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.deposit_positions(account_nonce).insert(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    DepositPosition::new(
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(1_000),
+                        account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(1_000)),
+                    ),
+                );
+                sc.deposit_positions(account_nonce).insert(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    DepositPosition::new(
+                        managed_token_id!(EGLD_TOKEN_ID),
+                        managed_biguint!(10),
+                        account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(10)),
+                    ),
+                );
+
+                // No debt yet
+                assert_eq!(
+                    sc.get_account_health_factor(account_nonce),
+                    BigUint::from_bytes_be(&u64::MAX.to_be_bytes())
+                );
+
+                sc.borrow_positions(account_nonce).insert(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    BorrowPosition::new(
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(500),
+                        account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(500)),
+                        Option::None,
+                    ),
+                );
+
+                // (1_000 * $1 + 10 * $200) * 0.7 / (500 * $1) = 4.2
+                assert_eq!(
+                    sc.get_account_health_factor(account_nonce),
+                    managed_biguint!(4_200_000_000)
+                );
+            },
+        )
+        .assert_ok();
+
+    // Once the borrow index moves, the stored debt is unchanged but valued at the new index
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &lending_setup.owner_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.borrow_index()
+                    .set(Ray::from_bp(&managed_biguint!(1_100_000_000)));
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let borrow_position = sc
+                    .borrow_positions(account_nonce)
+                    .get(&managed_token_id!(USDC_TOKEN_ID))
+                    .unwrap();
+                assert_eq!(borrow_position.amount, managed_biguint!(500));
+                // (1_000 * $1 + 10 * $200) * 0.7 / (550 * $1)
+                assert_eq!(
+                    sc.get_account_health_factor(account_nonce),
+                    managed_biguint!(3_818_181_818)
+                );
+            },
+        )
+        .assert_ok();
+}
+
+#[test]
+fn supply_without_collateral_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let user_addr = lending_setup.first_user_addr.clone();
+    let account_nonce = lending_setup.enter_market(&user_addr);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&user_addr, USDC_TOKEN_ID, &rust_biguint!(1_000));
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&user_addr, EGLD_TOKEN_ID, &rust_biguint!(10));
+
+    let transfers = [
+        TxTokenTransfer {
+            token_identifier: ACCOUNT_TOKEN.to_vec(),
+            nonce: account_nonce,
+            value: rust_biguint!(1),
+        },
+        TxTokenTransfer {
+            token_identifier: USDC_TOKEN_ID.to_vec(),
+            nonce: 0,
+            value: rust_biguint!(1_000),
+        },
+    ];
+    lending_setup
+        .b_mock
+        .execute_esdt_multi_transfer(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            &transfers,
+            |sc| {
+                sc.supply();
+
+                assert!(sc
+                    .deposit_positions(account_nonce)
+                    .contains_key(&managed_token_id!(USDC_TOKEN_ID)));
+                assert!(!sc.is_collateral_enabled(account_nonce, &managed_token_id!(USDC_TOKEN_ID)));
+                assert_eq!(sc.get_total_collateral_in_dollars(account_nonce), 0u64);
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            ACCOUNT_TOKEN,
+            account_nonce,
+            &rust_biguint!(1),
+            |sc| {
+                sc.set_collateral_enabled(managed_token_id!(USDC_TOKEN_ID), true);
+
+                sc.borrow_positions(account_nonce).insert(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    BorrowPosition::new(
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(500),
+                        account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(500)),
+                        Option::None,
+                    ),
+                );
+
+                // 1_000 * $1 * 0.7 / (500 * $1) = 1.4
+                assert_eq!(
+                    sc.get_account_health_factor(account_nonce),
+                    managed_biguint!(1_400_000_000)
+                );
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            ACCOUNT_TOKEN,
+            account_nonce,
+            &rust_biguint!(1),
+            |sc| {
+                sc.set_collateral_enabled(managed_token_id!(USDC_TOKEN_ID), false);
+            },
+        )
+        .assert_user_error("health factor too low to disable collateral");
+
+    // Supplied EGLD only backs the debt once it is enabled as collateral
+    let transfers = [
+        TxTokenTransfer {
+            token_identifier: ACCOUNT_TOKEN.to_vec(),
+            nonce: account_nonce,
+            value: rust_biguint!(1),
+        },
+        TxTokenTransfer {
+            token_identifier: EGLD_TOKEN_ID.to_vec(),
+            nonce: 0,
+            value: rust_biguint!(10),
+        },
+    ];
+    lending_setup
+        .b_mock
+        .execute_esdt_multi_transfer(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            &transfers,
+            |sc| {
+                sc.supply();
+
+                assert_eq!(
+                    sc.get_account_health_factor(account_nonce),
+                    managed_biguint!(1_400_000_000)
+                );
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            ACCOUNT_TOKEN,
+            account_nonce,
+            &rust_biguint!(1),
+            |sc| {
+                sc.set_collateral_enabled(managed_token_id!(EGLD_TOKEN_ID), true);
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            ACCOUNT_TOKEN,
+            account_nonce,
+            &rust_biguint!(1),
+            |sc| {
+                sc.set_collateral_enabled(managed_token_id!(USDC_TOKEN_ID), false);
+
+                // 10 * $200 * 0.7 / (500 * $1) = 2.8
+                assert_eq!(
+                    sc.get_account_health_factor(account_nonce),
+                    managed_biguint!(2_800_000_000)
+                );
+            },
+        )
+        .assert_ok();
+}
+
+#[test]
+fn isolation_mode_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+    let borrower_addr = lending_setup.first_user_addr.clone();
+    let lender_addr = lending_setup.second_user_addr.clone();
+    let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+    let lender_nonce = lending_setup.enter_market(&lender_addr);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&lender_addr, USDC_TOKEN_ID, &rust_biguint!(1_000));
+    lending_setup.add_collateral(&lender_addr, USDC_TOKEN_ID, 0, lender_nonce, 1_000, 1_000);
+
+    // Isolated borrowers share a ceiling of $300
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_asset_loan_to_value(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    managed_biguint!(750_000_000),
+                );
+                sc.set_asset_loan_to_value(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    managed_biguint!(750_000_000),
+                );
+                sc.set_asset_isolated(managed_token_id!(EGLD_TOKEN_ID), true);
+                sc.set_isolation_borrowable(managed_token_id!(USDC_TOKEN_ID), true);
+                sc.set_isolation_debt_ceiling(managed_biguint!(300) * BigUint::from(10u64).pow(18));
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&borrower_addr, EGLD_TOKEN_ID, &rust_biguint!(10));
+    let transfers = [
+        TxTokenTransfer {
+            token_identifier: ACCOUNT_TOKEN.to_vec(),
+            nonce: borrower_nonce,
+            value: rust_biguint!(1),
+        },
+        TxTokenTransfer {
+            token_identifier: EGLD_TOKEN_ID.to_vec(),
+            nonce: 0,
+            value: rust_biguint!(10),
+        },
+    ];
+    lending_setup
+        .b_mock
+        .execute_esdt_multi_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            &transfers,
+            |sc| {
+                sc.add_collateral();
+
+                assert!(sc.is_account_isolated(borrower_nonce));
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            ACCOUNT_TOKEN,
+            borrower_nonce,
+            &rust_biguint!(1),
+            |sc| {
+                sc.borrow(managed_token_id!(EGLD_TOKEN_ID), managed_biguint!(1));
+            },
+        )
+        .assert_user_error("asset not borrowable in isolation mode");
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            ACCOUNT_TOKEN,
+            borrower_nonce,
+            &rust_biguint!(1),
+            |sc| {
+                sc.borrow(managed_token_id!(USDC_TOKEN_ID), managed_biguint!(200));
+
+                assert_eq!(
+                    sc.isolated_debt().get(),
+                    managed_biguint!(200) * BigUint::from(10u64).pow(18)
+                );
+                assert_eq!(
+                    sc.get_isolation_remaining_debt(),
+                    managed_biguint!(100) * BigUint::from(10u64).pow(18)
+                );
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            ACCOUNT_TOKEN,
+            borrower_nonce,
+            &rust_biguint!(1),
+            |sc| {
+                sc.borrow(managed_token_id!(USDC_TOKEN_ID), managed_biguint!(200));
+            },
+        )
+        .assert_user_error("isolation debt ceiling exceeded");
+
+    let transfers = [
+        TxTokenTransfer {
+            token_identifier: ACCOUNT_TOKEN.to_vec(),
+            nonce: borrower_nonce,
+            value: rust_biguint!(1),
+        },
+        TxTokenTransfer {
+            token_identifier: USDC_TOKEN_ID.to_vec(),
+            nonce: 0,
+            value: rust_biguint!(100),
+        },
+    ];
+    lending_setup
+        .b_mock
+        .execute_esdt_multi_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            &transfers,
+            |sc| {
+                sc.repay();
+
+                assert_eq!(
+                    sc.isolated_debt().get(),
+                    managed_biguint!(100) * BigUint::from(10u64).pow(18)
+                );
+            },
+        )
+        .assert_ok();
+
+    // Repayments release the value the debt had when borrowed, not at today's price, and only
+    // for the principal they clear: with a 1.1 borrow index the 100 left owe 110, so 55 repay
+    // half of the principal
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.borrow_index()
+                    .set(Ray::from_bp(&managed_biguint!(1_100_000_000)));
+            },
+        )
+        .assert_ok();
+    lending_setup.set_price_feed(USDC_TICKER, DOLLAR_TICKER, 2 * USDC_PRICE_IN_DOLLARS);
+    lending_setup.set_price_feed(EGLD_TICKER, DOLLAR_TICKER, EGLD_PRICE_IN_DOLLARS);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&borrower_addr, USDC_TOKEN_ID, &rust_biguint!(110));
+    let transfers = [
+        TxTokenTransfer {
+            token_identifier: ACCOUNT_TOKEN.to_vec(),
+            nonce: borrower_nonce,
+            value: rust_biguint!(1),
+        },
+        TxTokenTransfer {
+            token_identifier: USDC_TOKEN_ID.to_vec(),
+            nonce: 0,
+            value: rust_biguint!(55),
+        },
+    ];
+    lending_setup
+        .b_mock
+        .execute_esdt_multi_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            &transfers,
+            |sc| {
+                sc.repay();
+
+                assert_eq!(
+                    sc.isolated_debt().get(),
+                    managed_biguint!(50) * BigUint::from(10u64).pow(18)
+                );
+                assert_eq!(
+                    sc.account_isolated_debt(borrower_nonce, &managed_token_id!(USDC_TOKEN_ID))
+                        .get(),
+                    managed_biguint!(50)
+                );
+            },
+        )
+        .assert_ok();
+
+    // The debt was borrowed while isolated, so it still releases the ceiling once the account
+    // is no longer isolated
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_asset_isolated(managed_token_id!(EGLD_TOKEN_ID), false);
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_esdt_multi_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            &transfers,
+            |sc| {
+                sc.repay();
+
+                assert!(!sc.is_account_isolated(borrower_nonce));
+                assert_eq!(sc.isolated_debt().get(), managed_biguint!(0));
+                assert!(sc
+                    .account_isolated_debt(borrower_nonce, &managed_token_id!(USDC_TOKEN_ID))
+                    .is_empty());
+            },
+        )
+        .assert_ok();
+}
+
+#[test]
+fn e_mode_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+    let user_addr = lending_setup.first_user_addr.clone();
+    let account_nonce = lending_setup.enter_market(&user_addr);
+
+    // A stablecoin category: 90% LTV, 95% liquidation threshold, 1% bonus
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_asset_loan_to_value(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    managed_biguint!(750_000_000),
+                );
+                sc.set_e_mode_category(
+                    1,
+                    managed_biguint!(900_000_000),
+                    managed_biguint!(950_000_000),
+                    managed_biguint!(10_000_000),
+                    OptionalValue::None,
+                );
+                sc.set_asset_e_mode_category(managed_token_id!(USDC_TOKEN_ID), 1);
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.deposit_positions(account_nonce).insert(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    DepositPosition::new(
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(1_000),
+                        account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(1_000)),
+                    ),
+                );
+                sc.borrow_positions(account_nonce).insert(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    BorrowPosition::new(
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(900),
+                        account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(900)),
+                        Option::None,
+                    ),
+                );
+
+                // 1_000 * 0.7 / 900
+                assert_eq!(
+                    sc.get_account_health_factor(account_nonce),
+                    managed_biguint!(777_777_777)
+                );
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            ACCOUNT_TOKEN,
+            account_nonce,
+            &rust_biguint!(1),
+            |sc| {
+                sc.set_account_e_mode_category(1);
+
+                // 1_000 * 0.95 / 900
+                assert_eq!(
+                    sc.get_account_health_factor(account_nonce),
+                    managed_biguint!(1_055_555_555)
+                );
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            ACCOUNT_TOKEN,
+            account_nonce,
+            &rust_biguint!(1),
+            |sc| {
+                sc.set_account_e_mode_category(0);
+            },
+        )
+        .assert_user_error("health factor too low to change the e-mode category");
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            ACCOUNT_TOKEN,
+            account_nonce,
+            &rust_biguint!(1),
+            |sc| {
+                sc.borrow(managed_token_id!(EGLD_TOKEN_ID), managed_biguint!(1));
+            },
+        )
+        .assert_user_error("asset not in the account's e-mode category");
+
+    // The category price source replaces the USDC feed for the account
+    lending_setup.set_price_feed(b"USD-STABLE", DOLLAR_TICKER, 90);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_e_mode_category(
+                    1,
+                    managed_biguint!(900_000_000),
+                    managed_biguint!(950_000_000),
+                    managed_biguint!(10_000_000),
+                    OptionalValue::Some(managed_buffer!(b"USD-STABLE")),
+                );
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            assert_eq!(
+                sc.get_total_collateral_in_dollars(account_nonce),
+                managed_biguint!(900) * BigUint::from(10u64).pow(18)
+            );
+        })
+        .assert_ok();
+
+    // Collateral outside the category keeps its own LTV and liquidation threshold
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.deposit_positions(account_nonce).insert(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    DepositPosition::new(
+                        managed_token_id!(EGLD_TOKEN_ID),
+                        managed_biguint!(10),
+                        account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(10)),
+                    ),
+                );
+
+                // 900 * 0.9 + 2_000 * 0.75
+                assert_eq!(
+                    sc.get_total_borrowable_collateral_in_dollars(account_nonce),
+                    managed_biguint!(2_310) * BigUint::from(10u64).pow(18)
+                );
+                // (900 * 0.95 + 2_000 * 0.7) / 810
+                assert_eq!(
+                    sc.get_account_health_factor(account_nonce),
+                    managed_biguint!(2_783_950_617)
+                );
+            },
+        )
+        .assert_ok();
+}
+
+#[test]
+fn nft_auction_liquidation_test() {
+    let _ = DebugApi::dummy();
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let borrower_addr = lending_setup.first_user_addr.clone();
+    let lender_addr = lending_setup.second_user_addr.clone();
+    let bidder_addr = lending_setup.third_user_addr.clone();
+    let owner_addr = lending_setup.owner_addr.clone();
+    let account_nonce = lending_setup.enter_market(&lender_addr);
+
+    lending_setup.set_price_feed(b"EGLD", DOLLAR_TICKER, EGLD_PRICE_IN_DOLLARS);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&lender_addr, EGLD_TOKEN_ID, &rust_biguint!(1_000));
+    lending_setup.add_collateral(&lender_addr, EGLD_TOKEN_ID, 0, account_nonce, 1_000, 1_000);
+
+    lending_setup.b_mock.set_nft_balance(
+        &borrower_addr,
+        APE_TOKEN,
+        1,
+        &rust_biguint!(1),
+        &Empty {},
+    );
+    lending_setup.add_collections();
+    lending_setup.borrow_with_nft(
+        &borrower_addr,
+        EGLD_TOKEN_ID,
+        250,
+        750,
+        250,
+        1,
+        1_000_000_000,
+    );
+
+    lending_setup.b_mock.set_block_timestamp(1_000);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_nft_auction_params(
+                    1_000,
+                    managed_biguint!(1_200_000_000),
+                    managed_biguint!(500_000_000),
+                );
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &bidder_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.start_nft_auction(1);
+            },
+        )
+        .assert_user_error("health not low enough for liquidation");
+
+    // The collection floor drops to 300 EGLD, below the 250 EGLD debt / 0.7 threshold
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.add_collection(&CollectionParams {
+                    token: managed_token_id!(APE_TOKEN),
+                    floor: managed_biguint!(300),
+                    ltv: managed_biguint!(APE_LTV),
+                    max_borrow: managed_biguint!(MAX_BORROW),
+                    liquidation_threshold: managed_biguint!(LIQ_THRESOLD),
+                });
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &bidder_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                // 300 * 0.7 / 250 = 0.84
+                assert_eq!(
+                    sc.get_nft_loan_health_factor(1),
+                    managed_biguint!(840_000_000)
+                );
+                sc.start_nft_auction(1);
+                assert_eq!(sc.get_nft_auction_price(1), managed_biguint!(360));
+            },
+        )
+        .assert_ok();
+
+    // Halfway through, the price is 360 - (360 - 150) / 2
+    lending_setup.b_mock.set_block_timestamp(1_500);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&bidder_addr, EGLD_TOKEN_ID, &rust_biguint!(300));
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &bidder_addr,
+            &lending_setup.lending_pool_wrapper,
+            EGLD_TOKEN_ID,
+            0,
+            &rust_biguint!(300),
+            |sc| {
+                sc.bid_nft_auction(1);
+
+                assert!(sc.nft_auctions(1).is_empty());
+                assert_eq!(sc.nft_auction_surplus(1).get(), managed_biguint!(5));
+                assert_eq!(sc.nft_borrow_positions(1).get().amount, managed_biguint!(0));
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .check_esdt_balance(&bidder_addr, EGLD_TOKEN_ID, &rust_biguint!(45));
+    lending_setup.b_mock.check_nft_balance::<Empty>(
+        &bidder_addr,
+        APE_TOKEN,
+        1,
+        &rust_biguint!(1),
+        None,
+    );
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_egld_wrapper, |sc| {
+            assert_eq!(sc.reserves().get(), managed_biguint!(1_000));
+            assert_eq!(sc.borrowed_amount().get(), managed_biguint!(0));
+        })
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            DEBT_NFT_TOKEN,
+            1,
+            &rust_biguint!(1),
+            |sc| {
+                let surplus = sc.claim_nft_auction_surplus();
+                assert_eq!(surplus, managed_biguint!(5));
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .check_esdt_balance(&borrower_addr, EGLD_TOKEN_ID, &rust_biguint!(255));
+}
+
+#[test]
+fn nft_auction_shortfall_test() {
+    let _ = DebugApi::dummy();
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let borrower_addr = lending_setup.first_user_addr.clone();
+    let lender_addr = lending_setup.second_user_addr.clone();
+    let bidder_addr = lending_setup.third_user_addr.clone();
+    let owner_addr = lending_setup.owner_addr.clone();
+    let account_nonce = lending_setup.enter_market(&lender_addr);
+
+    lending_setup.set_price_feed(b"EGLD", DOLLAR_TICKER, EGLD_PRICE_IN_DOLLARS);
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&lender_addr, EGLD_TOKEN_ID, &rust_biguint!(1_000));
+    lending_setup.add_collateral(&lender_addr, EGLD_TOKEN_ID, 0, account_nonce, 1_000, 1_000);
+
+    lending_setup.b_mock.set_nft_balance(
+        &borrower_addr,
+        APE_TOKEN,
+        1,
+        &rust_biguint!(1),
+        &Empty {},
+    );
+    lending_setup.add_collections();
+    lending_setup.borrow_with_nft(
+        &borrower_addr,
+        EGLD_TOKEN_ID,
+        250,
+        750,
+        250,
+        1,
+        1_000_000_000,
+    );
+
+    // The collection floor drops to 200 EGLD, the auction ends at 100 EGLD
+    lending_setup.b_mock.set_block_timestamp(1_000);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_nft_auction_params(
+                    1_000,
+                    managed_biguint!(1_200_000_000),
+                    managed_biguint!(500_000_000),
+                );
+                sc.add_collection(&CollectionParams {
+                    token: managed_token_id!(APE_TOKEN),
+                    floor: managed_biguint!(200),
+                    ltv: managed_biguint!(APE_LTV),
+                    max_borrow: managed_biguint!(MAX_BORROW),
+                    liquidation_threshold: managed_biguint!(LIQ_THRESOLD),
+                });
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &bidder_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.start_nft_auction(1);
+            },
+        )
+        .assert_ok();
+
+    // The 150 EGLD the bid doesn't cover are written off instead of left unrepayable
+    lending_setup.b_mock.set_block_timestamp(2_000);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&bidder_addr, EGLD_TOKEN_ID, &rust_biguint!(100));
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &bidder_addr,
+            &lending_setup.lending_pool_wrapper,
+            EGLD_TOKEN_ID,
+            0,
+            &rust_biguint!(100),
+            |sc| {
+                sc.bid_nft_auction(1);
+
+                assert!(sc.nft_borrow_positions(1).is_empty());
+                assert_eq!(sc.nft_auction_surplus(1).get(), managed_biguint!(0));
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_egld_wrapper, |sc| {
+            assert_eq!(sc.reserves().get(), managed_biguint!(850));
+            assert_eq!(sc.borrowed_amount().get(), managed_biguint!(0));
+            assert_eq!(sc.scaled_borrowed_amount().get(), Ray::zero());
+        })
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            DEBT_NFT_TOKEN,
+            1,
+            &rust_biguint!(1),
+            |sc| {
+                sc.claim_nft_auction_surplus();
+            },
+        )
+        .assert_user_error("no NFT loan for this debt token");
+}
+
+#[test]
+fn nft_loan_in_six_decimals_token_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+    lending_setup.set_price_feed(b"EGLD", DOLLAR_TICKER, EGLD_PRICE_IN_DOLLARS);
+    lending_setup.set_price_feed(USDC_TICKER, DOLLAR_TICKER, USDC_PRICE_IN_DOLLARS);
+
+    // A 1 EGLD floor ($200) against a 100 USDC debt, USDC counted in 6 decimals
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.asset_decimals(&managed_token_id!(USDC_TOKEN_ID)).set(6);
+                sc.add_collection(&CollectionParams {
+                    token: managed_token_id!(APE_TOKEN),
+                    floor: managed_biguint!(1_000_000_000_000_000_000),
+                    ltv: managed_biguint!(APE_LTV),
+                    max_borrow: managed_biguint!(1_000_000_000_000_000_000),
+                    liquidation_threshold: managed_biguint!(LIQ_THRESOLD),
+                });
+                sc.nft_borrow_positions(1).set(BorrowPosition::new(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    managed_biguint!(100_000_000),
+                    0,
+                    1,
+                    Ray::from_amount(&managed_biguint!(100_000_000)),
+                    Option::Some(EsdtTokenPayment::new(
+                        managed_token_id!(APE_TOKEN),
+                        1,
+                        BigUint::from(1u32),
+                    )),
+                ));
+
+                // 200 * 0.7 / 100
+                assert_eq!(
+                    sc.get_nft_loan_health_factor(1),
+                    managed_biguint!(1_400_000_000)
+                );
+                assert_eq!(
+                    sc.convert_egld_to_tokens(
+                        &managed_biguint!(500_000_000_000_000_000),
+                        &managed_token_id!(USDC_TOKEN_ID)
+                    ),
+                    managed_biguint!(100_000_000)
+                );
+            },
+        )
+        .assert_ok();
+}
+
+#[test]
+fn collection_floor_from_oracle_test() {
+    let _ = DebugApi::dummy();
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+    lending_setup.add_collections();
+
+    lending_setup.b_mock.set_block_timestamp(1_000);
+    lending_setup.set_price_feed(APE_TICKER, b"EGLD", 500);
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let ape = managed_token_id!(APE_TOKEN);
+                // Without an oracle the owner-set floor is used
+                assert_eq!(sc.get_collection_floor(&ape), managed_biguint!(APE_FLOOR));
+
+                sc.set_collection_oracle(
+                    ape.clone(),
+                    managed_buffer!(APE_TICKER),
+                    100,
+                    managed_biguint!(BP / 5),
+                );
+                // 5.00 EGLD
+                assert_eq!(
+                    sc.get_collection_floor(&ape),
+                    managed_biguint!(5_000_000_000_000_000_000)
+                );
+            },
+        )
+        .assert_ok();
+
+    // Stale feed
+    lending_setup.b_mock.set_block_timestamp(1_200);
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            let ape = managed_token_id!(APE_TOKEN);
+            assert_eq!(sc.get_collection_floor(&ape), managed_biguint!(APE_FLOOR));
+        })
+        .assert_ok();
+
+    // Endpoints reading the floor keep it as the reference, views leave storage alone
+    lending_setup.set_price_feed(APE_TICKER, b"EGLD", 500);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let ape = managed_token_id!(APE_TOKEN);
+                assert!(sc.collection_last_floor(&ape).is_empty());
+
+                sc.update_collection_floor(&ape);
+                assert_eq!(
+                    sc.collection_last_floor(&ape).get(),
+                    managed_biguint!(5_000_000_000_000_000_000)
+                );
+            },
+        )
+        .assert_ok();
+
+    // Doubling the floor is outside the 20% deviation bound
+    lending_setup.set_price_feed(APE_TICKER, b"EGLD", 1_000);
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            let ape = managed_token_id!(APE_TOKEN);
+            assert_eq!(sc.get_collection_floor(&ape), managed_biguint!(APE_FLOOR));
+        })
+        .assert_ok();
+
+    lending_setup.set_price_feed(APE_TICKER, b"EGLD", 550);
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            let ape = managed_token_id!(APE_TOKEN);
+            assert_eq!(
+                sc.get_collection_floor(&ape),
+                managed_biguint!(5_500_000_000_000_000_000)
+            );
+        })
+        .assert_ok();
+
+    // Once the reference is older than the staleness window, a lasting move is accepted
+    lending_setup.b_mock.set_block_timestamp(1_301);
+    lending_setup.set_price_feed(APE_TICKER, b"EGLD", 1_000);
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            let ape = managed_token_id!(APE_TOKEN);
+            assert_eq!(
+                sc.get_collection_floor(&ape),
+                managed_biguint!(5_000_000_000_000_000_000) * 2u64
+            );
+        })
+        .assert_ok();
+}
+
+#[test]
+fn collection_borrow_caps_test() {
+    let _ = DebugApi::dummy();
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let borrower_addr = lending_setup.first_user_addr.clone();
+    let lender_addr = lending_setup.second_user_addr.clone();
+    let owner_addr = lending_setup.owner_addr.clone();
+    let account_nonce = lending_setup.enter_market(&lender_addr);
+
+    lending_setup.set_price_feed(b"EGLD", DOLLAR_TICKER, EGLD_PRICE_IN_DOLLARS);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&lender_addr, EGLD_TOKEN_ID, &rust_biguint!(1_000));
+    lending_setup.add_collateral(&lender_addr, EGLD_TOKEN_ID, 0, account_nonce, 1_000, 1_000);
+    for nonce in 1..=2 {
+        lending_setup.b_mock.set_nft_balance(
+            &borrower_addr,
+            APE_TOKEN,
+            nonce,
+            &rust_biguint!(1),
+            &Empty {},
+        );
+    }
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.add_collection(&CollectionParams {
+                    token: managed_token_id!(APE_TOKEN),
+                    floor: managed_biguint!(APE_FLOOR),
+                    ltv: managed_biguint!(APE_LTV),
+                    max_borrow: managed_biguint!(200),
+                    liquidation_threshold: managed_biguint!(LIQ_THRESOLD),
+                });
+                sc.set_collection_borrow_cap(
+                    managed_token_id!(APE_TOKEN),
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    managed_biguint!(300),
+                );
+            },
+        )
+        .assert_ok();
+
+    // A single NFT can not back more than max_borrow
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            APE_TOKEN,
+            1,
+            &rust_biguint!(1),
+            |sc| {
+                sc.borrow_with_nfts(managed_token_id!(EGLD_TOKEN_ID), managed_biguint!(250));
+            },
+        )
+        .assert_user_error("Not enough collateral available for this loan!");
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            APE_TOKEN,
+            1,
+            &rust_biguint!(1),
+            |sc| {
+                sc.borrow_with_nfts(managed_token_id!(EGLD_TOKEN_ID), managed_biguint!(150));
+
+                let ape = managed_token_id!(APE_TOKEN);
+                let egld = managed_token_id!(EGLD_TOKEN_ID);
+                assert_eq!(
+                    sc.get_collection_remaining_capacity(&ape, &egld)
+                        .into_option(),
+                    Some(managed_biguint!(150))
+                );
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            APE_TOKEN,
+            2,
+            &rust_biguint!(1),
+            |sc| {
+                sc.borrow_with_nfts(managed_token_id!(EGLD_TOKEN_ID), managed_biguint!(180));
+            },
+        )
+        .assert_user_error("collection borrow cap exceeded");
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &borrower_addr,
+            &lending_setup.lending_pool_wrapper,
+            APE_TOKEN,
+            2,
+            &rust_biguint!(1),
+            |sc| {
+                sc.borrow_with_nfts(managed_token_id!(EGLD_TOKEN_ID), managed_biguint!(150));
+
+                let ape = managed_token_id!(APE_TOKEN);
+                let egld = managed_token_id!(EGLD_TOKEN_ID);
+                assert_eq!(
+                    sc.get_collection_remaining_capacity(&ape, &egld)
+                        .into_option(),
+                    Some(managed_biguint!(0))
+                );
+                // The cap only covers EGLD borrows against the collection
+                let usdc = managed_token_id!(USDC_TOKEN_ID);
+                assert!(sc
+                    .get_collection_remaining_capacity(&ape, &usdc)
+                    .into_option()
+                    .is_none());
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .check_esdt_balance(&borrower_addr, EGLD_TOKEN_ID, &rust_biguint!(300));
+}
+
+#[test]
+fn asset_caps_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+    let supplier_addr = lending_setup.first_user_addr.clone();
+    let borrower_addr = lending_setup.second_user_addr.clone();
+    let supplier_nonce = lending_setup.enter_market(&supplier_addr);
+    let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_asset_supply_cap(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    OptionalValue::Some(managed_biguint!(1_500)),
+                );
+                sc.set_asset_borrow_cap(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    OptionalValue::Some(managed_biguint!(500)),
+                );
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(1_600));
+    lending_setup.add_collateral(
+        &supplier_addr,
+        USDC_TOKEN_ID,
+        0,
+        supplier_nonce,
+        1_000,
+        1_000,
+    );
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &supplier_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            USDC_TOKEN_ID,
+            0,
+            &rust_biguint!(600),
+            |sc| {
+                sc.add_collateral(DepositPosition::new(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    managed_biguint!(1_000),
+                    supplier_nonce,
+                    1,
+                    Ray::from_amount(&managed_biguint!(1_000)),
+                ));
+            },
+        )
+        .assert_user_error("supply cap exceeded");
+
+    lending_setup.borrow(
+        &borrower_addr,
+        USDC_TOKEN_ID,
+        0,
+        borrower_nonce,
+        400,
+        600,
+        400,
+        1,
+        1_000_000_000,
+    );
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &borrower_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            ACCOUNT_TOKEN,
+            borrower_nonce,
+            &rust_biguint!(0),
+            |sc| {
+                sc.borrow(
+                    managed_address!(&borrower_addr),
+                    managed_biguint!(200),
+                    BorrowPosition::new(
+                        managed_token_id!(USDC_TOKEN_ID),
+                        managed_biguint!(400),
+                        borrower_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(400)),
+                        Option::None,
+                    ),
+                );
+            },
+        )
+        .assert_user_error("borrow cap exceeded");
+
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
+            assert_eq!(
+                sc.get_supply_cap_headroom().into_option(),
+                Some(managed_biguint!(500))
+            );
+            assert_eq!(
+                sc.get_borrow_cap_headroom().into_option(),
+                Some(managed_biguint!(100))
+            );
+        })
+        .assert_ok();
+
+    // Without caps there is no headroom to report
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_asset_supply_cap(managed_token_id!(USDC_TOKEN_ID), OptionalValue::None);
+                sc.set_asset_borrow_cap(managed_token_id!(USDC_TOKEN_ID), OptionalValue::None);
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
+            assert!(sc.get_supply_cap_headroom().into_option().is_none());
+            assert!(sc.get_borrow_cap_headroom().into_option().is_none());
+        })
+        .assert_ok();
+}
+
+#[test]
+fn pause_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+    let user_addr = lending_setup.first_user_addr.clone();
+    let guardian_addr = lending_setup.third_user_addr.clone();
+    let account_nonce = lending_setup.enter_market(&user_addr);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&user_addr, USDC_TOKEN_ID, &rust_biguint!(100));
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&user_addr, EGLD_TOKEN_ID, &rust_biguint!(100));
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.grant_role(Role::PauseGuardian, managed_address!(&guardian_addr));
+            },
+        )
+        .assert_ok();
+
+    // Only the owner and the pause guardians can pause
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.pause(MultiValueEncoded::new());
+            },
+        )
+        .assert_user_error("caller does not have the required role");
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &guardian_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let mut actions = MultiValueEncoded::new();
+                actions.push(PausableAction::Borrow);
+                sc.pause(actions);
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &lending_setup.lending_pool_wrapper,
+            ACCOUNT_TOKEN,
+            account_nonce,
+            &rust_biguint!(1),
+            |sc| {
+                sc.borrow(managed_token_id!(USDC_TOKEN_ID), managed_biguint!(100));
+            },
+        )
+        .assert_user_error("action is paused");
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.unpause(MultiValueEncoded::new());
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            assert!(!sc.is_paused(PausableAction::Borrow));
+        })
+        .assert_ok();
+
+    // Pausing an asset stops its pool only
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &guardian_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let mut actions = MultiValueEncoded::new();
+                actions.push(PausableAction::Supply);
+                sc.pause_asset(managed_token_id!(USDC_TOKEN_ID), actions);
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
+            assert!(sc.is_paused(PausableAction::Supply));
+            assert!(!sc.is_paused(PausableAction::Withdraw));
+        })
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            USDC_TOKEN_ID,
+            0,
+            &rust_biguint!(100),
+            |sc| {
+                sc.add_collateral(DepositPosition::new(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    managed_biguint!(0),
+                    account_nonce,
+                    1,
+                    Ray::zero(),
+                ));
+            },
+        )
+        .assert_user_error("action is paused");
+    lending_setup.add_collateral(&user_addr, EGLD_TOKEN_ID, 0, account_nonce, 100, 100);
+}
+
+#[test]
+fn roles_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+    let risk_admin_addr = lending_setup.first_user_addr.clone();
+    let listing_admin_addr = lending_setup.second_user_addr.clone();
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.grant_role(Role::RiskAdmin, managed_address!(&risk_admin_addr));
+                sc.grant_role(Role::ListingAdmin, managed_address!(&listing_admin_addr));
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &risk_admin_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_asset_loan_to_value(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    managed_biguint!(BP / 2),
+                );
+            },
+        )
+        .assert_ok();
+
+    // Roles do not overlap
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &listing_admin_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_asset_loan_to_value(managed_token_id!(USDC_TOKEN_ID), managed_biguint!(BP));
+            },
+        )
+        .assert_user_error("caller does not have the required role");
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &risk_admin_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.add_collection(&CollectionParams {
+                    token: managed_token_id!(APE_TOKEN),
+                    floor: managed_biguint!(APE_FLOOR),
+                    ltv: managed_biguint!(APE_LTV),
+                    max_borrow: managed_biguint!(MAX_BORROW),
+                    liquidation_threshold: managed_biguint!(LIQ_THRESOLD),
+                });
+            },
+        )
+        .assert_user_error("caller does not have the required role");
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.revoke_role(Role::RiskAdmin, managed_address!(&risk_admin_addr));
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            assert!(!sc.has_role(Role::RiskAdmin, managed_address!(&risk_admin_addr)));
+            assert!(sc.has_role(Role::ListingAdmin, managed_address!(&listing_admin_addr)));
+            assert!(sc.has_role(Role::RiskAdmin, managed_address!(&owner_addr)));
+            assert_eq!(
+                sc.asset_loan_to_value(&managed_token_id!(USDC_TOKEN_ID))
+                    .get(),
+                managed_biguint!(BP / 2)
+            );
+        })
+        .assert_ok();
+}
+
+#[test]
+fn timelock_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+
+    lending_setup.b_mock.set_block_timestamp(1_000);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_timelock_delay(86_400);
+            },
+        )
+        .assert_ok();
+
+    // Timelocked parameters can no longer be set directly
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_asset_loan_to_value(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    managed_biguint!(BP / 2),
+                );
+            },
+        )
+        .assert_user_error("parameter changes have to go through the timelock");
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_max_price_staleness(100);
+            },
+        )
+        .assert_user_error("parameter changes have to go through the timelock");
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_asset_isolated(managed_token_id!(EGLD_TOKEN_ID), true);
+            },
+        )
+        .assert_user_error("parameter changes have to go through the timelock");
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_asset_e_mode_category(managed_token_id!(USDC_TOKEN_ID), 0);
+            },
+        )
+        .assert_user_error("parameter changes have to go through the timelock");
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_timelock_delay(0);
+            },
+        )
+        .assert_user_error("the timelock delay can only be lowered through the timelock");
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let change_id = sc.queue_change(TimelockedChange::AssetLoanToValue {
+                    asset: managed_token_id!(USDC_TOKEN_ID),
+                    loan_to_value: managed_biguint!(BP / 2),
+                });
+                assert_eq!(change_id, 1);
+                assert_eq!(sc.queued_changes().get(&change_id).unwrap().eta, 87_400);
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.execute_change(1);
+            },
+        )
+        .assert_user_error("change is still timelocked");
+
+    lending_setup.b_mock.set_block_timestamp(87_400);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.execute_change(1);
+            },
+        )
+        .assert_ok();
+
+    // A cancelled change can not be executed
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let change_id = sc.queue_change(TimelockedChange::AssetLiquidationBonus {
+                    asset: managed_token_id!(USDC_TOKEN_ID),
+                    liquidation_bonus: managed_biguint!(0),
+                });
+                sc.cancel_change(change_id);
+            },
+        )
+        .assert_ok();
+    lending_setup.b_mock.set_block_timestamp(200_000);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.execute_change(2);
+            },
+        )
+        .assert_user_error("no queued change with this id");
+
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            assert!(sc.queued_changes().is_empty());
+            assert_eq!(
+                sc.asset_loan_to_value(&managed_token_id!(USDC_TOKEN_ID))
+                    .get(),
+                managed_biguint!(BP / 2)
+            );
+            assert!(
+                sc.asset_liquidation_bonus(&managed_token_id!(USDC_TOKEN_ID))
+                    .get()
+                    > 0
+            );
+        })
+        .assert_ok();
+
+    // Oracle, e-mode and isolation settings go through the same queue
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.queue_change(TimelockedChange::MaxPriceStaleness { max_staleness: 100 });
+                sc.queue_change(TimelockedChange::EModeCategory {
+                    category_id: 1,
+                    loan_to_value: managed_biguint!(900_000_000),
+                    liquidation_threshold: managed_biguint!(950_000_000),
+                    liquidation_bonus: managed_biguint!(10_000_000),
+                    price_source: None,
+                });
+                sc.queue_change(TimelockedChange::AssetEModeCategory {
+                    asset: managed_token_id!(USDC_TOKEN_ID),
+                    category_id: 1,
+                });
+                sc.queue_change(TimelockedChange::AssetIsolated {
+                    asset: managed_token_id!(EGLD_TOKEN_ID),
+                    isolated: true,
+                });
+            },
+        )
+        .assert_ok();
+    lending_setup.b_mock.set_block_timestamp(286_400);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                for change_id in 3..=6 {
+                    sc.execute_change(change_id);
+                }
+
+                assert_eq!(sc.max_price_staleness().get(), 100);
+                assert_eq!(
+                    sc.asset_e_mode_category(&managed_token_id!(USDC_TOKEN_ID))
+                        .get(),
+                    1
+                );
+                assert!(sc
+                    .isolated_assets()
+                    .contains(&managed_token_id!(EGLD_TOKEN_ID)));
+            },
+        )
+        .assert_ok();
+}
+
+#[test]
+fn stale_price_rejected_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let user_addr = lending_setup.first_user_addr.clone();
+    let owner_addr = lending_setup.owner_addr.clone();
+    let account_nonce = lending_setup.enter_market(&user_addr);
+
+    lending_setup.b_mock.set_block_timestamp(1_000);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_max_price_staleness(100);
+                sc.deposit_positions(account_nonce).insert(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    DepositPosition::new(
+                        managed_token_id!(EGLD_TOKEN_ID),
+                        managed_biguint!(1_000),
+                        account_nonce,
+                        1,
+                        Ray::from_amount(&managed_biguint!(1_000)),
+                    ),
+                );
+            },
+        )
+        .assert_ok();
+
+    // The WEGLD feed was last updated at genesis
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            sc.get_total_collateral_in_dollars(account_nonce);
+        })
+        .assert_user_error("price feed unavailable or stale");
+
+    lending_setup.set_price_feed(EGLD_TICKER, DOLLAR_TICKER, EGLD_PRICE_IN_DOLLARS);
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            // 1_000 * $200, with 18 decimals
+            assert_eq!(
+                sc.get_total_collateral_in_dollars(account_nonce),
+                BigUint::from(200_000u64) * BigUint::from(10u64).pow(18)
+            );
+        })
+        .assert_ok();
+
+    // Reads compare against the recorded round without recording it, endpoints record it
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let aggregator = sc.price_aggregator_addresses().iter().next().unwrap();
+                let last_round = sc.last_price_round(
+                    &aggregator,
+                    &managed_buffer!(EGLD_TICKER),
+                    &managed_buffer!(DOLLAR_TICKER),
+                );
+
+                sc.get_total_collateral_in_dollars(account_nonce);
+                assert!(last_round.is_empty());
+
+                sc.update_account_price_rounds(account_nonce);
+                assert!(!last_round.is_empty());
+            },
+        )
+        .assert_ok();
+
+    // Once recorded, an older round is refused
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.price_aggregator_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.latest_price_feed_round(
+                    &managed_buffer!(EGLD_TICKER),
+                    &managed_buffer!(DOLLAR_TICKER),
+                )
+                .update(|round| *round -= 1);
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            sc.get_total_collateral_in_dollars(account_nonce);
+        })
+        .assert_user_error("price feed unavailable or stale");
+}
+
+#[test]
+fn default_price_staleness_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_max_price_staleness(0);
+            },
+        )
+        .assert_user_error("max price staleness can not be zero");
+
+    // Without a configured max age the genesis feed expires after an hour
+    lending_setup.b_mock.set_block_timestamp(3_600);
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            assert_eq!(sc.get_max_price_staleness(), 3_600u64);
+            sc.get_token_price_data(&managed_token_id!(EGLD_TOKEN_ID));
+        })
+        .assert_ok();
+
+    lending_setup.b_mock.set_block_timestamp(3_601);
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            sc.get_token_price_data(&managed_token_id!(EGLD_TOKEN_ID));
+        })
+        .assert_user_error("price feed unavailable or stale");
+}
+
+#[test]
+fn price_median_and_fallback_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+
+    lending_setup.b_mock.set_block_timestamp(1_000);
+    let mut aggregators = Vec::new();
+    for price in [21_000u64, 30_000, 25_000] {
+        let aggregator_wrapper = lending_setup.b_mock.create_sc_account(
+            &rust_biguint!(0),
+            Some(&owner_addr),
+            aggregator_mock::contract_obj,
+            PRICE_AGGREGATOR_WASM_PATH,
+        );
+        lending_setup
+            .b_mock
+            .execute_tx(&owner_addr, &aggregator_wrapper, &rust_biguint!(0), |sc| {
+                sc.set_latest_price_feed(
+                    managed_buffer!(EGLD_TICKER),
+                    managed_buffer!(DOLLAR_TICKER),
+                    managed_biguint!(price),
+                );
+            })
+            .assert_ok();
+        aggregators.push(aggregator_wrapper);
+    }
+    lending_setup.set_price_feed(EGLD_TICKER, DOLLAR_TICKER, EGLD_PRICE_IN_DOLLARS);
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.add_price_aggregator_address(managed_address!(aggregators[0].address_ref()));
+                sc.add_price_aggregator_address(managed_address!(aggregators[1].address_ref()));
+                sc.set_max_price_staleness(100);
+
+                // median of $200, $210 and $300
+                let price_data = sc.get_token_price_data(&managed_token_id!(EGLD_TOKEN_ID));
+                assert_eq!(
+                    price_data.price,
+                    BigUint::from(21_000u64) * BigUint::from(10u64).pow(16)
+                );
+            },
+        )
+        .assert_ok();
+
+    // Only the first aggregator is refreshed, the other two are now stale
+    lending_setup.b_mock.set_block_timestamp(1_200);
+    lending_setup.set_price_feed(EGLD_TICKER, DOLLAR_TICKER, EGLD_PRICE_IN_DOLLARS);
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            sc.get_token_price_data(&managed_token_id!(EGLD_TOKEN_ID));
+        })
+        .assert_user_error("price feed unavailable or stale");
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_fallback_price_source(managed_address!(aggregators[2].address_ref()));
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            // the fallback feed is stale as well
+            sc.get_token_price_data(&managed_token_id!(EGLD_TOKEN_ID));
+        })
+        .assert_user_error("price feed unavailable or stale");
+
+    lending_setup
+        .b_mock
+        .execute_tx(&owner_addr, &aggregators[2], &rust_biguint!(0), |sc| {
+            sc.set_latest_price_feed(
+                managed_buffer!(EGLD_TICKER),
+                managed_buffer!(DOLLAR_TICKER),
+                managed_biguint!(25_000),
+            );
+        })
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            // two feeds missing, more than tolerated: the fallback answers
+            let price_data = sc.get_token_price_data(&managed_token_id!(EGLD_TOKEN_ID));
+            assert_eq!(
+                price_data.price,
+                BigUint::from(25_000u64) * BigUint::from(10u64).pow(16)
+            );
+        })
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_max_missing_price_feeds(2);
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.lending_pool_wrapper, |sc| {
+            let price_data = sc.get_token_price_data(&managed_token_id!(EGLD_TOKEN_ID));
+            assert_eq!(
+                price_data.price,
+                BigUint::from(EGLD_PRICE_IN_DOLLARS) * BigUint::from(10u64).pow(16)
+            );
+        })
+        .assert_ok();
+}
+
+#[test]
+fn legacy_aggregator_address_migrated_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = lending_setup.owner_addr.clone();
+
+    // A deployment that still has its aggregator under the old single-address key is upgraded
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let aggregator = sc.price_aggregator_addresses().iter().next().unwrap();
+                sc.price_aggregator_addresses().swap_remove(&aggregator);
+                sc.legacy_price_aggregator_address().set(&aggregator);
+
+                sc.init(sc.liq_pool_template_address().get());
+
+                assert!(sc.legacy_price_aggregator_address().is_empty());
+                assert_eq!(sc.price_aggregator_addresses().len(), 1);
+                assert!(sc.price_aggregator_addresses().contains(&aggregator));
+            },
+        )
+        .assert_ok();
+}
+
+#[test]
+fn compounded_interest_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let supplier_addr = lending_setup.first_user_addr.clone();
+    let borrower_addr = lending_setup.second_user_addr.clone();
+    let supplier_nonce = lending_setup.enter_market(&supplier_addr);
+    let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+
+    lending_setup.set_block_year(3);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(2_000));
+    lending_setup.add_collateral(
+        &supplier_addr,
+        USDC_TOKEN_ID,
+        0,
+        supplier_nonce,
+        2_000,
+        2_000,
+    );
+    lending_setup.borrow(
+        &borrower_addr,
+        USDC_TOKEN_ID,
+        0,
+        borrower_nonce,
+        1_000,
+        1_000,
+        1_000,
+        3,
+        1_000_000_000,
+    );
+
+    // 2.5% a year over two years: 5% simple, e^0.05 - 1 compounded
+    lending_setup.set_block_year(5);
+
+    // The view reports the indexes the next update will store, without storing them
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
+            let (borrow_index, supply_index) = sc.get_current_indexes().into_tuple();
+
+            assert_eq!(borrow_index.to_bp(), 1_051_270_833u64);
+            assert_eq!(
+                Ray::from_amount(&managed_biguint!(2_000))
+                    .mul(&supply_index)
+                    .to_amount(),
+                2_046u64
+            );
+            assert_eq!(sc.borrow_index().get().to_bp(), BP);
+            assert_eq!(
+                sc.borrow_index_last_update_timestamp().get(),
+                3 * SECONDS_PER_YEAR
+            );
+        })
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &lending_setup.owner_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.update_interest_indexes();
+
+                assert_eq!(sc.borrow_index().get().to_bp(), 1_051_270_833u64);
+                assert_eq!(sc.rewards_reserves().get(), 46u64);
+                assert_eq!(sc.protocol_revenue().get(), 5u64);
+
+                // The supplier's scaled balance covers the interest net of the reserve factor
+                assert_eq!(
+                    sc.get_deposit_amount(&Ray::from_amount(&managed_biguint!(2_000))),
+                    2_046u64
+                );
+                assert_eq!(
+                    sc.borrow_index_last_update_timestamp().get(),
+                    5 * SECONDS_PER_YEAR
+                );
+            },
+        )
+        .assert_ok();
+
+    // No time passed, nothing accrues
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &lending_setup.owner_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.update_interest_indexes();
+
+                assert_eq!(sc.borrow_index().get().to_bp(), 1_051_270_833u64);
+            },
+        )
+        .assert_ok();
+}
+
+#[test]
+fn withdraw_with_accrued_interest_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let supplier_addr = lending_setup.first_user_addr.clone();
+    let borrower_addr = lending_setup.second_user_addr.clone();
+    let supplier_nonce = lending_setup.enter_market(&supplier_addr);
+    let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+
+    lending_setup.set_block_year(3);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(2_000));
+    lending_setup.add_collateral(
+        &supplier_addr,
+        USDC_TOKEN_ID,
+        0,
+        supplier_nonce,
+        2_000,
+        2_000,
+    );
+    lending_setup.borrow(
+        &borrower_addr,
+        USDC_TOKEN_ID,
+        0,
+        borrower_nonce,
+        1_000,
+        1_000,
+        1_000,
+        3,
+        1_000_000_000,
+    );
+
+    lending_setup.set_block_year(5);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&borrower_addr, USDC_TOKEN_ID, &rust_biguint!(1_100));
+    lending_setup.repay(
+        &borrower_addr,
+        USDC_TOKEN_ID,
+        1_000,
+        borrower_nonce,
+        1_100,
+        2_051,
+        0,
+        3,
+        1_000_000_000,
+    );
+
+    // The last supplier's position is refreshed to include its interest, more than the
+    // principal the pool tracks, and withdrawn in full; only the protocol revenue stays
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &lending_setup.owner_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let deposit_position = sc.update_collateral_with_interest(DepositPosition::new(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    managed_biguint!(2_000),
+                    supplier_nonce,
+                    3,
+                    Ray::from_amount(&managed_biguint!(2_000)),
+                ));
+                assert_eq!(deposit_position.amount, managed_biguint!(2_046));
+
+                let amount = deposit_position.amount.clone();
+                let deposit_position = sc.remove_collateral(
+                    managed_address!(&supplier_addr),
+                    amount,
+                    deposit_position,
+                );
+                assert_eq!(deposit_position.amount, managed_biguint!(0));
+                assert_eq!(sc.supplied_amount().get(), managed_biguint!(0));
+                assert!(sc.scaled_supplied_amount().get().is_zero());
+                assert_eq!(sc.reserves().get(), managed_biguint!(5));
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(2_046));
+}
+
+#[test]
+fn pool_upgrade_keeps_accounting_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let supplier_addr = lending_setup.first_user_addr.clone();
+    let borrower_addr = lending_setup.second_user_addr.clone();
+    let supplier_nonce = lending_setup.enter_market(&supplier_addr);
+    let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+
+    lending_setup.set_block_year(3);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(2_000));
+    lending_setup.add_collateral(
+        &supplier_addr,
+        USDC_TOKEN_ID,
+        0,
+        supplier_nonce,
+        2_000,
+        2_000,
+    );
+    lending_setup.borrow(
+        &borrower_addr,
+        USDC_TOKEN_ID,
+        0,
+        borrower_nonce,
+        1_000,
+        1_000,
+        1_000,
+        3,
+        1_000_000_000,
+    );
+
+    // The upgrade runs init again, with a higher base rate; the two years of interest accrue
+    // under the old params, as in compounded_interest_test, and nothing is reset
+    lending_setup.set_block_year(5);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &lending_setup.owner_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.init(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    managed_biguint!(R_BASE + 10_000_000),
+                    managed_biguint!(R_SLOPE1),
+                    managed_biguint!(R_SLOPE2),
+                    managed_biguint!(U_OPTIMAL),
+                    managed_biguint!(RESERVE_FACTOR),
+                    managed_biguint!(LIQ_THRESOLD),
+                    OptionalValue::None,
+                );
+
+                assert_eq!(sc.borrow_index().get().to_bp(), 1_051_270_833u64);
+                assert_eq!(
+                    sc.get_deposit_amount(&Ray::from_amount(&managed_biguint!(2_000))),
+                    2_046u64
+                );
+                assert_eq!(sc.rewards_reserves().get(), 46u64);
+                assert_eq!(sc.protocol_revenue().get(), 5u64);
+                assert_eq!(
+                    sc.scaled_supplied_amount().get(),
+                    Ray::from_amount(&managed_biguint!(2_000))
+                );
+                assert_eq!(
+                    sc.scaled_borrowed_amount().get(),
+                    Ray::from_amount(&managed_biguint!(1_000))
+                );
+                assert_eq!(
+                    sc.borrow_index_last_update_timestamp().get(),
+                    5 * SECONDS_PER_YEAR
+                );
+                assert_eq!(sc.pool_params().get().r_base, R_BASE + 10_000_000);
+            },
+        )
+        .assert_ok();
+
+    // An upgrade can't repurpose the pool for another asset
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &lending_setup.owner_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.init(
+                    managed_token_id!(EGLD_TOKEN_ID),
+                    managed_biguint!(R_BASE),
+                    managed_biguint!(R_SLOPE1),
+                    managed_biguint!(R_SLOPE2),
+                    managed_biguint!(U_OPTIMAL),
+                    managed_biguint!(RESERVE_FACTOR),
+                    managed_biguint!(LIQ_THRESOLD),
+                    OptionalValue::None,
+                );
+            },
+        )
+        .assert_user_error("pool asset can not change");
+}
+
+#[test]
+fn interest_rate_strategies_test() {
+    let mut lending_setup = LendingSetup::deploy_lending(
+        lending_pool::contract_obj,
+        liquidity_pool::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let supplier_addr = lending_setup.first_user_addr.clone();
+    let borrower_addr = lending_setup.second_user_addr.clone();
+    let supplier_nonce = lending_setup.enter_market(&supplier_addr);
+    let borrower_nonce = lending_setup.enter_market(&borrower_addr);
 
+    // 50% utilisation
+    lending_setup.set_block_year(3);
     lending_setup
         .b_mock
-        .set_esdt_balance(&user_addr, USDC_TOKEN_ID, &rust_biguint!(1_000));
-
-    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 0, account_nonce, 1_000, 1_000);
-    lending_setup.borrow(
-        &user_addr,
+        .set_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(2_000));
+    lending_setup.add_collateral(
+        &supplier_addr,
         USDC_TOKEN_ID,
         0,
-        account_nonce,
-        250,
-        750,
-        250,
-        1,
-        1_000_000_000,
+        supplier_nonce,
+        2_000,
+        2_000,
     );
-    lending_setup.repay(
-        &user_addr,
+    lending_setup.borrow(
+        &borrower_addr,
         USDC_TOKEN_ID,
-        250,
-        account_nonce,
-        100,
-        850,
-        150,
-        1,
+        0,
+        borrower_nonce,
+        1_000,
+        1_000,
+        1_000,
+        3,
         1_000_000_000,
     );
-}
-
-#[test]
-fn add_collateral_test() {
-    let mut lending_setup = LendingSetup::deploy_lending(
-        lending_pool::contract_obj,
-        liquidity_pool::contract_obj,
-        aggregator_mock::contract_obj,
-    );
 
-    let user_addr = lending_setup.first_user_addr.clone();
-    let account_nonce = lending_setup.enter_market(&user_addr);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &lending_setup.owner_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                assert_eq!(sc.get_borrow_rate(), 25_000_000u64);
+
+                sc.interest_rate_strategy()
+                    .set(InterestRateStrategy::Fixed {
+                        rate: managed_biguint!(30_000_000),
+                    });
+                assert_eq!(sc.get_borrow_rate(), 30_000_000u64);
+
+                let mut kinks = ManagedVec::new();
+                kinks.push(RateKink::new(
+                    managed_biguint!(400_000_000),
+                    managed_biguint!(30_000_000),
+                ));
+                kinks.push(RateKink::new(
+                    managed_biguint!(900_000_000),
+                    managed_biguint!(80_000_000),
+                ));
+                kinks.push(RateKink::new(managed_biguint!(BP), managed_biguint!(BP)));
+                sc.interest_rate_strategy()
+                    .set(InterestRateStrategy::MultiKink {
+                        base_rate: managed_biguint!(10_000_000),
+                        kinks,
+                    });
+                assert_eq!(sc.get_borrow_rate(), 40_000_000u64);
+
+                // 30% below target: 20% - 0.5 * 30%
+                sc.interest_rate_strategy().set(InterestRateStrategy::Pid {
+                    base_rate: managed_biguint!(200_000_000),
+                    k_p: managed_biguint!(500_000_000),
+                    k_i: managed_biguint!(100_000_000),
+                    k_d: managed_biguint!(0),
+                    min_rate: managed_biguint!(5_000_000),
+                    max_rate: managed_biguint!(BP),
+                });
+                assert_eq!(sc.get_borrow_rate(), 50_000_000u64);
+            },
+        )
+        .assert_ok();
 
+    // A year below target lowers the rate by k_i * 30%
+    lending_setup.set_block_year(4);
     lending_setup
         .b_mock
-        .set_esdt_balance(&user_addr, USDC_TOKEN_ID, &rust_biguint!(1_000));
+        .execute_tx(
+            &lending_setup.owner_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.update_interest_indexes();
+
+                assert_eq!(
+                    sc.rate_controller_state().get().integral,
+                    BigInt::from(-300_000_000i64)
+                );
+                assert_eq!(sc.get_borrow_rate(), 20_000_000u64);
+            },
+        )
+        .assert_ok();
 
-    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 0, account_nonce, 500, 500);
-    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 500, account_nonce, 200, 700);
-    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 700, account_nonce, 150, 850);
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &lending_setup.owner_addr,
+            &lending_setup.liquidity_pool_usdc_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let mut kinks = ManagedVec::new();
+                kinks.push(RateKink::new(
+                    managed_biguint!(900_000_000),
+                    managed_biguint!(80_000_000),
+                ));
+                sc.require_valid_rate_strategy(&InterestRateStrategy::MultiKink {
+                    base_rate: managed_biguint!(10_000_000),
+                    kinks,
+                });
+            },
+        )
+        .assert_user_error("last rate kink must be at full utilisation");
 }
 
 #[test]
-fn remove_collateral_test() {
+fn sweep_protocol_revenue_test() {
     let mut lending_setup = LendingSetup::deploy_lending(
         lending_pool::contract_obj,
         liquidity_pool::contract_obj,
         aggregator_mock::contract_obj,
     );
+    let supplier_addr = lending_setup.first_user_addr.clone();
+    let borrower_addr = lending_setup.second_user_addr.clone();
+    let treasury_addr = lending_setup.third_user_addr.clone();
+    let supplier_nonce = lending_setup.enter_market(&supplier_addr);
+    let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+    lending_setup.use_simple_interest();
 
-    let user_addr = lending_setup.first_user_addr.clone();
-    let account_nonce = lending_setup.enter_market(&user_addr);
-
+    lending_setup.set_block_year(3);
     lending_setup
         .b_mock
-        .set_esdt_balance(&user_addr, USDC_TOKEN_ID, &rust_biguint!(1_000));
+        .set_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(2_000));
+    lending_setup.add_collateral(
+        &supplier_addr,
+        USDC_TOKEN_ID,
+        0,
+        supplier_nonce,
+        2_000,
+        2_000,
+    );
+    lending_setup.borrow(
+        &borrower_addr,
+        USDC_TOKEN_ID,
+        0,
+        borrower_nonce,
+        1_000,
+        1_000,
+        1_000,
+        3,
+        1_000_000_000,
+    );
 
-    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 0, account_nonce, 700, 700);
-    lending_setup.remove_collateral(
-        &user_addr,
+    lending_setup.set_block_year(5);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&borrower_addr, USDC_TOKEN_ID, &rust_biguint!(1_050));
+    lending_setup.repay(
+        &borrower_addr,
         USDC_TOKEN_ID,
-        700,
-        account_nonce,
-        300,
-        400,
-        1,
+        1_000,
+        borrower_nonce,
+        1_050,
+        2_050,
+        0,
+        3,
         1_000_000_000,
     );
-    lending_setup.add_collateral(&user_addr, USDC_TOKEN_ID, 400, account_nonce, 150, 550);
+
+    // 10% of the 50 interest is the protocol's
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
+            assert_eq!(sc.protocol_revenue().get(), 5u64);
+            assert_eq!(sc.rewards_reserves().get(), 45u64);
+        })
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &lending_setup.owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.sweep_revenue_to_treasury(managed_token_id!(USDC_TOKEN_ID));
+            },
+        )
+        .assert_user_error("no treasury address set");
+
+    lending_setup
+        .b_mock
+        .execute_tx(
+            &lending_setup.owner_addr,
+            &lending_setup.lending_pool_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.set_treasury_address(managed_address!(&treasury_addr));
+
+                let swept_amount = sc.sweep_revenue_to_treasury(managed_token_id!(USDC_TOKEN_ID));
+                assert_eq!(swept_amount, 5u64);
+            },
+        )
+        .assert_ok();
+
+    lending_setup
+        .b_mock
+        .check_esdt_balance(&treasury_addr, USDC_TOKEN_ID, &rust_biguint!(5));
+    lending_setup
+        .b_mock
+        .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
+            assert_eq!(sc.protocol_revenue().get(), 0u64);
+            assert_eq!(sc.reserves().get(), 2_045u64);
+        })
+        .assert_ok();
 }
 
 #[test]
-fn liquidate_test() {
+fn lend_tokens_test() {
     let mut lending_setup = LendingSetup::deploy_lending(
         lending_pool::contract_obj,
         liquidity_pool::contract_obj,
         aggregator_mock::contract_obj,
     );
+    let supplier_addr = lending_setup.first_user_addr.clone();
+    let borrower_addr = lending_setup.second_user_addr.clone();
+    let supplier_nonce = lending_setup.enter_market(&supplier_addr);
+    let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+    lending_setup.use_simple_interest();
 
-    let liquidatee_user = lending_setup.first_user_addr.clone();
-    let liquidator_user = lending_setup.second_user_addr.clone();
-
-    let liquidatee_account_nonce = lending_setup.enter_market(&liquidatee_user);
-
+    lending_setup.set_block_year(3);
     lending_setup
         .b_mock
-        .set_esdt_balance(&liquidatee_user, USDC_TOKEN_ID, &rust_biguint!(1_000));
+        .set_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(2_000));
+    lending_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &supplier_addr,
+            &lending_setup.lending_pool_wrapper,
+            USDC_TOKEN_ID,
+            0,
+            &rust_biguint!(2_000),
+            |sc| {
+                let lend_tokens = sc.mint_lend_tokens();
+                assert_eq!(lend_tokens.amount, managed_biguint!(2_000));
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .check_esdt_balance(&supplier_addr, USDC_LEND_TOKEN, &rust_biguint!(2_000));
 
-    lending_setup.add_collateral(
-        &liquidatee_user,
+    lending_setup.borrow(
+        &borrower_addr,
         USDC_TOKEN_ID,
         0,
-        liquidatee_account_nonce,
+        borrower_nonce,
         1_000,
         1_000,
+        1_000,
+        3,
+        1_000_000_000,
     );
-    lending_setup.borrow(
-        &liquidatee_user,
+
+    lending_setup.set_block_year(5);
+    lending_setup
+        .b_mock
+        .set_esdt_balance(&borrower_addr, USDC_TOKEN_ID, &rust_biguint!(1_050));
+    lending_setup.repay(
+        &borrower_addr,
         USDC_TOKEN_ID,
+        1_000,
+        borrower_nonce,
+        1_050,
+        2_050,
         0,
-        liquidatee_account_nonce,
-        600,
-        400,
-        600,
-        1,
+        3,
         1_000_000_000,
     );
 
+    // The genesis feed is older than the default max price age by now
+    lending_setup.set_price_feed(USDC_TICKER, DOLLAR_TICKER, USDC_PRICE_IN_DOLLARS);
+
+    // Each lend token is now worth 1.0225 USDC, redemptions round down
     lending_setup
         .b_mock
-        .set_esdt_balance(&liquidator_user, USDC_TOKEN_ID, &rust_biguint!(300));
-
-    lending_setup.liquidate(
-        &liquidator_user,
-        &liquidatee_user,
-        liquidatee_account_nonce,
-        300,
-        315,
-        85,
+        .execute_esdt_transfer(
+            &supplier_addr,
+            &lending_setup.lending_pool_wrapper,
+            USDC_LEND_TOKEN,
+            0,
+            &rust_biguint!(1_000),
+            |sc| {
+                let redeemed_amount = sc.redeem_lend_tokens();
+                assert_eq!(redeemed_amount, 1_022u64);
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(1_022));
+
+    // The rest backs a collateral position of the same scaled amount
+    let transfers = [
+        TxTokenTransfer {
+            token_identifier: ACCOUNT_TOKEN.to_vec(),
+            nonce: supplier_nonce,
+            value: rust_biguint!(1),
+        },
+        TxTokenTransfer {
+            token_identifier: USDC_LEND_TOKEN.to_vec(),
+            nonce: 0,
+            value: rust_biguint!(1_000),
+        },
+    ];
+    lending_setup
+        .b_mock
+        .execute_esdt_multi_transfer(
+            &supplier_addr,
+            &lending_setup.lending_pool_wrapper,
+            &transfers,
+            |sc| {
+                sc.add_collateral();
+
+                let deposit_position = sc
+                    .deposit_positions(supplier_nonce)
+                    .get(&managed_token_id!(USDC_TOKEN_ID))
+                    .unwrap();
+                assert_eq!(
+                    deposit_position.scaled_amount,
+                    Ray::from_amount(&managed_biguint!(1_000))
+                );
+                assert_eq!(deposit_position.amount, managed_biguint!(1_023));
+            },
+        )
+        .assert_ok();
+    lending_setup
+        .b_mock
+        .check_esdt_balance(&supplier_addr, USDC_LEND_TOKEN, &rust_biguint!(0));
+    lending_setup.b_mock.check_nft_balance::<Empty>(
+        &supplier_addr,
+        ACCOUNT_TOKEN,
+        supplier_nonce,
+        &rust_biguint!(1),
+        None,
     );
 }
 
@@ -296,8 +3892,9 @@ fn scenario1() {
     let borrower_addr = lending_setup.second_user_addr.clone();
     let supplier_nonce = lending_setup.enter_market(&supplier_addr);
     let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+    lending_setup.use_simple_interest();
 
-    lending_setup.b_mock.set_block_round(3);
+    lending_setup.set_block_year(3);
 
     // Supply/Deposit
     lending_setup
@@ -318,12 +3915,12 @@ fn scenario1() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, managed_biguint!(1_000_000_000));
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(5);
+    lending_setup.set_block_year(5);
 
     lending_setup.borrow(
         &borrower_addr,
@@ -341,12 +3938,12 @@ fn scenario1() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, managed_biguint!(1_000_000_000));
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(7);
+    lending_setup.set_block_year(7);
 
     // Repay
     lending_setup
@@ -372,27 +3969,28 @@ fn scenario1() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, managed_biguint!(1_050_000_000));
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(11);
+    lending_setup.set_block_year(11);
 
+    // The 10% reserve factor share of the interest stays in the pool as protocol revenue
     lending_setup.remove_collateral(
         &supplier_addr,
         USDC_TOKEN_ID,
-        2_050,
-        supplier_nonce,
         2_000,
-        0,
+        supplier_nonce,
+        2_045,
+        5,
         11,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(2_050));
+        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(2_045));
 }
 
 #[test]
@@ -406,8 +4004,9 @@ fn scenario2() {
     let borrower_addr = lending_setup.second_user_addr.clone();
     let supplier_nonce = lending_setup.enter_market(&supplier_addr);
     let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+    lending_setup.use_simple_interest();
 
-    lending_setup.b_mock.set_block_round(3);
+    lending_setup.set_block_year(3);
 
     // Supply/Deposit
     lending_setup
@@ -428,12 +4027,12 @@ fn scenario2() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(5);
+    lending_setup.set_block_year(5);
 
     lending_setup.borrow(
         &borrower_addr,
@@ -450,14 +4049,15 @@ fn scenario2() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(7);
+    lending_setup.set_block_year(7);
 
-    // Repay 600 USDC + interest (30 USDC)
+    // Repay 600 USDC + interest (30 USDC); principal and interest are cleared pro rata, so the
+    // 650 clear 650 / 1.05 = 619 of the principal
     lending_setup
         .b_mock
         .set_esdt_balance(&borrower_addr, USDC_TOKEN_ID, &rust_biguint!(650));
@@ -469,7 +4069,7 @@ fn scenario2() {
         borrower_nonce,
         650,
         1_650,
-        400,
+        381,
         5,
         1_000_000_000,
     );
@@ -477,12 +4077,12 @@ fn scenario2() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_050_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(9);
+    lending_setup.set_block_year(9);
 
     // repay the rest of 400 USDC
     lending_setup
@@ -505,23 +4105,25 @@ fn scenario2() {
         .b_mock
         .check_esdt_balance(&borrower_addr, USDC_TOKEN_ID, &rust_biguint!(0));
 
+    // The index compounds at the rate of the 381 principal left, 1.05 * 1.01905 = 1.0700025
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_070_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_070_002_500);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(11);
+    lending_setup.set_block_year(11);
 
+    // The 10% reserve factor share of the interest stays in the pool as protocol revenue
     lending_setup.remove_collateral(
         &supplier_addr,
         USDC_TOKEN_ID,
-        2_058,
-        supplier_nonce,
         2_000,
-        0,
+        supplier_nonce,
+        2_053,
+        5,
         11,
         1_000_000_000,
     );
@@ -529,8 +4131,8 @@ fn scenario2() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_070_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_070_002_500);
         })
         .assert_ok();
 }
@@ -546,8 +4148,9 @@ fn scenario3() {
     let borrower_addr = lending_setup.second_user_addr.clone();
     let supplier_nonce = lending_setup.enter_market(&supplier_addr);
     let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+    lending_setup.use_simple_interest();
 
-    lending_setup.b_mock.set_block_round(3);
+    lending_setup.set_block_year(3);
 
     // Supply/Deposit
     lending_setup
@@ -566,12 +4169,12 @@ fn scenario3() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(5);
+    lending_setup.set_block_year(5);
 
     // Borrow
     lending_setup.borrow(
@@ -589,12 +4192,12 @@ fn scenario3() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(7);
+    lending_setup.set_block_year(7);
 
     // Repay
     lending_setup
@@ -620,138 +4223,139 @@ fn scenario3() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_050_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(11);
+    lending_setup.set_block_year(11);
 
-    // Withdraw (400 USDC) - round 1
+    // Withdraw (409 USDC) - round 1; suppliers earn the interest net of the 10% reserve factor,
+    // a 1.0225 supply index, so each withdrawal of 409 takes 400 of the scaled balance
     lending_setup.remove_collateral(
         &supplier_addr,
         USDC_TOKEN_ID,
-        2_050,
+        2_000,
         supplier_nonce,
-        400,
-        1_640,
+        409,
+        1_641,
         11,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(410));
+        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(409));
 
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_050_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(15);
+    lending_setup.set_block_year(15);
 
-    // Withdraw (400 USDC) - round 2
+    // Withdraw (409 USDC) - round 2
     lending_setup.remove_collateral(
         &supplier_addr,
         USDC_TOKEN_ID,
-        1_640,
+        1_600,
         supplier_nonce,
-        400,
-        1_230,
+        409,
+        1_232,
         15,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(820));
+        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(818));
 
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_050_000_000);
         })
         .assert_ok();
-    lending_setup.b_mock.set_block_round(23);
+    lending_setup.set_block_year(23);
 
-    // Withdraw (400 USDC) - round 3
+    // Withdraw (409 USDC) - round 3
 
     lending_setup.remove_collateral(
         &supplier_addr,
         USDC_TOKEN_ID,
-        1_230,
+        1_200,
         supplier_nonce,
-        400,
-        820,
+        409,
+        823,
         25,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(1_230));
+        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(1_227));
 
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_050_000_000);
         })
         .assert_ok();
 
-    // Withdraw (400 USDC) - round 4
-    lending_setup.b_mock.set_block_round(27);
+    // Withdraw (409 USDC) - round 4
+    lending_setup.set_block_year(27);
 
     lending_setup.remove_collateral(
         &supplier_addr,
         USDC_TOKEN_ID,
-        820,
+        800,
         supplier_nonce,
-        400,
-        410,
+        409,
+        414,
         27,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(1_640));
+        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(1_636));
 
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_050_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(32);
+    lending_setup.set_block_year(32);
 
-    // Withdraw (400 USDC) - round 5
+    // Withdraw (409 USDC) - round 5
 
     lending_setup.remove_collateral(
         &supplier_addr,
         USDC_TOKEN_ID,
-        1_640,
-        supplier_nonce,
         400,
-        0,
+        supplier_nonce,
+        409,
+        5,
         32,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(2_050));
+        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(2_045));
 
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_050_000_000);
         })
         .assert_ok();
@@ -770,8 +4374,9 @@ fn scenario4() {
     let supplier_nonce = lending_setup.enter_market(&supplier_addr);
     let supplier2_nonce = lending_setup.enter_market(&supplier2_addr);
     let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+    lending_setup.use_simple_interest();
 
-    lending_setup.b_mock.set_block_round(3);
+    lending_setup.set_block_year(3);
 
     // Supply/Deposit
     lending_setup
@@ -790,12 +4395,12 @@ fn scenario4() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(5);
+    lending_setup.set_block_year(5);
 
     // Supply/Deposit
     lending_setup
@@ -814,12 +4419,12 @@ fn scenario4() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(7);
+    lending_setup.set_block_year(7);
 
     // Borrow
     lending_setup.borrow(
@@ -837,12 +4442,12 @@ fn scenario4() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(9);
+    lending_setup.set_block_year(9);
 
     // Repay
     lending_setup
@@ -864,60 +4469,60 @@ fn scenario4() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_050_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(11);
+    lending_setup.set_block_year(11);
 
-    // Withdraw - Supplier1
+    // Withdraw - Supplier1; half of the 45 USDC net interest, rounded up
     lending_setup.remove_collateral(
         &supplier_addr,
         USDC_TOKEN_ID,
-        1_025,
-        supplier_nonce,
         1_000,
-        1_025,
+        supplier_nonce,
+        1_023,
+        1_027,
         11,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(1_025));
+        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(1_023));
 
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_050_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(13);
+    lending_setup.set_block_year(13);
 
-    // Withdraw - Supplier 2
+    // Withdraw - Supplier 2; the rounding up of both withdrawals is taken from the protocol revenue
 
     lending_setup.remove_collateral(
         &supplier2_addr,
         USDC_TOKEN_ID,
-        1_025,
-        supplier2_nonce,
         1_000,
-        0,
+        supplier2_nonce,
+        1_023,
+        4,
         13,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&supplier2_addr, USDC_TOKEN_ID, &rust_biguint!(1_025));
+        .check_esdt_balance(&supplier2_addr, USDC_TOKEN_ID, &rust_biguint!(1_023));
 
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_050_000_000);
         })
         .assert_ok();
@@ -936,12 +4541,13 @@ fn scenario5() {
     let supplier_nonce = lending_setup.enter_market(&supplier_addr);
     let supplier2_nonce = lending_setup.enter_market(&supplier2_addr);
     let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+    lending_setup.use_simple_interest();
 
-    lending_setup.b_mock.set_block_round(3);
+    lending_setup.set_block_year(3);
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
@@ -963,12 +4569,12 @@ fn scenario5() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(5);
+    lending_setup.set_block_year(5);
 
     // Borrow
     lending_setup.borrow(
@@ -986,12 +4592,12 @@ fn scenario5() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(7);
+    lending_setup.set_block_year(7);
 
     // Supply/Deposit
     lending_setup
@@ -1010,12 +4616,12 @@ fn scenario5() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_020_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(9);
+    lending_setup.set_block_year(9);
 
     // Repay
     lending_setup
@@ -1038,23 +4644,24 @@ fn scenario5() {
         .b_mock
         .check_esdt_balance(&borrower_addr, USDC_TOKEN_ID, &rust_biguint!(0));
 
+    // The index compounds, 1.02 * 1.01 = 1.0302
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_030_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_030_200_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(11);
+    lending_setup.set_block_year(11);
 
     // Withdraw - Supplier1
     lending_setup.remove_collateral(
         &supplier_addr,
         USDC_TOKEN_ID,
-        2_010,
-        supplier_nonce,
         2_000,
+        supplier_nonce,
+        2_010,
         2_002,
         11,
         1_000_000_000,
@@ -1067,20 +4674,20 @@ fn scenario5() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_030_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_030_200_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(13);
+    lending_setup.set_block_year(13);
 
     // Withdraw - Supplier 2
     lending_setup.remove_collateral(
         &supplier2_addr,
         USDC_TOKEN_ID,
-        2_002,
-        supplier2_nonce,
         2_000,
+        supplier2_nonce,
+        2_002,
         0,
         13,
         1_004_000_000,
@@ -1093,8 +4700,8 @@ fn scenario5() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_030_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_030_200_000);
         })
         .assert_ok();
 }
@@ -1114,12 +4721,13 @@ fn scenario6() {
     let alice_nonce = lending_setup.enter_market(&alice_addr);
     let bob_nonce = lending_setup.enter_market(&bob_addr);
     let charlie_nonce = lending_setup.enter_market(&charlie_addr);
+    lending_setup.use_simple_interest();
 
-    lending_setup.b_mock.set_block_round(3);
+    lending_setup.set_block_year(3);
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
@@ -1134,12 +4742,12 @@ fn scenario6() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(5);
+    lending_setup.set_block_year(5);
 
     // Supply/Deposit - Bob
     lending_setup
@@ -1151,12 +4759,12 @@ fn scenario6() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(7);
+    lending_setup.set_block_year(7);
 
     // Borrow - Bob
     lending_setup
@@ -1178,12 +4786,12 @@ fn scenario6() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(9);
+    lending_setup.set_block_year(9);
 
     // Borrow - Alice
     lending_setup
@@ -1205,12 +4813,12 @@ fn scenario6() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_025_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(11);
+    lending_setup.set_block_year(11);
 
     // Borrow - Charlie
     lending_setup
@@ -1229,17 +4837,20 @@ fn scenario6() {
         1_060_000_000,
     );
 
+    // The index compounds, 1.025 * 1.035 = 1.060875
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_060_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_060_875_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(13);
+    lending_setup.set_block_year(13);
 
-    // Repay - Charlie
+    // Repay - Charlie; principal is cleared pro rata to the scaled debt, so clearing a loan
+    // taken at a higher index takes out less than its 12_000 principal, and the rate, the
+    // indexes and the amounts below follow from that
     lending_setup
         .b_mock
         .set_esdt_balance(&charlie_addr, USDC_TOKEN_ID, &rust_biguint!(12_660));
@@ -1251,9 +4862,9 @@ fn scenario6() {
         charlie_nonce,
         12_660,
         39_660,
-        21_000,
+        21_395,
         11,
-        1_060_000_000,
+        1_060_875_000,
     );
 
     lending_setup
@@ -1263,26 +4874,26 @@ fn scenario6() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_115_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_119_223_125);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(15);
+    lending_setup.set_block_year(15);
 
-    // Repay - Alice (1)
+    // Repay - Alice (1); the partial repayments leave exactly the debt the next step clears
     lending_setup
         .b_mock
-        .set_esdt_balance(&alice_addr, USDC_TOKEN_ID, &rust_biguint!(3_750));
+        .set_esdt_balance(&alice_addr, USDC_TOKEN_ID, &rust_biguint!(3_785));
 
     lending_setup.repay(
         &alice_addr,
         USDC_TOKEN_ID,
         6_000,
         alice_nonce,
-        3_750,
-        43_410,
-        18_000,
+        3_785,
+        43_445,
+        18_045,
         9,
         1_025_000_000,
     );
@@ -1294,12 +4905,12 @@ fn scenario6() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_150_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_159_132_755);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(17);
+    lending_setup.set_block_year(17);
 
     // Repay - Alice (2)
     lending_setup
@@ -1312,10 +4923,10 @@ fn scenario6() {
         3_000,
         alice_nonce,
         3_090,
-        46_500,
-        15_000,
+        46_535,
+        15_390,
         15,
-        1_150_000_000,
+        1_159_132_755,
     );
 
     lending_setup
@@ -1325,26 +4936,26 @@ fn scenario6() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_180_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_193_993_672);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(19);
+    lending_setup.set_block_year(19);
 
     // Repay - Bob (1)
     lending_setup
         .b_mock
-        .set_esdt_balance(&bob_addr, USDC_TOKEN_ID, &rust_biguint!(6_075));
+        .set_esdt_balance(&bob_addr, USDC_TOKEN_ID, &rust_biguint!(6_370));
 
     lending_setup.repay(
         &bob_addr,
         USDC_TOKEN_ID,
         15_000,
         bob_nonce,
-        6_075,
-        52_575,
-        12_000,
+        6_370,
+        52_905,
+        10_054,
         7,
         1_000_000_000,
     );
@@ -1356,28 +4967,28 @@ fn scenario6() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_205_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_224_619_610);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(21);
+    lending_setup.set_block_year(21);
 
     // Repay - Bob (2)
     lending_setup
         .b_mock
-        .set_esdt_balance(&bob_addr, USDC_TOKEN_ID, &rust_biguint!(12_240));
+        .set_esdt_balance(&bob_addr, USDC_TOKEN_ID, &rust_biguint!(12_201));
 
     lending_setup.repay(
         &bob_addr,
         USDC_TOKEN_ID,
         12_000,
         bob_nonce,
-        12_240,
-        64_815,
+        12_201,
+        65_106,
         0,
         19,
-        1_205_000_000,
+        1_224_619_610,
     );
 
     lending_setup
@@ -1387,62 +4998,64 @@ fn scenario6() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_225_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_245_140_152);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(23);
+    lending_setup.set_block_year(23);
 
-    // Withdraw - Supplier1
+    // Withdraw - Supplier1; positions are passed at their principal, which the pool values at
+    // the supply index, and the whole balance is withdrawn net of the reserve factor
 
     lending_setup.remove_collateral(
         &bob_addr,
         USDC_TOKEN_ID,
-        21_605,
-        bob_nonce,
         20_000,
-        43_210,
+        bob_nonce,
+        21_533,
+        43_573,
         5,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&bob_addr, USDC_TOKEN_ID, &rust_biguint!(21_605));
+        .check_esdt_balance(&bob_addr, USDC_TOKEN_ID, &rust_biguint!(21_533));
 
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_225_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_245_140_152);
         })
         .assert_ok();
 
     // Withdraw - Supplier 2
 
-    lending_setup.b_mock.set_block_round(25);
+    lending_setup.set_block_year(25);
 
+    // Only the protocol revenue is left in the pool
     lending_setup.remove_collateral(
         &alice_addr,
         USDC_TOKEN_ID,
-        43_210,
-        alice_nonce,
         40_000,
-        0,
+        alice_nonce,
+        43_066,
+        507,
         3,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&alice_addr, USDC_TOKEN_ID, &rust_biguint!(43_210));
+        .check_esdt_balance(&alice_addr, USDC_TOKEN_ID, &rust_biguint!(43_066));
 
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_225_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_245_140_152);
         })
         .assert_ok();
 }
@@ -1461,10 +5074,11 @@ fn scenario7() {
     let supplier_nonce = lending_setup.enter_market(&supplier_addr);
     let supplier2_nonce = lending_setup.enter_market(&supplier2_addr);
     let borrower_nonce = lending_setup.enter_market(&borrower_addr);
+    lending_setup.use_simple_interest();
     let borrower2_nonce = lending_setup.enter_market(&borrower2_addr);
 
     // Supply/Deposit
-    lending_setup.b_mock.set_block_round(5);
+    lending_setup.set_block_year(5);
 
     lending_setup
         .b_mock
@@ -1482,12 +5096,12 @@ fn scenario7() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(5);
+    lending_setup.set_block_year(5);
 
     // Supply/Deposit
     lending_setup
@@ -1506,12 +5120,12 @@ fn scenario7() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(7);
+    lending_setup.set_block_year(7);
 
     // Borrow
     lending_setup.borrow(
@@ -1529,12 +5143,12 @@ fn scenario7() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_000_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(8);
+    lending_setup.set_block_year(8);
 
     // Borrow
     lending_setup.borrow(
@@ -1552,13 +5166,14 @@ fn scenario7() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_010_000_000);
         })
         .assert_ok();
 
     // Still same round - 8
-    // Repay
+    // Repay; principal is cleared pro rata to the scaled debt, the 1_000 scaled of 2_980 clear
+    // 1_006 of the 3_000 principal
     lending_setup
         .b_mock
         .set_esdt_balance(&borrower_addr, USDC_TOKEN_ID, &rust_biguint!(1_010));
@@ -1570,7 +5185,7 @@ fn scenario7() {
         borrower_nonce,
         1_010,
         3_010,
-        2_000,
+        1_994,
         7,
         1_000_000_000,
     );
@@ -1582,12 +5197,12 @@ fn scenario7() {
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
+            let borrow_index = sc.borrow_index().get().to_bp();
             assert_eq!(borrow_index, 1_010_000_000);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(11);
+    lending_setup.set_block_year(11);
 
     // Repay - Dave (2000 USD)
     lending_setup
@@ -1606,41 +5221,42 @@ fn scenario7() {
         1_010_000_000,
     );
 
+    // The index compounds, at the rate of the 1_994 principal left, 1.01 * 1.0598 = 1.0704
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_070_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_070_418_200);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(13);
+    lending_setup.set_block_year(13);
 
-    // Withdraw - Supplier1
+    // Withdraw - Supplier1; the payout is net of the 10% reserve factor
     lending_setup.remove_collateral(
         &supplier_addr,
         USDC_TOKEN_ID,
         3_000,
         supplier_nonce,
-        3_000,
-        2_052,
+        3_070,
+        2_060,
         3,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(3_078));
+        .check_esdt_balance(&supplier_addr, USDC_TOKEN_ID, &rust_biguint!(3_070));
 
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_070_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_070_418_200);
         })
         .assert_ok();
 
-    lending_setup.b_mock.set_block_round(15);
+    lending_setup.set_block_year(15);
 
     // Withdraw - Supplier 2
     lending_setup.remove_collateral(
@@ -1648,21 +5264,21 @@ fn scenario7() {
         USDC_TOKEN_ID,
         2_000,
         supplier2_nonce,
-        2_000,
-        0,
+        2_047,
+        13,
         3,
         1_000_000_000,
     );
 
     lending_setup
         .b_mock
-        .check_esdt_balance(&supplier2_addr, USDC_TOKEN_ID, &rust_biguint!(2_052));
+        .check_esdt_balance(&supplier2_addr, USDC_TOKEN_ID, &rust_biguint!(2_047));
 
     lending_setup
         .b_mock
         .execute_query(&lending_setup.liquidity_pool_usdc_wrapper, |sc| {
-            let borrow_index = sc.borrow_index().get();
-            assert_eq!(borrow_index, 1_070_000_000);
+            let borrow_index = sc.borrow_index().get().to_bp();
+            assert_eq!(borrow_index, 1_070_418_200);
         })
         .assert_ok();
 }
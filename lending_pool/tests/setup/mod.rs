@@ -9,6 +9,7 @@ use price_aggregator_proxy::PriceAggregatorModule;
 use crate::constants::*;
 use aggregator_mock::PriceAggregatorMock;
 use lending_pool::{AccountTokenModule, LendingPool};
+use safety_module::SafetyModule;
 
 pub fn setup_price_aggregator<PriceAggregatorObjBuilder>(
     owner_addr: &Address,
@@ -100,3 +101,38 @@ where
 
     liquidity_pool_wrapper.address_ref().clone()
 }
+
+pub fn setup_safety_module<SafetyModuleObjBuilder>(
+    owner_addr: &Address,
+    b_mock: &mut BlockchainStateWrapper,
+    builder: SafetyModuleObjBuilder,
+    wegld_pool_addr: &Address,
+    max_slashing_percentage: u64,
+) -> ContractObjWrapper<safety_module::ContractObj<DebugApi>, SafetyModuleObjBuilder>
+where
+    SafetyModuleObjBuilder: 'static + Copy + Fn() -> safety_module::ContractObj<DebugApi>,
+{
+    let rust_zero = rust_biguint!(0u64);
+    let safety_module_wrapper = b_mock.create_sc_account(
+        &rust_zero,
+        Some(owner_addr),
+        builder,
+        SAFETY_MODULE_WASM_PATH,
+    );
+
+    b_mock
+        .execute_tx(owner_addr, &safety_module_wrapper, &rust_zero, |sc| {
+            sc.init(managed_token_id!(EGLD_TOKEN_ID));
+            sc.add_pool(
+                managed_token_id!(EGLD_TOKEN_ID),
+                &managed_address!(wegld_pool_addr),
+            );
+            sc.set_max_slashing_percentage(
+                managed_biguint!(max_slashing_percentage),
+                SLASHING_WINDOW,
+            );
+        })
+        .assert_ok();
+
+    safety_module_wrapper
+}
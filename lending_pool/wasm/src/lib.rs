@@ -5,9 +5,9 @@
 ////////////////////////////////////////////////////
 
 // Init:                                 1
-// Endpoints:                           37
+// Endpoints:                          129
 // Async Callback:                       1
-// Total number of exported functions:  39
+// Total number of exported functions: 131
 
 #![no_std]
 #![feature(lang_items)]
@@ -21,41 +21,133 @@ multiversx_sc_wasm_adapter::endpoints! {
         init => init
         registerAccountToken => register_account_token
         registerDebtNFTToken => register_debt_token
+        registerLendToken => register_lend_token
         enterMarket => enter_market
         exitMarket => exit_market
         addCollateral => add_collateral
+        supply => supply
+        setCollateralEnabled => set_collateral_enabled
+        setAccountEModeCategory => set_account_e_mode_category
+        mintLendTokens => mint_lend_tokens
+        redeemLendTokens => redeem_lend_tokens
         removeCollateral => remove_collateral
         borrow => borrow
         borrowWithNFTs => borrow_with_nfts
         repay => repay
         repayNFT => repay_nft_debt
         liquidate => liquidate
+        writeOffBadDebt => write_off_bad_debt
         updateCollateralWithInterest => update_collateral_with_interest
         updateBorrowsWithDebt => update_borrows_with_debt
         getLiqPoolTemplateAddress => liq_pool_template_address
         createLiquidityPool => create_liquidity_pool
+        setAssetDecimals => set_asset_decimals
         upgradeLiquidityPool => upgrade_liquidity_pool
         setAggregator => set_aggregator
+        setSafetyModule => set_safety_module
+        setBadDebtDustThreshold => set_bad_debt_dust_threshold
+        setInterestApproximationTerms => set_interest_approximation_terms
+        setTreasuryAddress => set_treasury_address
+        sweepRevenueToTreasury => sweep_revenue_to_treasury
+        sweepRevenueToSafetyModule => sweep_revenue_to_safety_module
         setAssetLoanToValue => set_asset_loan_to_value
+        setAssetSupplyCap => set_asset_supply_cap
+        setAssetBorrowCap => set_asset_borrow_cap
         setAssetLiquidationBonus => set_asset_liquidation_bonus
+        setAssetLiquidationThreshold => set_asset_liquidation_threshold
+        setAssetIsolated => set_asset_isolated
+        setIsolationBorrowable => set_isolation_borrowable
+        setIsolationDebtCeiling => set_isolation_debt_ceiling
+        setEModeCategory => set_e_mode_category
+        setAssetEModeCategory => set_asset_e_mode_category
+        pauseAsset => pause_asset
+        unpauseAsset => unpause_asset
         addCollection => add_collection
+        setCollectionBorrowCap => set_collection_borrow_cap
+        setCollectionOracle => set_collection_oracle
+        removeCollectionOracle => remove_collection_oracle
         getPoolAddress => get_pool_address
         getPoolAllowed => pools_allowed
+        getTreasuryAddress => treasury_address
         getAssetLoanToValue => asset_loan_to_value
+        getAssetSupplyCap => asset_supply_cap
+        getAssetBorrowCap => asset_borrow_cap
         getAssetLiquidationBonus => asset_liquidation_bonus
         getAccountToken => account_token
         getDebtNFT => debt_nft_token
         getAccountPositions => account_positions
         getDepositPositions => deposit_positions
         getBorrowPositions => borrow_positions
+        getNonCollateralDeposits => non_collateral_deposits
         getNFTBorrowPositions => nft_borrow_positions
+        getAssetDecimals => asset_decimals
+        getAssetLiquidationThreshold => asset_liquidation_threshold
         getCollections => collections
         getCollectionParam => collection_params
+        getCollectionOracleParams => collection_oracle_params
+        getCollectionLastFloor => collection_last_floor
+        getCollectionLastFloorTimestamp => collection_last_floor_timestamp
+        getCollectionBorrowCap => collection_borrow_cap
+        getCollectionBorrowedAmount => collection_borrowed_amount
+        getIsolatedAssets => isolated_assets
+        getIsolationBorrowableAssets => isolation_borrowable_assets
+        getIsolationDebtCeiling => isolation_debt_ceiling
+        getIsolatedDebt => isolated_debt
+        getAccountIsolatedDebt => account_isolated_debt
+        getAccountIsolatedDebtInDollars => account_isolated_debt_in_dollars
+        getBadDebtDustThreshold => bad_debt_dust_threshold
+        getEModeCategory => e_mode_categories
+        getAssetEModeCategory => asset_e_mode_category
+        getAccountEModeCategory => account_e_mode_category
+        getLendToken => lend_token
+        getLendTokenAsset => lend_token_asset
+        setTimelockDelay => set_timelock_delay
+        queueChange => queue_change
+        executeChange => execute_change
+        cancelChange => cancel_change
+        getTimelockDelay => get_timelock_delay
+        getQueuedChanges => queued_changes
+        getCollectionFloor => get_collection_floor
         getCollateralAmountForToken => get_collateral_amount_for_token
+        isCollateralEnabled => is_collateral_enabled
         getTotalCollateralAvailable => get_total_collateral_in_dollars
+        getTotalBorrowableCollateralInDollars => get_total_borrowable_collateral_in_dollars
+        getTotalWeightedCollateralInDollars => get_total_weighted_collateral_in_dollars
+        getAccountHealthFactor => get_account_health_factor
         getTotalBorrowInDollars => get_total_borrow_in_dollars
+        getCollectionRemainingCapacity => get_collection_remaining_capacity
+        isAccountIsolated => is_account_isolated
+        getIsolationRemainingDebt => get_isolation_remaining_debt
+        setNftAuctionParams => set_nft_auction_params
+        startNftAuction => start_nft_auction
+        bidNftAuction => bid_nft_auction
+        claimNftAuctionSurplus => claim_nft_auction_surplus
+        getNftAuctionPrice => get_nft_auction_price
+        getNftLoanHealthFactor => get_nft_loan_health_factor
+        getNftAuction => nft_auctions
+        getNftAuctionSurplus => nft_auction_surplus
+        getNftAuctionDuration => nft_auction_duration
+        getNftAuctionStartPriceRatio => nft_auction_start_price_ratio
+        getNftAuctionEndPriceRatio => nft_auction_end_price_ratio
         setPriceAggregatorAddress => set_price_aggregator_address
-        getAggregatorAddress => price_aggregator_address
+        addPriceAggregatorAddress => add_price_aggregator_address
+        removePriceAggregatorAddress => remove_price_aggregator_address
+        setFallbackPriceSource => set_fallback_price_source
+        setMaxMissingPriceFeeds => set_max_missing_price_feeds
+        setMaxPriceStaleness => set_max_price_staleness
+        getAggregatorAddresses => price_aggregator_addresses
+        getFallbackPriceSource => fallback_price_source
+        getMaxMissingPriceFeeds => max_missing_price_feeds
+        getMaxPriceStaleness => get_max_price_staleness
+        getLastPriceRound => last_price_round
+        grantRole => grant_role
+        revokeRole => revoke_role
+        hasRole => has_role
+        getRoleMembers => role_members
+        pause => pause
+        unpause => unpause
+        isPaused => is_paused
+        getPausedActions => paused_actions
     )
 }
 
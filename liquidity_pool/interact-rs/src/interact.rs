@@ -43,33 +43,55 @@ async fn main() {
         "getReserves" => state.reserves().await,
         "getSuppliedAmount" => state.supplied_amount().await,
         "getRewardsReserves" => state.rewards_reserves().await,
+        "getProtocolRevenue" => state.protocol_revenue().await,
         "getLendToken" => state.lend_token().await,
         "borrowToken" => state.borrow_token().await,
         "getPoolParams" => state.pool_params().await,
+        "getInterestRateStrategy" => state.interest_rate_strategy().await,
+        "getRateControllerState" => state.rate_controller_state().await,
         "getTotalBorrow" => state.borrowed_amount().await,
         "getLiquidationThreshold" => state.liquidation_threshold().await,
         "getBorrowIndex" => state.borrow_index().await,
         "getSupplyIndex" => state.supply_index().await,
-        "borrowIndexLastUpdateRound" => state.borrow_index_last_update_round().await,
+        "getScaledSuppliedAmount" => state.scaled_supplied_amount().await,
+        "getScaledBorrowedAmount" => state.scaled_borrowed_amount().await,
+        "borrowIndexLastUpdateTimestamp" => state.borrow_index_last_update_timestamp().await,
+        "getInterestApproximationTerms" => state.interest_approximation_terms().await,
+        "getSupplyCap" => state.supply_cap().await,
+        "getBorrowCap" => state.borrow_cap().await,
+        "getSafetyModule" => state.safety_module().await,
         "getAccountToken" => state.account_token().await,
         "getDebtNFT" => state.debt_nft_token().await,
         "getAccountPositions" => state.account_positions().await,
+        "setSafetyModule" => state.set_safety_module().await,
+        "setLendToken" => state.set_lend_token().await,
+        "setSupplyCap" => state.set_supply_cap().await,
+        "setBorrowCap" => state.set_borrow_cap().await,
+        "setInterestApproximationTerms" => state.set_interest_approximation_terms().await,
         "updateCollateralWithInterest" => state.update_collateral_with_interest().await,
         "updateBorrowsWithDebt" => state.update_borrows_with_debt().await,
         "addCollateral" => state.add_collateral().await,
+        "supplyForLendTokens" => state.supply_for_lend_tokens().await,
+        "redeemLendTokens" => state.redeem_lend_tokens().await,
+        "addLendTokenCollateral" => state.add_lend_token_collateral().await,
         "borrow" => state.borrow().await,
         "borrowWithNFTs" => state.borrow_bulk_nfts().await,
         "remove_collateral" => state.remove_collateral().await,
         "repay" => state.repay().await,
         "repayNFTs" => state.repay_nfts().await,
-        "sendTokens" => state.send_tokens().await,
+        "seizeCollateral" => state.seize_collateral().await,
+        "writeOffBadDebt" => state.write_off_bad_debt().await,
+        "sweepProtocolRevenue" => state.sweep_protocol_revenue().await,
         "getCapitalUtilisation" => state.get_capital_utilisation().await,
         "getTotalCapital" => state.get_total_capital().await,
         "getDebtInterest" => state.get_debt_interest().await,
+        "getSupplyCapHeadroom" => state.get_supply_cap_headroom().await,
+        "getBorrowCapHeadroom" => state.get_borrow_cap_headroom().await,
         "getDepositRate" => state.get_deposit_rate().await,
         "getBorrowRate" => state.get_borrow_rate().await,
+        "getCurrentIndexes" => state.get_current_indexes().await,
         "setPriceAggregatorAddress" => state.set_price_aggregator_address().await,
-        "getAggregatorAddress" => state.price_aggregator_address().await,
+        "getAggregatorAddresses" => state.price_aggregator_addresses().await,
         _ => panic!("unknown command: {}", &cmd),
     }
 }
@@ -106,12 +128,13 @@ impl State {
         let u_optimal = BigUint::<DebugApi>::from(0u128);
         let reserve_factor = BigUint::<DebugApi>::from(0u128);
         let liquidation_threshold = BigUint::<DebugApi>::from(0u128);
+        let rate_strategy = OptionalValue::Some(InterestRateStrategy::<DebugApi>::JumpRate);
 
         let result: multiversx_sc_snippets::InteractorResult<()> = self
             .interactor
             .sc_deploy(
                 self.contract
-                    .init(asset, r_base, r_slope1, r_slope2, u_optimal, reserve_factor, liquidation_threshold)
+                    .init(asset, r_base, r_slope1, r_slope2, u_optimal, reserve_factor, liquidation_threshold, rate_strategy)
                     .into_blockchain_call()
                     .from(&self.wallet_address)
                     .code_metadata(CodeMetadata::all())
@@ -164,6 +187,15 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn protocol_revenue(&mut self) {
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.protocol_revenue())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn lend_token(&mut self) {
         let result_value: TokenIdentifier<DebugApi> = self
             .interactor
@@ -191,6 +223,24 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn interest_rate_strategy(&mut self) {
+        let result_value: InterestRateStrategy<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.interest_rate_strategy())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn rate_controller_state(&mut self) {
+        let result_value: PidControllerState<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.rate_controller_state())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn borrowed_amount(&mut self) {
         let result_value: BigUint<DebugApi> = self
             .interactor
@@ -210,7 +260,7 @@ impl State {
     }
 
     async fn borrow_index(&mut self) {
-        let result_value: BigUint<DebugApi> = self
+        let result_value: Ray<DebugApi> = self
             .interactor
             .vm_query(self.contract.borrow_index())
             .await;
@@ -219,7 +269,7 @@ impl State {
     }
 
     async fn supply_index(&mut self) {
-        let result_value: BigUint<DebugApi> = self
+        let result_value: Ray<DebugApi> = self
             .interactor
             .vm_query(self.contract.supply_index())
             .await;
@@ -227,10 +277,64 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
-    async fn borrow_index_last_update_round(&mut self) {
+    async fn scaled_supplied_amount(&mut self) {
+        let result_value: Ray<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.scaled_supplied_amount())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn scaled_borrowed_amount(&mut self) {
+        let result_value: Ray<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.scaled_borrowed_amount())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn borrow_index_last_update_timestamp(&mut self) {
         let result_value: u64 = self
             .interactor
-            .vm_query(self.contract.borrow_index_last_update_round())
+            .vm_query(self.contract.borrow_index_last_update_timestamp())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn interest_approximation_terms(&mut self) {
+        let result_value: u32 = self
+            .interactor
+            .vm_query(self.contract.interest_approximation_terms())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn supply_cap(&mut self) {
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.supply_cap())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn borrow_cap(&mut self) {
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.borrow_cap())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn safety_module(&mut self) {
+        let result_value: ManagedAddress<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.safety_module())
             .await;
 
         println!("Result: {:?}", result_value);
@@ -263,6 +367,101 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn set_safety_module(&mut self) {
+        let safety_module_address = bech32::decode("");
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_safety_module(safety_module_address)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_lend_token(&mut self) {
+        let lend_token = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_lend_token(lend_token)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_supply_cap(&mut self) {
+        let cap = OptionalValue::Some(BigUint::<DebugApi>::from(0u128));
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_supply_cap(cap)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_borrow_cap(&mut self) {
+        let cap = OptionalValue::Some(BigUint::<DebugApi>::from(0u128));
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_borrow_cap(cap)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_interest_approximation_terms(&mut self) {
+        let terms = 0u32;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_interest_approximation_terms(terms)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn update_collateral_with_interest(&mut self) {
         let token_id = b"";
         let token_nonce = 0u64;
@@ -338,6 +537,69 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn supply_for_lend_tokens(&mut self) {
+        let token_id = b"";
+        let token_nonce = 0u64;
+        let token_amount = BigUint::<DebugApi>::from(0u128);
+
+        let result: multiversx_sc_snippets::InteractorResult<BigUint<DebugApi>> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .supply_for_lend_tokens()
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+            .esdt_transfer(token_id.to_vec(), token_nonce, token_amount)
+
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn redeem_lend_tokens(&mut self) {
+        let initial_caller = bech32::decode("");
+        let lend_amount = BigUint::<DebugApi>::from(0u128);
+
+        let result: multiversx_sc_snippets::InteractorResult<BigUint<DebugApi>> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .redeem_lend_tokens(initial_caller, lend_amount)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn add_lend_token_collateral(&mut self) {
+        let lend_amount = BigUint::<DebugApi>::from(0u128);
+        let deposit_position = PlaceholderInput;
+
+        let result: multiversx_sc_snippets::InteractorResult<DepositPosition<DebugApi>> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .add_lend_token_collateral(lend_amount, deposit_position)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn borrow(&mut self) {
         let token_id = b"";
         let token_nonce = 0u64;
@@ -471,15 +733,60 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
-    async fn send_tokens(&mut self) {
-        let initial_caller = bech32::decode("");
-        let payment_amount = BigUint::<DebugApi>::from(0u128);
+    async fn seize_collateral(&mut self) {
+        let token_id = b"";
+        let token_nonce = 0u64;
+        let token_amount = BigUint::<DebugApi>::from(0u128);
 
-        let result: multiversx_sc_snippets::InteractorResult<()> = self
+        let liquidator = bech32::decode("");
+        let amount = BigUint::<DebugApi>::from(0u128);
+        let deposit_position = PlaceholderInput;
+
+        let result: multiversx_sc_snippets::InteractorResult<DepositPosition<DebugApi>> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .seize_collateral(liquidator, amount, deposit_position)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+            .esdt_transfer(token_id.to_vec(), token_nonce, token_amount)
+
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn write_off_bad_debt(&mut self) {
+        let borrow_position = PlaceholderInput;
+
+        let result: multiversx_sc_snippets::InteractorResult<MultiValue2<BigUint<DebugApi>, BigUint<DebugApi>>> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .write_off_bad_debt(borrow_position)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn sweep_protocol_revenue(&mut self) {
+        let treasury = OptionalValue::Some(bech32::decode(""));
+
+        let result: multiversx_sc_snippets::InteractorResult<BigUint<DebugApi>> = self
             .interactor
             .sc_call_get_result(
                 self.contract
-                    .send_tokens(initial_caller, payment_amount)
+                    .sweep_protocol_revenue(treasury)
                     .into_blockchain_call()
                     .from(&self.wallet_address)
                     .gas_limit(DEFAULT_GAS_LIMIT)
@@ -510,12 +817,29 @@ impl State {
     }
 
     async fn get_debt_interest(&mut self) {
-        let amount = BigUint::<DebugApi>::from(0u128);
-        let initial_borrow_index = BigUint::<DebugApi>::from(0u128);
+        let borrow_position = PlaceholderInput;
 
         let result_value: BigUint<DebugApi> = self
             .interactor
-            .vm_query(self.contract.get_debt_interest(amount, initial_borrow_index))
+            .vm_query(self.contract.get_debt_interest(borrow_position))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn get_supply_cap_headroom(&mut self) {
+        let result_value: OptionalValue<BigUint<DebugApi>> = self
+            .interactor
+            .vm_query(self.contract.get_supply_cap_headroom())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn get_borrow_cap_headroom(&mut self) {
+        let result_value: OptionalValue<BigUint<DebugApi>> = self
+            .interactor
+            .vm_query(self.contract.get_borrow_cap_headroom())
             .await;
 
         println!("Result: {:?}", result_value);
@@ -539,6 +863,15 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn get_current_indexes(&mut self) {
+        let result_value: MultiValue2<Ray<DebugApi>, Ray<DebugApi>> = self
+            .interactor
+            .vm_query(self.contract.get_current_indexes())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn set_price_aggregator_address(&mut self) {
         let address = bech32::decode("");
 
@@ -558,10 +891,10 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
-    async fn price_aggregator_address(&mut self) {
-        let result_value: ManagedAddress<DebugApi> = self
+    async fn price_aggregator_addresses(&mut self) {
+        let result_value: MultiValueVec<ManagedAddress<DebugApi>> = self
             .interactor
-            .vm_query(self.contract.price_aggregator_address())
+            .vm_query(self.contract.price_aggregator_addresses())
             .await;
 
         println!("Result: {:?}", result_value);
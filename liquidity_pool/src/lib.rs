@@ -11,12 +11,17 @@ pub mod tokens;
 pub use common_structs::*;
 pub use common_tokens::*;
 
+pub mod liq_events;
 pub mod liq_storage;
 pub mod liq_utils;
 
+// Taylor terms of the compounded interest, enough for yearly rates well below 100%
+pub const DEFAULT_INTEREST_APPROXIMATION_TERMS: u32 = 3;
+
 #[multiversx_sc::contract]
 pub trait LiquidityPool:
     liq_storage::StorageModule
+    + liq_events::EventsModule
     + tokens::TokensModule
     + common_tokens::AccountTokenModule
     + liq_math::MathModule
@@ -24,6 +29,8 @@ pub trait LiquidityPool:
     + liq_utils::UtilsModule
     + price_aggregator_proxy::PriceAggregatorModule
     + common_checks::ChecksModule
+    + common_permissions::PermissionsModule
+    + common_pause::PauseModule
 {
     #[init]
     fn init(
@@ -35,8 +42,25 @@ pub trait LiquidityPool:
         u_optimal: BigUint,
         reserve_factor: BigUint,
         liquidation_threshold: BigUint,
+        rate_strategy: OptionalValue<InterestRateStrategy<Self::Api>>,
     ) {
-        self.pool_asset().set(&asset);
+        let rate_strategy = rate_strategy
+            .into_option()
+            .unwrap_or(InterestRateStrategy::JumpRate);
+        self.require_valid_rate_strategy(&rate_strategy);
+
+        // An upgrade re-runs init: the interest accrued so far follows the old params, and the
+        // indexes, totals and revenue are only set on the first deployment
+        if self.pool_asset().is_empty() {
+            self.pool_asset().set(&asset);
+        } else {
+            require!(
+                self.pool_asset().get() == asset,
+                "pool asset can not change"
+            );
+            self.update_interest_indexes();
+        }
+
         self.pool_params().set(&PoolParams {
             r_base,
             r_slope1,
@@ -44,10 +68,21 @@ pub trait LiquidityPool:
             u_optimal,
             reserve_factor,
         });
+        self.interest_rate_strategy().set(&rate_strategy);
         self.liquidation_threshold().set(&liquidation_threshold);
-        self.borrow_index().set(BigUint::from(BP));
-        self.supply_index().set(BigUint::from(BP));
-        self.rewards_reserves().set(BigUint::zero());
-        self.borrow_index_last_update_round().set(0);
+
+        self.rate_controller_state()
+            .set_if_empty(PidControllerState::default());
+        self.borrow_index().set_if_empty(Ray::one());
+        self.supply_index().set_if_empty(Ray::one());
+        self.scaled_supplied_amount().set_if_empty(Ray::zero());
+        self.scaled_borrowed_amount().set_if_empty(Ray::zero());
+        self.rewards_reserves().set_if_empty(BigUint::zero());
+        self.protocol_revenue().set_if_empty(BigUint::zero());
+        self.borrow_index_last_update_timestamp()
+            .set_if_empty(self.blockchain().get_block_timestamp());
+        self.interest_approximation_terms()
+            .set_if_empty(DEFAULT_INTEREST_APPROXIMATION_TERMS);
+        self.migrate_price_aggregator_address();
     }
 }
@@ -0,0 +1,40 @@
+multiversx_sc::imports!();
+
+use common_structs::Ray;
+
+#[multiversx_sc::module]
+pub trait EventsModule {
+    #[event("update_interest_indexes")]
+    fn update_interest_indexes_event(
+        &self,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] borrow_index: &Ray<Self::Api>,
+        #[indexed] supply_index: &Ray<Self::Api>,
+        #[indexed] protocol_revenue: &BigUint,
+        timestamp: u64,
+    );
+
+    #[event("protocol_revenue_swept")]
+    fn protocol_revenue_swept_event(
+        &self,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] destination: &ManagedAddress,
+        amount: &BigUint,
+    );
+
+    #[event("bad_debt_covered")]
+    fn bad_debt_covered_event(
+        &self,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] bad_debt: &BigUint,
+        covered_amount: &BigUint,
+    );
+
+    #[event("loss_socialized")]
+    fn loss_socialized_event(
+        &self,
+        #[indexed] asset: &TokenIdentifier,
+        #[indexed] loss: &BigUint,
+        supply_index: &Ray<Self::Api>,
+    );
+}
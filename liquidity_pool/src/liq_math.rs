@@ -1,4 +1,4 @@
-use common_structs::BP;
+use common_structs::{PidControllerState, RateKink, Ray, BP, SECONDS_PER_YEAR};
 
 multiversx_sc::imports!();
 
@@ -24,6 +24,83 @@ pub trait MathModule {
         }
     }
 
+    // Piecewise-linear from base_rate at no utilisation through each kink, the last one being
+    // at full utilisation
+    fn compute_multi_kink_rate(
+        &self,
+        base_rate: &BigUint,
+        kinks: &ManagedVec<RateKink<Self::Api>>,
+        u_current: &BigUint,
+    ) -> BigUint {
+        let mut prev_utilisation = BigUint::zero();
+        let mut prev_rate = base_rate.clone();
+
+        for kink in kinks.iter() {
+            if *u_current <= kink.utilisation {
+                let rate_increase = (&kink.rate - &prev_rate) * (u_current - &prev_utilisation)
+                    / (&kink.utilisation - &prev_utilisation);
+                return prev_rate + rate_increase;
+            }
+            prev_utilisation = kink.utilisation;
+            prev_rate = kink.rate;
+        }
+
+        prev_rate
+    }
+
+    // base_rate + k_p * e + k_i * integral(e dt) + k_d * (e - previous e), e being the
+    // utilisation above target, kept within [min_rate, max_rate]
+    fn compute_pid_rate(
+        &self,
+        base_rate: &BigUint,
+        k_p: &BigUint,
+        k_i: &BigUint,
+        k_d: &BigUint,
+        min_rate: &BigUint,
+        max_rate: &BigUint,
+        error: &BigInt,
+        state: &PidControllerState<Self::Api>,
+    ) -> BigUint {
+        let proportional = &BigInt::from(k_p.clone()) * error;
+        let integral = &BigInt::from(k_i.clone()) * &state.integral;
+        let derivative = &BigInt::from(k_d.clone()) * &(error - &state.last_error);
+        let adjustment = (proportional + integral + derivative) / BigInt::from(BP as i64);
+
+        let rate = BigInt::from(base_rate.clone()) + adjustment;
+        let rate = match rate.sign() {
+            Sign::Minus => BigUint::zero(),
+            _ => rate.magnitude(),
+        };
+
+        BigUint::min(BigUint::max(rate, min_rate.clone()), max_rate.clone())
+    }
+
+    // e^(rate * t) - 1 for an annual rate over `delta_seconds`, from the first `terms` terms of
+    // its Taylor expansion; the per-second rate is folded into rate * t to keep the precision
+    fn compute_compounded_interest(
+        &self,
+        annual_rate: &BigUint,
+        delta_seconds: u64,
+        terms: u32,
+    ) -> Ray<Self::Api> {
+        let rate_times_time =
+            Ray::from_raw(Ray::from_bp(annual_rate).raw * delta_seconds / SECONDS_PER_YEAR);
+
+        let mut term = rate_times_time.clone();
+        let mut compounded_interest = Ray::zero();
+        for n in 1..=terms {
+            if n > 1 {
+                term = Ray::from_raw(term.mul(&rate_times_time).raw / n);
+            }
+            if term.is_zero() {
+                break;
+            }
+            compounded_interest = compounded_interest.add(&term);
+        }
+
+        compounded_interest
+    }
+
     fn compute_deposit_rate(
         &self,
         u_current: &BigUint,
@@ -50,29 +127,6 @@ pub trait MathModule {
         }
     }
 
-    fn compute_withdrawal_amount(
-        &self,
-        amount: &BigUint,
-        current_supply_index: &BigUint,
-        initial_supply_index: &BigUint,
-    ) -> BigUint {
-        let bp = BigUint::from(BP);
-        let interest = (current_supply_index - initial_supply_index) * amount / bp;
-
-        amount + &interest
-    }
-
-    fn compute_interest(
-        &self,
-        amount: &BigUint,
-        current_supply_index: &BigUint,
-        initial_supply_index: &BigUint,
-    ) -> BigUint {
-        let bp = BigUint::from(BP);
-
-        (current_supply_index - initial_supply_index) * amount / bp
-    }
-
     fn compute_borrowable_amount(
         &self,
         total_collateral: &BigUint,
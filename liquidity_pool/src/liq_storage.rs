@@ -1,7 +1,7 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
-use common_structs::PoolParams;
+use common_structs::{InterestRateStrategy, PidControllerState, PoolParams, Ray};
 
 #[multiversx_sc::module]
 pub trait StorageModule {
@@ -21,6 +21,10 @@ pub trait StorageModule {
     #[storage_mapper("rewards_reserves")]
     fn rewards_reserves(&self) -> SingleValueMapper<BigUint>;
 
+    #[view(getProtocolRevenue)]
+    #[storage_mapper("protocol_revenue")]
+    fn protocol_revenue(&self) -> SingleValueMapper<BigUint>;
+
     #[view(getLendToken)]
     #[storage_mapper("lend_token")]
     fn lend_token(&self) -> SingleValueMapper<TokenIdentifier>;
@@ -33,6 +37,14 @@ pub trait StorageModule {
     #[storage_mapper("pool_params")]
     fn pool_params(&self) -> SingleValueMapper<PoolParams<Self::Api>>;
 
+    #[view(getInterestRateStrategy)]
+    #[storage_mapper("interest_rate_strategy")]
+    fn interest_rate_strategy(&self) -> SingleValueMapper<InterestRateStrategy<Self::Api>>;
+
+    #[view(getRateControllerState)]
+    #[storage_mapper("rate_controller_state")]
+    fn rate_controller_state(&self) -> SingleValueMapper<PidControllerState<Self::Api>>;
+
     #[view(getTotalBorrow)]
     #[storage_mapper("borrowed_amount")]
     fn borrowed_amount(&self) -> SingleValueMapper<BigUint>;
@@ -43,13 +55,38 @@ pub trait StorageModule {
 
     #[view(getBorrowIndex)]
     #[storage_mapper("borrow_index")]
-    fn borrow_index(&self) -> SingleValueMapper<BigUint>;
+    fn borrow_index(&self) -> SingleValueMapper<Ray<Self::Api>>;
 
     #[view(getSupplyIndex)]
     #[storage_mapper("supply_index")]
-    fn supply_index(&self) -> SingleValueMapper<BigUint>;
+    fn supply_index(&self) -> SingleValueMapper<Ray<Self::Api>>;
+
+    #[view(getScaledSuppliedAmount)]
+    #[storage_mapper("scaled_supplied_amount")]
+    fn scaled_supplied_amount(&self) -> SingleValueMapper<Ray<Self::Api>>;
+
+    #[view(getScaledBorrowedAmount)]
+    #[storage_mapper("scaled_borrowed_amount")]
+    fn scaled_borrowed_amount(&self) -> SingleValueMapper<Ray<Self::Api>>;
+
+    #[view(borrowIndexLastUpdateTimestamp)]
+    #[storage_mapper("borrow_index_last_update_timestamp")]
+    fn borrow_index_last_update_timestamp(&self) -> SingleValueMapper<u64>;
+
+    #[view(getInterestApproximationTerms)]
+    #[storage_mapper("interest_approximation_terms")]
+    fn interest_approximation_terms(&self) -> SingleValueMapper<u32>;
+
+    // No cap when empty
+    #[view(getSupplyCap)]
+    #[storage_mapper("supply_cap")]
+    fn supply_cap(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getBorrowCap)]
+    #[storage_mapper("borrow_cap")]
+    fn borrow_cap(&self) -> SingleValueMapper<BigUint>;
 
-    #[view(borrowIndexLastUpdateRound)]
-    #[storage_mapper("borrow_index_last_update_round")]
-    fn borrow_index_last_update_round(&self) -> SingleValueMapper<u64>;
+    #[view(getSafetyModule)]
+    #[storage_mapper("safety_module")]
+    fn safety_module(&self) -> SingleValueMapper<ManagedAddress>;
 }
@@ -1,13 +1,19 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
-use crate::{liq_math, liq_storage};
+use crate::{liq_events, liq_math, liq_storage};
 
 use common_structs::*;
 
+const MAX_RATE_KINKS: usize = 8;
+
 #[multiversx_sc::module]
 pub trait UtilsModule:
-    liq_math::MathModule + liq_storage::StorageModule + price_aggregator_proxy::PriceAggregatorModule
+    liq_math::MathModule
+    + liq_storage::StorageModule
+    + liq_events::EventsModule
+    + price_aggregator_proxy::PriceAggregatorModule
+    + common_permissions::PermissionsModule
 {
     #[view(getCapitalUtilisation)]
     fn get_capital_utilisation(&self) -> BigUint {
@@ -25,11 +31,83 @@ pub trait UtilsModule:
         &reserve_amount + &borrowed_amount
     }
 
+    // Interest accrued on the position since it was last updated
     #[view(getDebtInterest)]
-    fn get_debt_interest(&self, amount: &BigUint, initial_borrow_index: &BigUint) -> BigUint {
-        let borrow_index_diff = self.get_borrow_index_diff(initial_borrow_index);
+    fn get_debt_interest(&self, borrow_position: &BorrowPosition<Self::Api>) -> BigUint {
+        let debt = self.get_debt_amount(&borrow_position.scaled_amount);
 
-        amount * &borrow_index_diff / BP
+        if debt > borrow_position.amount {
+            debt - &borrow_position.amount
+        } else {
+            BigUint::zero()
+        }
+    }
+
+    // None when the pool has no supply cap
+    #[view(getSupplyCapHeadroom)]
+    fn get_supply_cap_headroom(&self) -> OptionalValue<BigUint> {
+        if self.supply_cap().is_empty() {
+            return OptionalValue::None;
+        }
+
+        let cap = self.supply_cap().get();
+        let supplied = self.get_deposit_amount(&self.scaled_supplied_amount().get());
+        if supplied >= cap {
+            OptionalValue::Some(BigUint::zero())
+        } else {
+            OptionalValue::Some(cap - supplied)
+        }
+    }
+
+    // None when the pool has no borrow cap
+    #[view(getBorrowCapHeadroom)]
+    fn get_borrow_cap_headroom(&self) -> OptionalValue<BigUint> {
+        if self.borrow_cap().is_empty() {
+            return OptionalValue::None;
+        }
+
+        let cap = self.borrow_cap().get();
+        let borrowed = self.get_debt_amount(&self.scaled_borrowed_amount().get());
+        if borrowed >= cap {
+            OptionalValue::Some(BigUint::zero())
+        } else {
+            OptionalValue::Some(cap - borrowed)
+        }
+    }
+
+    // The caps bound the supply and the debt including their accrued interest
+    fn require_supply_cap_respected(&self) {
+        if self.supply_cap().is_empty() {
+            return;
+        }
+
+        let supplied = self.get_deposit_amount(&self.scaled_supplied_amount().get());
+        require!(supplied <= self.supply_cap().get(), "supply cap exceeded");
+    }
+
+    fn require_borrow_cap_respected(&self) {
+        if self.borrow_cap().is_empty() {
+            return;
+        }
+
+        let borrowed = self.get_debt_amount(&self.scaled_borrowed_amount().get());
+        require!(borrowed <= self.borrow_cap().get(), "borrow cap exceeded");
+    }
+
+    fn get_deposit_amount(&self, scaled_amount: &Ray<Self::Api>) -> BigUint {
+        scaled_amount.mul(&self.supply_index().get()).to_amount()
+    }
+
+    fn get_debt_amount(&self, scaled_amount: &Ray<Self::Api>) -> BigUint {
+        scaled_amount.mul(&self.borrow_index().get()).to_amount()
+    }
+
+    fn scale_deposit_amount(&self, amount: &BigUint) -> Ray<Self::Api> {
+        Ray::from_amount(amount).div(&self.supply_index().get())
+    }
+
+    fn scale_debt_amount(&self, amount: &BigUint) -> Ray<Self::Api> {
+        Ray::from_amount(amount).div(&self.borrow_index().get())
     }
 
     #[view(getDepositRate)]
@@ -50,73 +128,296 @@ pub trait UtilsModule:
         let pool_params = self.pool_params().get();
         let capital_utilisation = self.get_capital_utilisation();
 
-        self.compute_borrow_rate(
-            &pool_params.r_base,
-            &pool_params.r_slope1,
-            &pool_params.r_slope2,
-            &pool_params.u_optimal,
-            &capital_utilisation,
+        match self.interest_rate_strategy().get() {
+            InterestRateStrategy::JumpRate => self.compute_borrow_rate(
+                &pool_params.r_base,
+                &pool_params.r_slope1,
+                &pool_params.r_slope2,
+                &pool_params.u_optimal,
+                &capital_utilisation,
+            ),
+            InterestRateStrategy::Fixed { rate } => rate,
+            InterestRateStrategy::MultiKink { base_rate, kinks } => {
+                self.compute_multi_kink_rate(&base_rate, &kinks, &capital_utilisation)
+            }
+            InterestRateStrategy::Pid {
+                base_rate,
+                k_p,
+                k_i,
+                k_d,
+                min_rate,
+                max_rate,
+            } => self.compute_pid_rate(
+                &base_rate,
+                &k_p,
+                &k_i,
+                &k_d,
+                &min_rate,
+                &max_rate,
+                &self.get_utilisation_error(&capital_utilisation),
+                &self.rate_controller_state().get(),
+            ),
+        }
+    }
+
+    // Borrow and supply indexes with the interest accrued up to now, computed as
+    // update_interest_indexes would without storing them
+    #[view(getCurrentIndexes)]
+    fn get_current_indexes(&self) -> MultiValue2<Ray<Self::Api>, Ray<Self::Api>> {
+        let borrow_index = self.borrow_index().get();
+        let last_update_timestamp = self.borrow_index_last_update_timestamp().get();
+        let delta_seconds = self.get_timestamp_diff(last_update_timestamp);
+
+        if delta_seconds == 0 {
+            return MultiValue2::from((borrow_index, self.supply_index().get()));
+        }
+
+        let accrued_interest_rate = self.get_accrued_interest_rate(delta_seconds);
+        let (rewards_increase, _) = self.compute_rewards_increase(&accrued_interest_rate);
+
+        MultiValue2::from((
+            borrow_index.mul(&Ray::one().add(&accrued_interest_rate)),
+            self.compute_supply_index(&rewards_increase),
+        ))
+    }
+
+    fn get_accrued_interest_rate(&self, delta_seconds: u64) -> Ray<Self::Api> {
+        let borrow_rate = self.get_borrow_rate();
+
+        self.compute_compounded_interest(
+            &borrow_rate,
+            delta_seconds,
+            self.interest_approximation_terms().get(),
         )
     }
 
-    fn update_borrow_index(&self, borrow_rate: &BigUint, delta_rounds: u64) {
-        self.borrow_index()
-            .update(|new_index| *new_index += borrow_rate * delta_rounds);
+    fn get_utilisation_error(&self, capital_utilisation: &BigUint) -> BigInt {
+        let u_optimal = self.pool_params().get().u_optimal;
+
+        BigInt::from(capital_utilisation.clone()) - BigInt::from(u_optimal)
     }
 
-    fn update_supply_index(&self, rewards_increase: BigUint) {
-        let total_supplied_amount = self.supplied_amount().get();
+    fn require_valid_rate_strategy(&self, rate_strategy: &InterestRateStrategy<Self::Api>) {
+        match rate_strategy {
+            InterestRateStrategy::MultiKink { base_rate, kinks } => {
+                require!(
+                    !kinks.is_empty() && kinks.len() <= MAX_RATE_KINKS,
+                    "invalid number of rate kinks"
+                );
 
-        if total_supplied_amount != BigUint::zero() {
-            self.supply_index()
-                .update(|new_index| *new_index += rewards_increase * BP / total_supplied_amount);
+                let mut prev_utilisation = BigUint::zero();
+                let mut prev_rate = base_rate.clone();
+                for kink in kinks.iter() {
+                    require!(
+                        kink.utilisation > prev_utilisation && kink.rate >= prev_rate,
+                        "rate kinks must increase with utilisation"
+                    );
+                    prev_utilisation = kink.utilisation;
+                    prev_rate = kink.rate;
+                }
+                require!(
+                    prev_utilisation == BP,
+                    "last rate kink must be at full utilisation"
+                );
+            }
+            InterestRateStrategy::Pid {
+                base_rate,
+                min_rate,
+                max_rate,
+                ..
+            } => {
+                require!(
+                    min_rate <= base_rate && base_rate <= max_rate,
+                    "base rate out of the controller bounds"
+                );
+            }
+            _ => {}
         }
     }
 
-    fn update_rewards_reserves(&self, borrow_rate: &BigUint, delta_rounds: u64) -> BigUint {
-        let borrowed_amount = self.borrowed_amount().get();
-        let rewards_increase = borrow_rate * &borrowed_amount * delta_rounds / BP;
+    // Integrates the utilisation error of the elapsed period; the integral is bounded so that
+    // its share of the rate never exceeds max_rate
+    fn update_rate_controller(&self, delta_seconds: u64) {
+        let (k_i, max_rate) = match self.interest_rate_strategy().get() {
+            InterestRateStrategy::Pid { k_i, max_rate, .. } => (k_i, max_rate),
+            _ => return,
+        };
+        let error = self.get_utilisation_error(&self.get_capital_utilisation());
+
+        self.rate_controller_state().update(|state| {
+            state.integral += &error * &BigInt::from(delta_seconds as i64)
+                / BigInt::from(SECONDS_PER_YEAR as i64);
+            if k_i > 0 {
+                let bound = BigInt::from(max_rate * BP / k_i);
+                state.integral =
+                    BigInt::min(BigInt::max(state.integral.clone(), -bound.clone()), bound);
+            }
+            state.last_error = error;
+        });
+    }
+
+    fn update_borrow_index(&self, accrued_interest_rate: &Ray<Self::Api>) {
+        self.borrow_index().update(|index| {
+            *index = index.mul(&Ray::one().add(accrued_interest_rate));
+        });
+    }
+
+    // Suppliers share the interest in proportion to their scaled balances
+    fn update_supply_index(&self, rewards_increase: &Ray<Self::Api>) {
+        let supply_index = self.compute_supply_index(rewards_increase);
+        self.supply_index().set(supply_index);
+    }
+
+    fn compute_supply_index(&self, rewards_increase: &Ray<Self::Api>) -> Ray<Self::Api> {
+        let supply_index = self.supply_index().get();
+        let total_supplied = self.scaled_supplied_amount().get().mul(&supply_index);
 
+        if total_supplied.is_zero() {
+            return supply_index;
+        }
+
+        let supply_rate = rewards_increase.div(&total_supplied);
+        supply_index.mul(&Ray::one().add(&supply_rate))
+    }
+
+    // Interest accrued on the whole debt, computed before the borrow index moves; the reserve
+    // factor share goes to the protocol and the rest is returned for the suppliers
+    fn update_rewards_reserves(&self, accrued_interest_rate: &Ray<Self::Api>) -> Ray<Self::Api> {
+        let (suppliers_increase, protocol_share) =
+            self.compute_rewards_increase(accrued_interest_rate);
+        let suppliers_share = suppliers_increase.to_amount();
+
+        self.protocol_revenue()
+            .update(|protocol_revenue| *protocol_revenue += &protocol_share);
         self.rewards_reserves().update(|rewards_reserves| {
-            *rewards_reserves += &rewards_increase;
+            *rewards_reserves += suppliers_share;
         });
-        rewards_increase
+        suppliers_increase
     }
 
-    fn update_index_last_used(&self) {
-        let current_block_round = self.blockchain().get_block_round();
-        self.borrow_index_last_update_round()
-            .set(current_block_round);
+    // Suppliers' part of the interest accrued on the whole debt, and the protocol's share
+    fn compute_rewards_increase(
+        &self,
+        accrued_interest_rate: &Ray<Self::Api>,
+    ) -> (Ray<Self::Api>, BigUint) {
+        let total_debt = self
+            .scaled_borrowed_amount()
+            .get()
+            .mul(&self.borrow_index().get());
+        let rewards_increase = total_debt.mul(accrued_interest_rate);
+
+        let reserve_factor = self.pool_params().get().reserve_factor;
+        let protocol_share = rewards_increase.to_amount() * &reserve_factor / BP;
+
+        (
+            rewards_increase.saturating_sub(&Ray::from_amount(&protocol_share)),
+            protocol_share,
+        )
     }
 
-    fn get_round_diff(&self, initial_round: u64) -> u64 {
-        let current_round = self.blockchain().get_block_round();
-        require!(current_round >= initial_round, "Invalid round");
+    fn increase_scaled_supplied_amount(&self, scaled_amount: &Ray<Self::Api>) {
+        self.scaled_supplied_amount()
+            .update(|total| *total = total.add(scaled_amount));
+    }
+
+    fn decrease_scaled_supplied_amount(&self, scaled_amount: &Ray<Self::Api>) {
+        self.scaled_supplied_amount()
+            .update(|total| *total = total.saturating_sub(scaled_amount));
+    }
+
+    fn increase_scaled_borrowed_amount(&self, scaled_amount: &Ray<Self::Api>) {
+        self.scaled_borrowed_amount()
+            .update(|total| *total = total.add(scaled_amount));
+    }
+
+    fn decrease_scaled_borrowed_amount(&self, scaled_amount: &Ray<Self::Api>) {
+        self.scaled_borrowed_amount()
+            .update(|total| *total = total.saturating_sub(scaled_amount));
+    }
+
+    // Takes cleared debt out of both totals; the principal part is pro rata to the scaled debt,
+    // as positions may reach the pool already refreshed and their amount can't tell it apart
+    fn decrease_borrowed_debt(&self, scaled_amount: &Ray<Self::Api>) {
+        let scaled_borrowed_amount = self.scaled_borrowed_amount().get();
+        self.borrowed_amount().update(|total| {
+            if scaled_amount.raw >= scaled_borrowed_amount.raw {
+                *total = BigUint::zero();
+            } else {
+                *total -= &*total * &scaled_amount.raw / &scaled_borrowed_amount.raw;
+            }
+        });
+        self.decrease_scaled_borrowed_amount(scaled_amount);
+    }
 
-        current_round - initial_round
+    // Takes withdrawn supply out of both totals; as with debt, the principal part is pro rata
+    // to the scaled amount and the rest of the withdrawal is interest
+    fn decrease_supplied_amount(&self, scaled_amount: &Ray<Self::Api>) {
+        let scaled_supplied_amount = self.scaled_supplied_amount().get();
+        self.supplied_amount().update(|total| {
+            if scaled_amount.raw >= scaled_supplied_amount.raw {
+                *total = BigUint::zero();
+            } else {
+                *total -= &*total * &scaled_amount.raw / &scaled_supplied_amount.raw;
+            }
+        });
+        self.decrease_scaled_supplied_amount(scaled_amount);
+    }
+
+    // Spreads a loss over the suppliers by lowering the supply index, which can not reach zero
+    fn socialize_loss(&self, loss: &BigUint) {
+        let supply_index = self.supply_index().get();
+        let total_supplied = self.scaled_supplied_amount().get().mul(&supply_index);
+        if total_supplied.is_zero() {
+            return;
+        }
+
+        let remaining = total_supplied.saturating_sub(&Ray::from_amount(loss));
+        let new_supply_index = if remaining.is_zero() {
+            Ray::from_raw(BigUint::from(1u32))
+        } else {
+            supply_index.mul(&remaining.div(&total_supplied))
+        };
+        self.supplied_amount().update(|total| {
+            *total -= &*total * &(&total_supplied.raw - &remaining.raw) / &total_supplied.raw;
+        });
+        self.supply_index().set(&new_supply_index);
+        self.loss_socialized_event(&self.pool_asset().get(), loss, &new_supply_index);
     }
 
-    fn get_borrow_index_diff(&self, initial_borrow_index: &BigUint) -> BigUint {
-        let current_borrow_index = self.borrow_index().get();
-        require!(
-            &current_borrow_index >= initial_borrow_index,
-            "Invalid borrow index"
-        );
+    fn update_index_last_used(&self) {
+        let current_timestamp = self.blockchain().get_block_timestamp();
+        self.borrow_index_last_update_timestamp()
+            .set(current_timestamp);
+    }
 
-        current_borrow_index - initial_borrow_index
+    fn get_timestamp_diff(&self, initial_timestamp: u64) -> u64 {
+        let current_timestamp = self.blockchain().get_block_timestamp();
+        require!(current_timestamp >= initial_timestamp, "Invalid timestamp");
+
+        current_timestamp - initial_timestamp
     }
 
+    // Rates are annual, interest accrues on the seconds elapsed since the last update
     fn update_interest_indexes(&self) {
-        let borrow_index_last_update_round = self.borrow_index_last_update_round().get();
-        let delta_rounds = self.get_round_diff(borrow_index_last_update_round);
+        let last_update_timestamp = self.borrow_index_last_update_timestamp().get();
+        let delta_seconds = self.get_timestamp_diff(last_update_timestamp);
 
-        if delta_rounds > 0 {
-            let borrow_rate = self.get_borrow_rate();
+        if delta_seconds > 0 {
+            let accrued_interest_rate = self.get_accrued_interest_rate(delta_seconds);
 
-            self.update_borrow_index(&borrow_rate, delta_rounds);
-            let rewards_increase = self.update_rewards_reserves(&borrow_rate, delta_rounds);
-            self.update_supply_index(rewards_increase);
+            let rewards_increase = self.update_rewards_reserves(&accrued_interest_rate);
+            self.update_borrow_index(&accrued_interest_rate);
+            self.update_supply_index(&rewards_increase);
+            self.update_rate_controller(delta_seconds);
             self.update_index_last_used();
+
+            self.update_interest_indexes_event(
+                &self.pool_asset().get(),
+                &self.borrow_index().get(),
+                &self.supply_index().get(),
+                &self.protocol_revenue().get(),
+                self.blockchain().get_block_timestamp(),
+            );
         }
     }
 
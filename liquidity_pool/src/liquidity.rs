@@ -3,21 +3,73 @@ multiversx_sc::derive_imports!();
 
 use common_structs::*;
 
+use super::liq_events;
 use super::liq_math;
 use super::liq_storage;
 use super::liq_utils;
 use super::tokens;
+use super::tokens::safety_module_proxy_mod::ProxyTrait as _;
+
+const MAX_INTEREST_APPROXIMATION_TERMS: u32 = 8;
 
 #[multiversx_sc::module]
 pub trait LiquidityModule:
     liq_storage::StorageModule
+    + liq_events::EventsModule
     + tokens::TokensModule
     + common_tokens::AccountTokenModule
     + liq_utils::UtilsModule
     + liq_math::MathModule
     + price_aggregator_proxy::PriceAggregatorModule
     + common_checks::ChecksModule
+    + common_permissions::PermissionsModule
+    + common_pause::PauseModule
 {
+    #[only_owner]
+    #[endpoint(setSafetyModule)]
+    fn set_safety_module(&self, safety_module_address: ManagedAddress) {
+        self.require_non_zero_address(&safety_module_address);
+
+        self.safety_module().set(&safety_module_address);
+    }
+
+    #[only_owner]
+    #[endpoint(setLendToken)]
+    fn set_lend_token(&self, lend_token: TokenIdentifier) {
+        self.lend_token().set(&lend_token);
+    }
+
+    // Without a cap the pool accepts any amount
+    #[only_owner]
+    #[endpoint(setSupplyCap)]
+    fn set_supply_cap(&self, cap: OptionalValue<BigUint>) {
+        match cap {
+            OptionalValue::Some(cap) => self.supply_cap().set(&cap),
+            OptionalValue::None => self.supply_cap().clear(),
+        }
+    }
+
+    #[only_owner]
+    #[endpoint(setBorrowCap)]
+    fn set_borrow_cap(&self, cap: OptionalValue<BigUint>) {
+        match cap {
+            OptionalValue::Some(cap) => self.borrow_cap().set(&cap),
+            OptionalValue::None => self.borrow_cap().clear(),
+        }
+    }
+
+    #[only_owner]
+    #[endpoint(setInterestApproximationTerms)]
+    fn set_interest_approximation_terms(&self, terms: u32) {
+        require!(
+            terms > 0 && terms <= MAX_INTEREST_APPROXIMATION_TERMS,
+            "invalid number of approximation terms"
+        );
+
+        self.update_interest_indexes();
+        self.interest_approximation_terms().set(terms);
+    }
+
     #[only_owner]
     #[payable("*")]
     #[endpoint(updateCollateralWithInterest)]
@@ -26,19 +78,11 @@ pub trait LiquidityModule:
         mut deposit_position: DepositPosition<Self::Api>,
     ) -> DepositPosition<Self::Api> {
         let round = self.blockchain().get_block_round();
-        let supply_index = self.supply_index().get();
 
         self.update_interest_indexes();
 
-        let accrued_interest = self.compute_interest(
-            &deposit_position.amount,
-            &supply_index,
-            &deposit_position.initial_supply_index,
-        );
-
-        deposit_position.amount += accrued_interest;
+        deposit_position.amount = self.get_deposit_amount(&deposit_position.scaled_amount);
         deposit_position.round = round;
-        deposit_position.initial_supply_index = supply_index;
 
         deposit_position
     }
@@ -51,18 +95,11 @@ pub trait LiquidityModule:
         mut borrow_position: BorrowPosition<Self::Api>,
     ) -> BorrowPosition<Self::Api> {
         let round = self.blockchain().get_block_round();
-        let borrow_index = self.borrow_index().get();
 
         self.update_interest_indexes();
 
-        let accumulated_debt = self.get_debt_interest(
-            &borrow_position.amount,
-            &borrow_position.initial_borrow_index,
-        );
-
-        borrow_position.amount += accumulated_debt;
+        borrow_position.amount = self.get_debt_amount(&borrow_position.scaled_amount);
         borrow_position.round = round;
-        borrow_position.initial_borrow_index = borrow_index;
 
         borrow_position
     }
@@ -74,11 +111,12 @@ pub trait LiquidityModule:
         &self,
         deposit_position: DepositPosition<Self::Api>,
     ) -> DepositPosition<Self::Api> {
+        self.require_not_paused(PausableAction::Supply);
+
         let (deposit_asset, deposit_amount) = self.call_value().single_fungible_esdt();
         let pool_asset = self.pool_asset().get();
         let round = self.blockchain().get_block_round();
-        let supply_index = self.supply_index().get();
-        let mut ret_deposit_position = deposit_position.clone();
+        let mut ret_deposit_position = deposit_position;
 
         require!(
             deposit_asset == pool_asset,
@@ -87,18 +125,104 @@ pub trait LiquidityModule:
 
         self.update_interest_indexes();
 
-        if deposit_position.amount != 0 {
-            ret_deposit_position = self.update_collateral_with_interest(deposit_position);
-        }
-        ret_deposit_position.amount += &deposit_amount;
+        let added_scaled_amount = self.scale_deposit_amount(&deposit_amount);
+        self.increase_scaled_supplied_amount(&added_scaled_amount);
+        self.require_supply_cap_respected();
+        ret_deposit_position.scaled_amount =
+            ret_deposit_position.scaled_amount.add(&added_scaled_amount);
+        ret_deposit_position.amount = self.get_deposit_amount(&ret_deposit_position.scaled_amount);
         ret_deposit_position.round = round;
-        ret_deposit_position.initial_supply_index = supply_index;
 
         self.reserves().update(|x| *x += &deposit_amount);
         self.supplied_amount().update(|x| *x += deposit_amount);
         ret_deposit_position
     }
 
+    // Supplies liquidity outside of any account, for the lend tokens minted by the lending pool;
+    // their amount is the scaled deposit rounded down, so every lend token stays backed
+    #[only_owner]
+    #[payable("*")]
+    #[endpoint(supplyForLendTokens)]
+    fn supply_for_lend_tokens(&self) -> BigUint {
+        self.require_not_paused(PausableAction::Supply);
+
+        let (deposit_asset, deposit_amount) = self.call_value().single_fungible_esdt();
+        let pool_asset = self.pool_asset().get();
+
+        require!(
+            deposit_asset == pool_asset,
+            "asset not supported for this liquidity pool"
+        );
+
+        self.update_interest_indexes();
+
+        let lend_amount = self
+            .scale_deposit_amount(&deposit_amount)
+            .to_amount_rounded_down();
+        require!(lend_amount > 0, "deposit too small");
+
+        self.increase_scaled_supplied_amount(&Ray::from_amount(&lend_amount));
+        self.require_supply_cap_respected();
+        self.reserves().update(|x| *x += &deposit_amount);
+        self.supplied_amount().update(|x| *x += deposit_amount);
+
+        lend_amount
+    }
+
+    // The lend tokens are already burnt by the lending pool
+    #[only_owner]
+    #[endpoint(redeemLendTokens)]
+    fn redeem_lend_tokens(&self, initial_caller: ManagedAddress, lend_amount: BigUint) -> BigUint {
+        self.require_not_paused(PausableAction::Withdraw);
+
+        let pool_asset = self.pool_asset().get();
+
+        self.require_non_zero_address(&initial_caller);
+        self.require_amount_greater_than_zero(&lend_amount);
+
+        self.update_interest_indexes();
+
+        let scaled_amount = Ray::from_amount(&lend_amount);
+        let withdrawal_amount = scaled_amount
+            .mul(&self.supply_index().get())
+            .to_amount_rounded_down();
+
+        self.reserves().update(|asset_reserve| {
+            require!(*asset_reserve >= withdrawal_amount, "insufficient funds");
+            *asset_reserve -= &withdrawal_amount;
+        });
+        self.decrease_supplied_amount(&scaled_amount);
+
+        self.send()
+            .direct_esdt(&initial_caller, &pool_asset, 0, &withdrawal_amount);
+
+        withdrawal_amount
+    }
+
+    // Lend tokens, burnt by the lending pool, become collateral; their scaled amount is already
+    // part of the supply
+    #[only_owner]
+    #[endpoint(addLendTokenCollateral)]
+    fn add_lend_token_collateral(
+        &self,
+        lend_amount: BigUint,
+        mut deposit_position: DepositPosition<Self::Api>,
+    ) -> DepositPosition<Self::Api> {
+        self.require_not_paused(PausableAction::Supply);
+
+        self.require_amount_greater_than_zero(&lend_amount);
+
+        self.update_interest_indexes();
+
+        deposit_position.scaled_amount = deposit_position
+            .scaled_amount
+            .add(&Ray::from_amount(&lend_amount));
+        deposit_position.amount = self.get_deposit_amount(&deposit_position.scaled_amount);
+        deposit_position.round = self.blockchain().get_block_round();
+
+        deposit_position
+    }
+
     #[only_owner]
     #[payable("*")]
     #[endpoint]
@@ -108,10 +232,12 @@ pub trait LiquidityModule:
         borrow_amount: BigUint,
         existing_borrow_position: BorrowPosition<Self::Api>,
     ) -> BorrowPosition<Self::Api> {
+        self.require_not_paused(PausableAction::Borrow);
+
         let pool_token_id = self.pool_asset().get();
 
         let asset_reserve = self.reserves().get();
-        let mut ret_borrow_position = existing_borrow_position.clone();
+        let mut ret_borrow_position = existing_borrow_position;
         self.require_non_zero_address(&initial_caller);
         require!(
             asset_reserve >= borrow_amount,
@@ -119,15 +245,15 @@ pub trait LiquidityModule:
         );
 
         self.update_interest_indexes();
-        if ret_borrow_position.amount != 0 && ret_borrow_position.nft.is_none() {
-            ret_borrow_position = self.update_borrows_with_debt(existing_borrow_position);
-        }
 
         let round = self.blockchain().get_block_round();
-        let borrow_index = self.borrow_index().get();
-        ret_borrow_position.amount += &borrow_amount;
+        let added_scaled_amount = self.scale_debt_amount(&borrow_amount);
+        self.increase_scaled_borrowed_amount(&added_scaled_amount);
+        self.require_borrow_cap_respected();
+        ret_borrow_position.scaled_amount =
+            ret_borrow_position.scaled_amount.add(&added_scaled_amount);
+        ret_borrow_position.amount = self.get_debt_amount(&ret_borrow_position.scaled_amount);
         ret_borrow_position.round = round;
-        ret_borrow_position.initial_borrow_index = borrow_index;
 
         self.borrowed_amount()
             .update(|total| *total += &borrow_amount);
@@ -149,6 +275,8 @@ pub trait LiquidityModule:
         borrow_amount: BigUint,
         existing_borrow_positions: ManagedVec<BorrowPosition<Self::Api>>,
     ) -> ManagedVec<BorrowPosition<Self::Api>> {
+        self.require_not_paused(PausableAction::Borrow);
+
         let pool_token_id = self.pool_asset().get();
 
         let asset_reserve = self.reserves().get();
@@ -160,10 +288,13 @@ pub trait LiquidityModule:
 
         self.update_interest_indexes();
 
-        let borrow_index = self.borrow_index().get();
-        for mut pos in &existing_borrow_positions {
-            pos.initial_borrow_index = borrow_index.clone();
+        let mut borrow_positions = ManagedVec::new();
+        for mut position in &existing_borrow_positions {
+            position.scaled_amount = self.scale_debt_amount(&position.amount);
+            self.increase_scaled_borrowed_amount(&position.scaled_amount);
+            borrow_positions.push(position);
         }
+        self.require_borrow_cap_respected();
 
         self.borrowed_amount()
             .update(|total| *total += &borrow_amount);
@@ -173,7 +304,7 @@ pub trait LiquidityModule:
         self.send()
             .direct_esdt(&initial_caller, &pool_token_id, 0, &borrow_amount);
 
-        existing_borrow_positions
+        borrow_positions
     }
 
     #[only_owner]
@@ -185,6 +316,8 @@ pub trait LiquidityModule:
         amount: BigUint,
         mut deposit_position: DepositPosition<Self::Api>,
     ) -> DepositPosition<Self::Api> {
+        self.require_not_paused(PausableAction::Withdraw);
+
         let pool_asset = self.pool_asset().get();
 
         self.require_non_zero_address(&initial_caller);
@@ -192,23 +325,34 @@ pub trait LiquidityModule:
 
         self.update_interest_indexes();
 
-        // Withdrawal amount = initial_deposit + Interest
-        let withdrawal_amount = self.compute_withdrawal_amount(
-            &amount,
-            &self.supply_index().get(),
-            &deposit_position.initial_supply_index,
+        // The position is valued at the current supply index and the amount, interest included,
+        // is taken out of its scaled balance; withdrawing all of it clears the position
+        deposit_position.amount = self.get_deposit_amount(&deposit_position.scaled_amount);
+        let removed_scaled_amount = if amount == deposit_position.amount {
+            deposit_position.scaled_amount.clone()
+        } else {
+            self.scale_deposit_amount(&amount)
+        };
+        require!(
+            removed_scaled_amount.raw <= deposit_position.scaled_amount.raw,
+            "not enough collateral to withdraw"
         );
+        let withdrawal_amount = amount;
 
+        require!(
+            removed_scaled_amount.raw <= self.scaled_supplied_amount().get().raw,
+            "insufficient funds"
+        );
         self.reserves().update(|asset_reserve| {
             require!(*asset_reserve >= withdrawal_amount, "insufficient funds");
             *asset_reserve -= &withdrawal_amount;
         });
+        self.decrease_supplied_amount(&removed_scaled_amount);
 
-        self.supplied_amount().update(|asset_supplied_amount| {
-            require!(*asset_supplied_amount >= amount, "insufficient funds");
-            *asset_supplied_amount -= &amount;
-        });
-        deposit_position.amount -= &amount;
+        deposit_position.scaled_amount = deposit_position
+            .scaled_amount
+            .saturating_sub(&removed_scaled_amount);
+        deposit_position.amount = self.get_deposit_amount(&deposit_position.scaled_amount);
 
         self.send()
             .direct_esdt(&initial_caller, &pool_asset, 0, &withdrawal_amount);
@@ -224,6 +368,8 @@ pub trait LiquidityModule:
         initial_caller: ManagedAddress,
         borrow_position: BorrowPosition<Self::Api>,
     ) -> BorrowPosition<Self::Api> {
+        self.require_not_paused(PausableAction::Repay);
+
         let (received_asset, mut received_amount) = self.call_value().single_fungible_esdt();
         let pool_asset = self.pool_asset().get();
 
@@ -236,12 +382,8 @@ pub trait LiquidityModule:
 
         self.update_interest_indexes();
 
-        let accumulated_debt = self.get_debt_interest(
-            &borrow_position.amount,
-            &borrow_position.initial_borrow_index,
-        );
-
         let mut ret_borrow_position = self.update_borrows_with_debt(borrow_position);
+        let initial_scaled_amount = ret_borrow_position.scaled_amount.clone();
 
         let total_owed_with_interest = ret_borrow_position.amount.clone();
 
@@ -251,14 +393,16 @@ pub trait LiquidityModule:
                 .direct_esdt(&initial_caller, &received_asset, 0, &extra_amount);
             received_amount -= &extra_amount;
             ret_borrow_position.amount = BigUint::zero();
+            ret_borrow_position.scaled_amount = Ray::zero();
         } else {
-            let principal_amount = &received_amount - &accumulated_debt;
-            ret_borrow_position.amount -= &principal_amount;
+            ret_borrow_position.scaled_amount = ret_borrow_position
+                .scaled_amount
+                .saturating_sub(&self.scale_debt_amount(&received_amount));
+            ret_borrow_position.amount = self.get_debt_amount(&ret_borrow_position.scaled_amount);
         }
-
-        let amount_without_interest = &received_amount - &accumulated_debt;
-        self.borrowed_amount()
-            .update(|total| *total -= amount_without_interest);
+        self.decrease_borrowed_debt(
+            &initial_scaled_amount.saturating_sub(&ret_borrow_position.scaled_amount),
+        );
 
         self.reserves().update(|total| *total += &received_amount);
 
@@ -275,6 +419,8 @@ pub trait LiquidityModule:
             MultiValue2<EsdtTokenPayment, BorrowPosition<Self::Api>>,
         >,
     ) -> MultiValueEncoded<MultiValue2<EsdtTokenPayment, BorrowPosition<Self::Api>>> {
+        self.require_not_paused(PausableAction::Repay);
+
         let (received_asset, received_amount) = self.call_value().single_fungible_esdt();
         let pool_asset = self.pool_asset().get();
 
@@ -288,33 +434,32 @@ pub trait LiquidityModule:
         self.update_interest_indexes();
 
         let mut total_received_amount = received_amount.clone();
-        let mut total_accumulated_debt = BigUint::zero();
         let mut total_amount_paid = BigUint::zero();
         let mut vec_borrow_positions: MultiValueEncoded<
             MultiValue2<EsdtTokenPayment<Self::Api>, BorrowPosition<Self::Api>>,
         > = MultiValueEncoded::new();
         for data in borrow_positions.clone().into_iter() {
             let (token, borrow_position) = data.into_tuple();
-            let accumulated_debt = self.get_debt_interest(
-                &borrow_position.amount,
-                &borrow_position.initial_borrow_index,
-            );
-            total_accumulated_debt += &accumulated_debt;
-
             let mut ret_borrow_position = self.update_borrows_with_debt(borrow_position);
+            let initial_scaled_amount = ret_borrow_position.scaled_amount.clone();
 
             if &total_received_amount >= &ret_borrow_position.amount {
                 total_received_amount -= &ret_borrow_position.amount;
                 total_amount_paid += &ret_borrow_position.amount;
                 ret_borrow_position.amount = BigUint::zero();
-            } else if total_received_amount > accumulated_debt
-                && total_received_amount > BigUint::zero()
-            {
-                let remaining_amount = &total_received_amount - &accumulated_debt;
-                ret_borrow_position.amount -= &remaining_amount;
+                ret_borrow_position.scaled_amount = Ray::zero();
+            } else if total_received_amount > 0 {
+                ret_borrow_position.scaled_amount = ret_borrow_position
+                    .scaled_amount
+                    .saturating_sub(&self.scale_debt_amount(&total_received_amount));
+                ret_borrow_position.amount =
+                    self.get_debt_amount(&ret_borrow_position.scaled_amount);
                 total_amount_paid += total_received_amount;
                 total_received_amount = BigUint::zero();
             }
+            self.decrease_borrowed_debt(
+                &initial_scaled_amount.saturating_sub(&ret_borrow_position.scaled_amount),
+            );
             vec_borrow_positions.push(MultiValue2::from((token, ret_borrow_position)));
         }
 
@@ -327,19 +472,127 @@ pub trait LiquidityModule:
             );
         }
 
-        self.borrowed_amount()
-            .update(|total| *total -= &total_amount_paid - &total_accumulated_debt);
-
         self.reserves().update(|total| *total += &total_amount_paid);
         vec_borrow_positions
     }
 
     #[only_owner]
-    #[endpoint(sendTokens)]
-    fn send_tokens(&self, initial_caller: ManagedAddress, payment_amount: BigUint) {
+    #[payable("*")]
+    #[endpoint(seizeCollateral)]
+    fn seize_collateral(
+        &self,
+        liquidator: ManagedAddress,
+        amount: BigUint,
+        deposit_position: DepositPosition<Self::Api>,
+    ) -> DepositPosition<Self::Api> {
+        self.require_not_paused(PausableAction::Liquidate);
+
         let pool_asset = self.pool_asset().get();
 
+        self.require_non_zero_address(&liquidator);
+        self.require_amount_greater_than_zero(&amount);
+
+        let mut ret_deposit_position = self.update_collateral_with_interest(deposit_position);
+        require!(
+            ret_deposit_position.amount >= amount,
+            "not enough collateral to seize"
+        );
+        let removed_scaled_amount = if ret_deposit_position.amount == amount {
+            ret_deposit_position.scaled_amount.clone()
+        } else {
+            self.scale_deposit_amount(&amount)
+        };
+        ret_deposit_position.scaled_amount = ret_deposit_position
+            .scaled_amount
+            .saturating_sub(&removed_scaled_amount);
+        self.decrease_supplied_amount(&removed_scaled_amount);
+        ret_deposit_position.amount -= &amount;
+
+        self.reserves().update(|asset_reserve| {
+            require!(*asset_reserve >= amount, "insufficient funds");
+            *asset_reserve -= &amount;
+        });
+
         self.send()
-            .direct_esdt(&initial_caller, &pool_asset, 0, &payment_amount);
+            .direct_esdt(&liquidator, &pool_asset, 0, &amount);
+
+        ret_deposit_position
+    }
+
+    // Drops debt that no collateral backs anymore and asks the safety module to refill
+    // the reserves, what it can't cover is taken from the suppliers; returns the written
+    // off debt and the amount covered
+    #[only_owner]
+    #[endpoint(writeOffBadDebt)]
+    fn write_off_bad_debt(
+        &self,
+        borrow_position: BorrowPosition<Self::Api>,
+    ) -> MultiValue2<BigUint, BigUint> {
+        let pool_asset = self.pool_asset().get();
+        let borrow_position = self.update_borrows_with_debt(borrow_position);
+        let bad_debt = borrow_position.amount;
+        self.decrease_borrowed_debt(&borrow_position.scaled_amount);
+
+        if bad_debt == 0 {
+            return MultiValue2::from((bad_debt, BigUint::zero()));
+        }
+
+        let covered_amount = if self.safety_module().is_empty() {
+            BigUint::zero()
+        } else {
+            let covered_amount: BigUint = self
+                .safety_module_proxy(self.safety_module().get())
+                .take_funds(pool_asset.clone(), bad_debt.clone())
+                .execute_on_dest_context();
+            self.reserves().update(|total| *total += &covered_amount);
+            self.bad_debt_covered_event(&pool_asset, &bad_debt, &covered_amount);
+            covered_amount
+        };
+
+        if covered_amount < bad_debt {
+            self.socialize_loss(&(&bad_debt - &covered_amount));
+        }
+
+        MultiValue2::from((bad_debt, covered_amount))
+    }
+
+    // Sends the accrued protocol revenue to the treasury, or funds the safety module with it
+    // when no treasury is given; only what is left in reserves can be swept
+    #[only_owner]
+    #[endpoint(sweepProtocolRevenue)]
+    fn sweep_protocol_revenue(&self, treasury: OptionalValue<ManagedAddress>) -> BigUint {
+        self.update_interest_indexes();
+
+        let protocol_revenue = self.protocol_revenue().get();
+        let amount = BigUint::min(protocol_revenue.clone(), self.reserves().get());
+        if amount == 0 {
+            return amount;
+        }
+
+        self.protocol_revenue().set(protocol_revenue - &amount);
+        self.reserves().update(|total| *total -= &amount);
+
+        let pool_asset = self.pool_asset().get();
+        match treasury.into_option() {
+            Some(treasury_address) => {
+                self.require_non_zero_address(&treasury_address);
+                self.send()
+                    .direct_esdt(&treasury_address, &pool_asset, 0, &amount);
+                self.protocol_revenue_swept_event(&pool_asset, &treasury_address, &amount);
+            }
+            None => {
+                require!(!self.safety_module().is_empty(), "no safety module set");
+
+                let safety_module = self.safety_module().get();
+                let _: BigUint = self
+                    .safety_module_proxy(safety_module.clone())
+                    .fund_from_pool(OptionalValue::<BigUint>::None)
+                    .with_esdt_transfer((pool_asset.clone(), 0, amount.clone()))
+                    .execute_on_dest_context();
+                self.protocol_revenue_swept_event(&pool_asset, &safety_module, &amount);
+            }
+        }
+
+        amount
     }
 }
@@ -1,6 +1,7 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
+use super::liq_events;
 use super::liq_math;
 use super::liq_storage;
 use super::liq_utils;
@@ -8,15 +9,23 @@ use super::liq_utils;
 #[multiversx_sc::module]
 pub trait TokensModule:
     liq_storage::StorageModule
+    + liq_events::EventsModule
     + liq_utils::UtilsModule
     + liq_math::MathModule
     + price_aggregator_proxy::PriceAggregatorModule
+    + common_permissions::PermissionsModule
 {
     #[proxy]
     fn lending_pool_proxy(
         &self,
         sc_address: ManagedAddress,
     ) -> lending_pool_proxy_mod::Proxy<Self::Api>;
+
+    #[proxy]
+    fn safety_module_proxy(
+        &self,
+        sc_address: ManagedAddress,
+    ) -> safety_module_proxy_mod::Proxy<Self::Api>;
 }
 
 // can't simply import, we would have a circular dependency
@@ -29,3 +38,17 @@ mod lending_pool_proxy_mod {
         fn set_token_id_after_issue(&self, token_id: TokenIdentifier);
     }
 }
+
+pub mod safety_module_proxy_mod {
+    multiversx_sc::imports!();
+
+    #[multiversx_sc::proxy]
+    pub trait SafetyModule {
+        #[payable("*")]
+        #[endpoint(fundFromPool)]
+        fn fund_from_pool(&self, min_wegld_out: OptionalValue<BigUint>) -> BigUint;
+
+        #[endpoint(takeFunds)]
+        fn take_funds(&self, pool_token: TokenIdentifier, amount: BigUint) -> BigUint;
+    }
+}
@@ -5,9 +5,9 @@
 ////////////////////////////////////////////////////
 
 // Init:                                 1
-// Endpoints:                           31
+// Endpoints:                           70
 // Async Callback (empty):               1
-// Total number of exported functions:  33
+// Total number of exported functions:  72
 
 #![no_std]
 #![feature(lang_items)]
@@ -23,33 +23,72 @@ multiversx_sc_wasm_adapter::endpoints! {
         getReserves => reserves
         getSuppliedAmount => supplied_amount
         getRewardsReserves => rewards_reserves
+        getProtocolRevenue => protocol_revenue
         getLendToken => lend_token
         borrowToken => borrow_token
         getPoolParams => pool_params
+        getInterestRateStrategy => interest_rate_strategy
+        getRateControllerState => rate_controller_state
         getTotalBorrow => borrowed_amount
         getLiquidationThreshold => liquidation_threshold
         getBorrowIndex => borrow_index
         getSupplyIndex => supply_index
-        borrowIndexLastUpdateRound => borrow_index_last_update_round
+        getScaledSuppliedAmount => scaled_supplied_amount
+        getScaledBorrowedAmount => scaled_borrowed_amount
+        borrowIndexLastUpdateTimestamp => borrow_index_last_update_timestamp
+        getInterestApproximationTerms => interest_approximation_terms
+        getSupplyCap => supply_cap
+        getBorrowCap => borrow_cap
+        getSafetyModule => safety_module
         getAccountToken => account_token
         getDebtNFT => debt_nft_token
         getAccountPositions => account_positions
+        setSafetyModule => set_safety_module
+        setLendToken => set_lend_token
+        setSupplyCap => set_supply_cap
+        setBorrowCap => set_borrow_cap
+        setInterestApproximationTerms => set_interest_approximation_terms
         updateCollateralWithInterest => update_collateral_with_interest
         updateBorrowsWithDebt => update_borrows_with_debt
         addCollateral => add_collateral
+        supplyForLendTokens => supply_for_lend_tokens
+        redeemLendTokens => redeem_lend_tokens
+        addLendTokenCollateral => add_lend_token_collateral
         borrow => borrow
         borrowWithNFTs => borrow_bulk_nfts
         remove_collateral => remove_collateral
         repay => repay
         repayNFTs => repay_nfts
-        sendTokens => send_tokens
+        seizeCollateral => seize_collateral
+        writeOffBadDebt => write_off_bad_debt
+        sweepProtocolRevenue => sweep_protocol_revenue
         getCapitalUtilisation => get_capital_utilisation
         getTotalCapital => get_total_capital
         getDebtInterest => get_debt_interest
+        getSupplyCapHeadroom => get_supply_cap_headroom
+        getBorrowCapHeadroom => get_borrow_cap_headroom
         getDepositRate => get_deposit_rate
         getBorrowRate => get_borrow_rate
+        getCurrentIndexes => get_current_indexes
         setPriceAggregatorAddress => set_price_aggregator_address
-        getAggregatorAddress => price_aggregator_address
+        addPriceAggregatorAddress => add_price_aggregator_address
+        removePriceAggregatorAddress => remove_price_aggregator_address
+        setFallbackPriceSource => set_fallback_price_source
+        setMaxMissingPriceFeeds => set_max_missing_price_feeds
+        setMaxPriceStaleness => set_max_price_staleness
+        getAggregatorAddresses => price_aggregator_addresses
+        getFallbackPriceSource => fallback_price_source
+        getMaxMissingPriceFeeds => max_missing_price_feeds
+        getMaxPriceStaleness => get_max_price_staleness
+        getLastPriceRound => last_price_round
+        grantRole => grant_role
+        revokeRole => revoke_role
+        hasRole => has_role
+        getRoleMembers => role_members
+        pause => pause
+        unpause => unpause
+        isPaused => is_paused
+        getPausedActions => paused_actions
     )
 }
 
@@ -0,0 +1,148 @@
+#![no_std]
+
+multiversx_sc::imports!();
+
+// Constant product pair without fees, exposing the swap endpoints of a DEX pair
+#[multiversx_sc::contract]
+pub trait PairMock {
+    #[init]
+    fn init(&self, first_token_id: TokenIdentifier, second_token_id: TokenIdentifier) {
+        require!(first_token_id != second_token_id, "identical tokens");
+
+        self.first_token_id().set(&first_token_id);
+        self.second_token_id().set(&second_token_id);
+    }
+
+    #[payable("*")]
+    #[endpoint(addInitialLiquidity)]
+    fn add_initial_liquidity(&self) {
+        for payment in self.call_value().all_esdt_transfers().iter() {
+            self.require_pair_token(&payment.token_identifier);
+            self.reserve(&payment.token_identifier)
+                .update(|reserve| *reserve += &payment.amount);
+        }
+    }
+
+    #[payable("*")]
+    #[endpoint(swapTokensFixedInput)]
+    fn swap_tokens_fixed_input(
+        &self,
+        token_out: TokenIdentifier,
+        amount_out_min: BigUint,
+    ) -> EsdtTokenPayment {
+        let (token_in, amount_in) = self.call_value().single_fungible_esdt();
+        self.require_swap_tokens(&token_in, &token_out);
+
+        let amount_out = self.get_amount_out(token_in.clone(), amount_in.clone());
+        require!(amount_out > 0, "zero output amount");
+        require!(amount_out >= amount_out_min, "Slippage exceeded");
+
+        self.reserve(&token_in)
+            .update(|reserve| *reserve += &amount_in);
+        self.reserve(&token_out)
+            .update(|reserve| *reserve -= &amount_out);
+
+        let caller = self.blockchain().get_caller();
+        self.send().direct_esdt(&caller, &token_out, 0, &amount_out);
+
+        EsdtTokenPayment::new(token_out, 0, amount_out)
+    }
+
+    // Returns the bought tokens and whatever was left of the input
+    #[payable("*")]
+    #[endpoint(swapTokensFixedOutput)]
+    fn swap_tokens_fixed_output(
+        &self,
+        token_out: TokenIdentifier,
+        amount_out: BigUint,
+    ) -> MultiValue2<EsdtTokenPayment, EsdtTokenPayment> {
+        let (token_in, amount_in_max) = self.call_value().single_fungible_esdt();
+        self.require_swap_tokens(&token_in, &token_out);
+
+        let amount_in = self.get_amount_in(token_out.clone(), amount_out.clone());
+        require!(amount_in <= amount_in_max, "Slippage exceeded");
+
+        self.reserve(&token_in)
+            .update(|reserve| *reserve += &amount_in);
+        self.reserve(&token_out)
+            .update(|reserve| *reserve -= &amount_out);
+
+        let caller = self.blockchain().get_caller();
+        let residuum = &amount_in_max - &amount_in;
+        self.send().direct_esdt(&caller, &token_out, 0, &amount_out);
+        if residuum > 0 {
+            self.send().direct_esdt(&caller, &token_in, 0, &residuum);
+        }
+
+        MultiValue2::from((
+            EsdtTokenPayment::new(token_out, 0, amount_out),
+            EsdtTokenPayment::new(token_in, 0, residuum),
+        ))
+    }
+
+    #[view(getAmountOut)]
+    fn get_amount_out(&self, token_in: TokenIdentifier, amount_in: BigUint) -> BigUint {
+        let token_out = self.get_other_token(&token_in);
+        let reserve_in = self.reserve(&token_in).get();
+        let reserve_out = self.reserve(&token_out).get();
+
+        &amount_in * &reserve_out / (reserve_in + amount_in)
+    }
+
+    #[view(getAmountIn)]
+    fn get_amount_in(&self, token_wanted: TokenIdentifier, amount_wanted: BigUint) -> BigUint {
+        let token_in = self.get_other_token(&token_wanted);
+        let reserve_in = self.reserve(&token_in).get();
+        let reserve_out = self.reserve(&token_wanted).get();
+        require!(amount_wanted < reserve_out, "not enough reserve");
+
+        reserve_in * &amount_wanted / (reserve_out - amount_wanted) + 1u32
+    }
+
+    // Spot price, without price impact
+    #[view(getEquivalent)]
+    fn get_equivalent(&self, token_in: TokenIdentifier, amount_in: BigUint) -> BigUint {
+        let token_out = self.get_other_token(&token_in);
+        let reserve_in = self.reserve(&token_in).get();
+        require!(reserve_in > 0, "no liquidity");
+
+        amount_in * self.reserve(&token_out).get() / reserve_in
+    }
+
+    fn get_other_token(&self, token_id: &TokenIdentifier) -> TokenIdentifier {
+        self.require_pair_token(token_id);
+
+        let first_token_id = self.first_token_id().get();
+        if token_id == &first_token_id {
+            self.second_token_id().get()
+        } else {
+            first_token_id
+        }
+    }
+
+    fn require_swap_tokens(&self, token_in: &TokenIdentifier, token_out: &TokenIdentifier) {
+        require!(
+            &self.get_other_token(token_in) == token_out,
+            "invalid swap tokens"
+        );
+    }
+
+    fn require_pair_token(&self, token_id: &TokenIdentifier) {
+        require!(
+            token_id == &self.first_token_id().get() || token_id == &self.second_token_id().get(),
+            "token not in pair"
+        );
+    }
+
+    #[view(getFirstTokenId)]
+    #[storage_mapper("first_token_id")]
+    fn first_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+
+    #[view(getSecondTokenId)]
+    #[storage_mapper("second_token_id")]
+    fn second_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+
+    #[view(getReserve)]
+    #[storage_mapper("reserve")]
+    fn reserve(&self, token_id: &TokenIdentifier) -> SingleValueMapper<BigUint>;
+}
@@ -0,0 +1,34 @@
+// Code generated by the multiversx-sc multi-contract system. DO NOT EDIT.
+
+////////////////////////////////////////////////////
+////////////////// AUTO-GENERATED //////////////////
+////////////////////////////////////////////////////
+
+// Init:                                 1
+// Endpoints:                            9
+// Async Callback (empty):               1
+// Total number of exported functions:  11
+
+#![no_std]
+#![feature(lang_items)]
+
+multiversx_sc_wasm_adapter::allocator!();
+multiversx_sc_wasm_adapter::panic_handler!();
+
+multiversx_sc_wasm_adapter::endpoints! {
+    pair_mock
+    (
+        init => init
+        addInitialLiquidity => add_initial_liquidity
+        swapTokensFixedInput => swap_tokens_fixed_input
+        swapTokensFixedOutput => swap_tokens_fixed_output
+        getAmountOut => get_amount_out
+        getAmountIn => get_amount_in
+        getEquivalent => get_equivalent
+        getFirstTokenId => first_token_id
+        getSecondTokenId => second_token_id
+        getReserve => reserve
+    )
+}
+
+multiversx_sc_wasm_adapter::async_callback_empty! {}
@@ -1,5 +1,7 @@
 #![allow(non_snake_case)]
 
+use safety_module::cooldown::ProxyTrait as _;
+use safety_module::dex::ProxyTrait as _;
 use safety_module::ProxyTrait as _;
 use safety_module::*;
 use multiversx_sc_snippets::{
@@ -39,18 +41,38 @@ async fn main() {
     let mut state = State::new().await;
     match cmd.as_str() {
         "deploy" => state.deploy().await,
+        "registerNftToken" => state.register_nft_token().await,
         "addPool" => state.add_pool().await,
         "removePool" => state.remove_pool().await,
+        "setMaxSlashingPercentage" => state.set_max_slashing_percentage().await,
         "fund" => state.fund().await,
         "fundFromPool" => state.fund_from_pool().await,
         "takeFunds" => state.take_funds().await,
         "withdraw" => state.withdraw().await,
+        "getExchangeRate" => state.get_exchange_rate().await,
+        "getShareValue" => state.get_share_value().await,
         "setLocalRolesNftToken" => state.set_local_roles_nft_token().await,
         "pools" => state.pools().await,
+        "getMaxSlashingPercentage" => state.max_slashing_percentage().await,
+        "getSlashingWindow" => state.slashing_window().await,
+        "getSlashingWindowStart" => state.slashing_window_start().await,
+        "getSlashedInWindow" => state.slashed_in_window().await,
         "wegld_token" => state.wegld_token().await,
-        "deposit_apy" => state.deposit_apy().await,
+        "getTotalShares" => state.total_shares().await,
         "nftToken" => state.nft_token().await,
         "lastErrorMessage" => state.last_error_message().await,
+        "setCooldownParams" => state.set_cooldown_params().await,
+        "cooldown" => state.cooldown().await,
+        "getUnstakeWindow" => state.get_unstake_window().await,
+        "canUnstake" => state.can_unstake().await,
+        "getCooldownStart" => state.cooldown_start().await,
+        "getCooldownPeriod" => state.cooldown_period().await,
+        "getUnstakeWindowLength" => state.unstake_window().await,
+        "setPair" => state.set_pair().await,
+        "removePair" => state.remove_pair().await,
+        "setMaxSlippage" => state.set_max_slippage().await,
+        "getPair" => state.pair().await,
+        "getMaxSlippage" => state.max_slippage().await,
         _ => panic!("unknown command: {}", &cmd),
     }
 }
@@ -81,13 +103,12 @@ impl State {
 
     async fn deploy(&mut self) {
         let wegld_token = TokenIdentifier::from_esdt_bytes(&b""[..]);
-        let depositors_apy = BigUint::<DebugApi>::from(0u128);
 
         let result: multiversx_sc_snippets::InteractorResult<()> = self
             .interactor
             .sc_deploy(
                 self.contract
-                    .init(wegld_token, depositors_apy)
+                    .init(wegld_token)
                     .into_blockchain_call()
                     .from(&self.wallet_address)
                     .code_metadata(CodeMetadata::all())
@@ -104,6 +125,29 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn register_nft_token(&mut self) {
+        let egld_amount = BigUint::<DebugApi>::from(0u128);
+
+        let token_name = ManagedBuffer::new_from_bytes(&b""[..]);
+        let ticker = ManagedBuffer::new_from_bytes(&b""[..]);
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .register_nft_token(token_name, ticker)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .egld_value(egld_amount)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn add_pool(&mut self) {
         let token = TokenIdentifier::from_esdt_bytes(&b""[..]);
         let address = bech32::decode("");
@@ -143,6 +187,26 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn set_max_slashing_percentage(&mut self) {
+        let max_slashing_percentage = BigUint::<DebugApi>::from(0u128);
+        let slashing_window = 0u64;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_max_slashing_percentage(max_slashing_percentage, slashing_window)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn fund(&mut self) {
         let token_id = b"";
         let token_nonce = 0u64;
@@ -150,7 +214,7 @@ impl State {
 
         let caller = OptionalValue::Some(bech32::decode(""));
 
-        let result: multiversx_sc_snippets::InteractorResult<()> = self
+        let result: multiversx_sc_snippets::InteractorResult<EsdtTokenPayment<DebugApi>> = self
             .interactor
             .sc_call_get_result(
                 self.contract
@@ -173,11 +237,13 @@ impl State {
         let token_nonce = 0u64;
         let token_amount = BigUint::<DebugApi>::from(0u128);
 
-        let result: multiversx_sc_snippets::InteractorResult<()> = self
+        let min_wegld_out = OptionalValue::Some(BigUint::<DebugApi>::from(0u128));
+
+        let result: multiversx_sc_snippets::InteractorResult<BigUint<DebugApi>> = self
             .interactor
             .sc_call_get_result(
                 self.contract
-                    .fund_from_pool()
+                    .fund_from_pool(min_wegld_out)
                     .into_blockchain_call()
                     .from(&self.wallet_address)
             .esdt_transfer(token_id.to_vec(), token_nonce, token_amount)
@@ -195,7 +261,7 @@ impl State {
         let pool_token = TokenIdentifier::from_esdt_bytes(&b""[..]);
         let amount = BigUint::<DebugApi>::from(0u128);
 
-        let result: multiversx_sc_snippets::InteractorResult<()> = self
+        let result: multiversx_sc_snippets::InteractorResult<BigUint<DebugApi>> = self
             .interactor
             .sc_call_get_result(
                 self.contract
@@ -234,6 +300,26 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn get_exchange_rate(&mut self) {
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.get_exchange_rate())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn get_share_value(&mut self) {
+        let shares = BigUint::<DebugApi>::from(0u128);
+
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.get_share_value(shares))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn set_local_roles_nft_token(&mut self) {
         let roles = PlaceholderInput;
 
@@ -264,6 +350,42 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn max_slashing_percentage(&mut self) {
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.max_slashing_percentage())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn slashing_window(&mut self) {
+        let result_value: u64 = self
+            .interactor
+            .vm_query(self.contract.slashing_window())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn slashing_window_start(&mut self) {
+        let result_value: u64 = self
+            .interactor
+            .vm_query(self.contract.slashing_window_start())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn slashed_in_window(&mut self) {
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.slashed_in_window())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
     async fn wegld_token(&mut self) {
         let result_value: TokenIdentifier<DebugApi> = self
             .interactor
@@ -273,10 +395,10 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
-    async fn deposit_apy(&mut self) {
+    async fn total_shares(&mut self) {
         let result_value: BigUint<DebugApi> = self
             .interactor
-            .vm_query(self.contract.deposit_apy())
+            .vm_query(self.contract.total_shares())
             .await;
 
         println!("Result: {:?}", result_value);
@@ -300,4 +422,176 @@ impl State {
         println!("Result: {:?}", result_value);
     }
 
+    async fn set_cooldown_params(&mut self) {
+        let cooldown_period = 0u64;
+        let unstake_window = 0u64;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_cooldown_params(cooldown_period, unstake_window)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn cooldown(&mut self) {
+        let token_id = b"";
+        let token_nonce = 0u64;
+        let token_amount = BigUint::<DebugApi>::from(0u128);
+
+        let result: multiversx_sc_snippets::InteractorResult<MultiValue2<u64, u64>> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .cooldown()
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+            .esdt_transfer(token_id.to_vec(), token_nonce, token_amount)
+
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn get_unstake_window(&mut self) {
+        let nft_nonce = 0u64;
+
+        let result_value: MultiValue2<u64, u64> = self
+            .interactor
+            .vm_query(self.contract.get_unstake_window(nft_nonce))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn can_unstake(&mut self) {
+        let nft_nonce = 0u64;
+
+        let result_value: bool = self
+            .interactor
+            .vm_query(self.contract.can_unstake(nft_nonce))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn cooldown_start(&mut self) {
+        let nft_nonce = 0u64;
+
+        let result_value: u64 = self
+            .interactor
+            .vm_query(self.contract.cooldown_start(nft_nonce))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn cooldown_period(&mut self) {
+        let result_value: u64 = self
+            .interactor
+            .vm_query(self.contract.cooldown_period())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn unstake_window(&mut self) {
+        let result_value: u64 = self
+            .interactor
+            .vm_query(self.contract.unstake_window())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_pair(&mut self) {
+        let token = TokenIdentifier::from_esdt_bytes(&b""[..]);
+        let pair_address = bech32::decode("");
+        let decimals = 0u8;
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_pair(token, pair_address, decimals)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn remove_pair(&mut self) {
+        let token = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .remove_pair(token)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn set_max_slippage(&mut self) {
+        let max_slippage = BigUint::<DebugApi>::from(0u128);
+
+        let result: multiversx_sc_snippets::InteractorResult<()> = self
+            .interactor
+            .sc_call_get_result(
+                self.contract
+                    .set_max_slippage(max_slippage)
+                    .into_blockchain_call()
+                    .from(&self.wallet_address)
+                    .gas_limit(DEFAULT_GAS_LIMIT)
+                    .into(),
+            )
+            .await;
+        let result_value = result.value();
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn pair(&mut self) {
+        let token = TokenIdentifier::from_esdt_bytes(&b""[..]);
+
+        let result_value: ManagedAddress<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.pairs(&token))
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
+
+    async fn max_slippage(&mut self) {
+        let result_value: BigUint<DebugApi> = self
+            .interactor
+            .vm_query(self.contract.max_slippage())
+            .await;
+
+        println!("Result: {:?}", result_value);
+    }
 }
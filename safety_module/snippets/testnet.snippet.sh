@@ -30,7 +30,6 @@ NFT_TICKER=0x4e46542d623935616131 #NFT-b95aa1
 NFT_NAME=0x4e4654 #NFT
 
 # 0,03 = 3%
-APY = 30000000
 
 
 
@@ -47,7 +46,7 @@ issueBUSD(){
 }
 
 deploy(){
-    erdpy contract deploy --project=${PROJECT} --pem=${ALICE} --arguments ${ESDT_TICKER} ${APY} --proxy=${PROXY} --outfile="deploy.json" --recall-nonce --send --gas-limit="600000000" --chain ${CHAIN_ID} --send || return
+    erdpy contract deploy --project=${PROJECT} --pem=${ALICE} --arguments ${ESDT_TICKER} --proxy=${PROXY} --outfile="deploy.json" --recall-nonce --send --gas-limit="600000000" --chain ${CHAIN_ID} --send || return
 
     TRANSACTION=$(erdpy data parse --file="deploy.json" --expression="data['emitted_tx']['hash']")
     ADDRESS=$(erdpy data parse --file="deploy.json" --expression="data['emitted_tx']['address']")
@@ -61,7 +60,7 @@ deploy(){
 }
 
 upgrade(){
-    erdpy contract upgrade ${ADDRESS} --metadata-payable --project=${PROJECT} --pem=${ALICE} --arguments ${ESDT_TICKER} 30000000 --proxy=${PROXY} --outfile="upgrade.json" --recall-nonce --send --gas-limit="600000000" --chain ${CHAIN_ID}
+    erdpy contract upgrade ${ADDRESS} --metadata-payable --project=${PROJECT} --pem=${ALICE} --arguments ${ESDT_TICKER} --proxy=${PROXY} --outfile="upgrade.json" --recall-nonce --send --gas-limit="600000000" --chain ${CHAIN_ID}
     echo ""
     echo "Smart contract address: ${ADDRESS}"
 }
@@ -85,7 +84,7 @@ takeFunds(){
 }
 
 NFTIssue(){
-    erdpy contract call ${ADDRESS} --function=nftIssue --pem=${ALICE} --value=5000000000000000000 --arguments 0x4e4654 0x4e4654 --proxy=${PROXY} --outfile="NFTIssue.json" --recall-nonce --send --gas-limit="600000000" --chain ${CHAIN_ID} --send || return
+    erdpy contract call ${ADDRESS} --function=registerNftToken --pem=${ALICE} --value=5000000000000000000 --arguments 0x4e4654 0x4e4654 --proxy=${PROXY} --outfile="NFTIssue.json" --recall-nonce --send --gas-limit="600000000" --chain ${CHAIN_ID} --send || return
 }
 
 getNFTTokenInfo(){
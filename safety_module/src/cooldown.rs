@@ -0,0 +1,75 @@
+multiversx_sc::imports!();
+
+use crate::events;
+
+use common_structs::Role;
+
+#[multiversx_sc::module]
+pub trait CooldownModule: events::EventsModule + common_permissions::PermissionsModule {
+    #[endpoint(setCooldownParams)]
+    fn set_cooldown_params(&self, cooldown_period: u64, unstake_window: u64) {
+        self.require_role(Role::RiskAdmin);
+
+        require!(unstake_window > 0, "unstake window can not be zero");
+
+        self.cooldown_period().set(cooldown_period);
+        self.unstake_window().set(unstake_window);
+    }
+
+    fn start_cooldown(&self, nft_nonce: u64) -> MultiValue2<u64, u64> {
+        require!(
+            !self.unstake_window().is_empty(),
+            "cooldown parameters are not set"
+        );
+
+        self.cooldown_start(nft_nonce)
+            .set(self.blockchain().get_block_timestamp());
+
+        self.get_unstake_window(nft_nonce)
+    }
+
+    // Start and end timestamps of the position's unstake window, zero if it never asked for a cooldown
+    #[view(getUnstakeWindow)]
+    fn get_unstake_window(&self, nft_nonce: u64) -> MultiValue2<u64, u64> {
+        if self.cooldown_start(nft_nonce).is_empty() {
+            return MultiValue2::from((0, 0));
+        }
+
+        let window_start = self.cooldown_start(nft_nonce).get() + self.cooldown_period().get();
+        let window_end = window_start + self.unstake_window().get();
+
+        MultiValue2::from((window_start, window_end))
+    }
+
+    #[view(canUnstake)]
+    fn can_unstake(&self, nft_nonce: u64) -> bool {
+        let (window_start, window_end) = self.get_unstake_window(nft_nonce).into_tuple();
+        let current_timestamp = self.blockchain().get_block_timestamp();
+
+        window_end > 0 && window_start <= current_timestamp && current_timestamp <= window_end
+    }
+
+    fn require_unstake_window_open(&self, nft_nonce: u64) {
+        require!(
+            !self.cooldown_start(nft_nonce).is_empty(),
+            "cooldown not started"
+        );
+
+        let (window_start, window_end) = self.get_unstake_window(nft_nonce).into_tuple();
+        let current_timestamp = self.blockchain().get_block_timestamp();
+        require!(current_timestamp >= window_start, "cooldown not finished");
+        require!(current_timestamp <= window_end, "unstake window expired");
+    }
+
+    #[view(getCooldownStart)]
+    #[storage_mapper("cooldown_start")]
+    fn cooldown_start(&self, nft_nonce: u64) -> SingleValueMapper<u64>;
+
+    #[view(getCooldownPeriod)]
+    #[storage_mapper("cooldown_period")]
+    fn cooldown_period(&self) -> SingleValueMapper<u64>;
+
+    #[view(getUnstakeWindowLength)]
+    #[storage_mapper("unstake_window")]
+    fn unstake_window(&self) -> SingleValueMapper<u64>;
+}
@@ -0,0 +1,190 @@
+multiversx_sc::imports!();
+
+use common_structs::{Role, BP, EGLD_DECIMALS};
+use price_aggregator_proxy::DOLLAR_TICKER;
+
+#[multiversx_sc::module]
+pub trait DexModule:
+    common_permissions::PermissionsModule + price_aggregator_proxy::PriceAggregatorModule
+{
+    #[endpoint(setPair)]
+    fn set_pair(&self, token: TokenIdentifier, pair_address: ManagedAddress, decimals: u8) {
+        self.require_role(Role::ListingAdmin);
+
+        require!(token != self.wegld_token().get(), "WEGLD needs no pair");
+        require!(
+            self.blockchain().is_smart_contract(&pair_address),
+            "invalid pair address"
+        );
+
+        self.pairs(&token).set(&pair_address);
+        self.token_decimals(&token).set(decimals);
+    }
+
+    #[endpoint(removePair)]
+    fn remove_pair(&self, token: TokenIdentifier) {
+        self.require_role(Role::ListingAdmin);
+
+        self.pairs(&token).clear();
+        self.token_decimals(&token).clear();
+    }
+
+    #[endpoint(setMaxSlippage)]
+    fn set_max_slippage(&self, max_slippage: BigUint) {
+        self.require_role(Role::RiskAdmin);
+
+        require!(max_slippage < BP, "slippage must be lower than 100%");
+
+        self.max_slippage().set(&max_slippage);
+    }
+
+    // Sells the whole amount for WEGLD; the oracle price minus the allowed slippage
+    // is always enforced, on top of the caller's own minimum
+    fn convert_to_wegld(
+        &self,
+        token: TokenIdentifier,
+        amount: BigUint,
+        min_out: BigUint,
+    ) -> BigUint {
+        let wegld_token = self.wegld_token().get();
+        if token == wegld_token {
+            require!(amount >= min_out, "Slippage exceeded");
+            return amount;
+        }
+
+        let pair_address = self.get_pair_address(&token);
+        let equivalent = self.get_oracle_wegld_equivalent(&token, &amount);
+        let slippage_min_out = equivalent * (BigUint::from(BP) - self.max_slippage().get()) / BP;
+        let amount_out_min = BigUint::max(slippage_min_out, min_out);
+
+        let payment_out: EsdtTokenPayment = self
+            .pair_proxy(pair_address)
+            .swap_tokens_fixed_input(wegld_token.clone(), amount_out_min.clone())
+            .with_esdt_transfer((token, 0, amount))
+            .execute_on_dest_context();
+        require!(
+            payment_out.token_identifier == wegld_token && payment_out.amount >= amount_out_min,
+            "Slippage exceeded"
+        );
+
+        payment_out.amount
+    }
+
+    // Buys exactly `amount` of the pool token, paying at most the oracle price plus the allowed slippage
+    fn convert_wegld(&self, pool_token: TokenIdentifier, amount: BigUint) -> BigUint {
+        let wegld_token = self.wegld_token().get();
+        if pool_token == wegld_token {
+            return amount;
+        }
+
+        let amount_in_max = self.get_wegld_amount_in_max(&pool_token, &amount);
+        require!(
+            amount_in_max <= self.get_wegld_balance(),
+            "not enough WEGLD"
+        );
+
+        let pair_address = self.get_pair_address(&pool_token);
+        let (payment_out, residuum): (EsdtTokenPayment, EsdtTokenPayment) = self
+            .pair_proxy(pair_address)
+            .swap_tokens_fixed_output(pool_token.clone(), amount.clone())
+            .with_esdt_transfer((wegld_token, 0, amount_in_max.clone()))
+            .execute_on_dest_context::<MultiValue2<EsdtTokenPayment, EsdtTokenPayment>>()
+            .into_tuple();
+        require!(
+            payment_out.token_identifier == pool_token && payment_out.amount >= amount,
+            "Slippage exceeded"
+        );
+
+        amount_in_max - residuum.amount
+    }
+
+    // The most WEGLD that buying `amount` of the pool token may cost
+    fn get_wegld_amount_in_max(&self, pool_token: &TokenIdentifier, amount: &BigUint) -> BigUint {
+        if pool_token == &self.wegld_token().get() {
+            return amount.clone();
+        }
+
+        let equivalent = self.get_oracle_wegld_equivalent(pool_token, amount);
+
+        equivalent * (self.max_slippage().get() + BP) / BP
+    }
+
+    // Priced through the aggregators rather than the pair, so the pool being traded against
+    // can not move its own limits
+    fn get_oracle_wegld_equivalent(&self, token: &TokenIdentifier, amount: &BigUint) -> BigUint {
+        require!(!self.pairs(token).is_empty(), "no pair for token");
+
+        let token_price = self.get_dollar_price(token);
+        let wegld_price = self.get_dollar_price(&self.wegld_token().get());
+        let token_unit = BigUint::from(10u64).pow(self.token_decimals(token).get() as u32);
+        let wegld_unit = BigUint::from(10u64).pow(EGLD_DECIMALS);
+
+        amount * &token_price * wegld_unit / (wegld_price * token_unit)
+    }
+
+    fn get_dollar_price(&self, token: &TokenIdentifier) -> BigUint {
+        let ticker = self.get_token_ticker(token);
+        // Only reached from swaps, so the rounds can be recorded
+        self.update_price_rounds(ticker.clone(), ManagedBuffer::new_from_bytes(DOLLAR_TICKER));
+        match self.get_price_for_pair(ticker, ManagedBuffer::new_from_bytes(DOLLAR_TICKER)) {
+            Some(price) => price,
+            None => sc_panic!("price feed unavailable or stale"),
+        }
+    }
+
+    fn get_wegld_balance(&self) -> BigUint {
+        self.blockchain().get_esdt_balance(
+            &self.blockchain().get_sc_address(),
+            &self.wegld_token().get(),
+            0,
+        )
+    }
+
+    fn get_pair_address(&self, token: &TokenIdentifier) -> ManagedAddress {
+        require!(!self.pairs(token).is_empty(), "no pair for token");
+
+        self.pairs(token).get()
+    }
+
+    #[proxy]
+    fn pair_proxy(&self, sc_address: ManagedAddress) -> pair_proxy_mod::Proxy<Self::Api>;
+
+    #[view(getPair)]
+    #[storage_mapper("pairs")]
+    fn pairs(&self, token: &TokenIdentifier) -> SingleValueMapper<ManagedAddress>;
+
+    #[view(getTokenDecimals)]
+    #[storage_mapper("token_decimals")]
+    fn token_decimals(&self, token: &TokenIdentifier) -> SingleValueMapper<u8>;
+
+    #[view(getMaxSlippage)]
+    #[storage_mapper("max_slippage")]
+    fn max_slippage(&self) -> SingleValueMapper<BigUint>;
+
+    #[view]
+    #[storage_mapper("wegld_token")]
+    fn wegld_token(&self) -> SingleValueMapper<TokenIdentifier>;
+}
+
+mod pair_proxy_mod {
+    multiversx_sc::imports!();
+
+    #[multiversx_sc::proxy]
+    pub trait Pair {
+        #[payable("*")]
+        #[endpoint(swapTokensFixedInput)]
+        fn swap_tokens_fixed_input(
+            &self,
+            token_out: TokenIdentifier,
+            amount_out_min: BigUint,
+        ) -> EsdtTokenPayment;
+
+        #[payable("*")]
+        #[endpoint(swapTokensFixedOutput)]
+        fn swap_tokens_fixed_output(
+            &self,
+            token_out: TokenIdentifier,
+            amount_out: BigUint,
+        ) -> MultiValue2<EsdtTokenPayment, EsdtTokenPayment>;
+    }
+}
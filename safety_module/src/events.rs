@@ -0,0 +1,49 @@
+multiversx_sc::imports!();
+
+#[multiversx_sc::module]
+pub trait EventsModule {
+    #[event("fund")]
+    fn fund_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] nft_nonce: u64,
+        #[indexed] amount: &BigUint,
+        shares: &BigUint,
+    );
+
+    #[event("fund_from_pool")]
+    fn fund_from_pool_event(
+        &self,
+        #[indexed] pool: &ManagedAddress,
+        #[indexed] token: &TokenIdentifier,
+        #[indexed] amount: &BigUint,
+        wegld_amount: &BigUint,
+    );
+
+    #[event("take_funds")]
+    fn take_funds_event(
+        &self,
+        #[indexed] pool: &ManagedAddress,
+        #[indexed] token: &TokenIdentifier,
+        #[indexed] requested_amount: &BigUint,
+        covered_amount: &BigUint,
+    );
+
+    #[event("withdraw")]
+    fn withdraw_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] nft_nonce: u64,
+        #[indexed] shares: &BigUint,
+        amount: &BigUint,
+    );
+
+    #[event("cooldown")]
+    fn cooldown_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        #[indexed] nft_nonce: u64,
+        #[indexed] window_start: u64,
+        window_end: u64,
+    );
+}
@@ -4,57 +4,154 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
-use common_structs::{DepositPosition, BP, SECONDS_PER_YEAR};
+pub mod cooldown;
+pub mod dex;
+pub mod events;
+
+use common_structs::{Role, SafetyModulePosition, BP};
+
+const NFT_TOKEN_DECIMALS: usize = 18;
+// One virtual share backed by one virtual WEGLD: a first depositor who inflates the share
+// price by donating WEGLD loses more to rounding than later deposits do
+const VIRTUAL_SHARES: u64 = 1;
+const VIRTUAL_WEGLD: u64 = 1;
 
 #[multiversx_sc::contract]
-pub trait SafetyModule {
+pub trait SafetyModule:
+    cooldown::CooldownModule
+    + dex::DexModule
+    + events::EventsModule
+    + common_permissions::PermissionsModule
+    + price_aggregator_proxy::PriceAggregatorModule
+{
     #[init]
-    fn init(&self, wegld_token: TokenIdentifier, depositors_apy: BigUint) {
+    fn init(&self, wegld_token: TokenIdentifier) {
         self.wegld_token().set(&wegld_token);
-        self.deposit_apy().set(&depositors_apy);
+        self.migrate_price_aggregator_address();
     }
 
     #[only_owner]
+    #[payable("EGLD")]
+    #[endpoint(registerNftToken)]
+    fn register_nft_token(&self, token_name: ManagedBuffer, ticker: ManagedBuffer) {
+        require!(self.nft_token().is_empty(), "nft token already issued");
+
+        let payment_amount = self.call_value().egld_value().clone_value();
+        self.send()
+            .esdt_system_sc_proxy()
+            .register_meta_esdt(
+                payment_amount,
+                &token_name,
+                &ticker,
+                MetaTokenProperties {
+                    num_decimals: NFT_TOKEN_DECIMALS,
+                    can_freeze: true,
+                    can_wipe: true,
+                    can_pause: true,
+                    can_transfer_create_role: false,
+                    can_change_owner: false,
+                    can_upgrade: true,
+                    can_add_special_roles: true,
+                },
+            )
+            .async_call()
+            .with_callback(self.callbacks().issue_callback())
+            .call_and_exit();
+    }
+
     #[endpoint(addPool)]
     fn add_pool(&self, token: TokenIdentifier, address: &ManagedAddress) {
+        self.require_role(Role::ListingAdmin);
+
         self.pools(token).set(address);
     }
 
-    #[only_owner]
     #[endpoint(removePool)]
     fn remove_pool(&self, token: TokenIdentifier) {
+        self.require_role(Role::ListingAdmin);
+
         self.pools(token).clear();
     }
 
+    // Nothing is paid out of the module until the owner sets a slashing percentage; it caps
+    // everything taken within one slashing window, not each call
+    #[endpoint(setMaxSlashingPercentage)]
+    fn set_max_slashing_percentage(&self, max_slashing_percentage: BigUint, slashing_window: u64) {
+        self.require_role(Role::RiskAdmin);
+
+        require!(
+            max_slashing_percentage <= BP,
+            "slashing percentage can not exceed 100%"
+        );
+        require!(slashing_window > 0, "slashing window can not be zero");
+
+        self.max_slashing_percentage().set(&max_slashing_percentage);
+        self.slashing_window().set(slashing_window);
+    }
+
+    // Deposits WEGLD for shares of the module, minted as a position NFT
     #[payable("*")]
     #[endpoint(fund)]
-    fn fund(self, caller: OptionalValue<ManagedAddress>) {
+    fn fund(&self, caller: OptionalValue<ManagedAddress>) -> EsdtTokenPayment {
         let (token, payment) = self.call_value().egld_or_single_fungible_esdt();
 
         require!(payment > 0, "amount must be greater than 0");
         require!(token == self.wegld_token().get(), "invalid token");
+        require!(!self.nft_token().is_empty(), "No nft token issued");
 
         let caller_address = caller
             .into_option()
             .unwrap_or_else(|| self.blockchain().get_caller());
 
-        let nft_token = self.nft_token().get();
+        // The payment is already part of the balance
+        let total_wegld = self.get_wegld_balance() - &payment;
+        let total_shares = self.total_shares().get();
+        let shares = self.convert_to_shares(&payment, &total_wegld, &total_shares);
+        require!(shares > 0, "deposit too small");
+
+        let position = SafetyModulePosition {
+            deposited_amount: payment,
+            shares: shares.clone(),
+            round: self.blockchain().get_block_round(),
+        };
+        let nft_nonce = self.mint_deposit_nft(&position, shares.clone());
+        self.total_shares().set(total_shares + &shares);
 
+        let nft_token = self.nft_token().get();
         self.send()
-            .direct_esdt(&caller_address, &nft_token, 0, &payment);
+            .direct_esdt(&caller_address, &nft_token, nft_nonce, &shares);
+        self.fund_event(
+            &caller_address,
+            nft_nonce,
+            &position.deposited_amount,
+            &shares,
+        );
+
+        EsdtTokenPayment::new(nft_token, nft_nonce, shares)
     }
 
     #[payable("*")]
     #[endpoint(fundFromPool)]
-    fn fund_from_pool(&self) {
+    fn fund_from_pool(&self, min_wegld_out: OptionalValue<BigUint>) -> BigUint {
         let (token, payment) = self.call_value().single_fungible_esdt();
         require!(payment > 0, "amount must be greater than 0");
 
-        self.convert_to_wegld(token, payment);
+        let min_out = min_wegld_out.into_option().unwrap_or_default();
+        let wegld_amount = self.convert_to_wegld(token.clone(), payment.clone(), min_out);
+
+        self.fund_from_pool_event(
+            &self.blockchain().get_caller(),
+            &token,
+            &payment,
+            &wegld_amount,
+        );
+        wegld_amount
     }
 
+    // Covers a pool shortfall out of the stakers' WEGLD, which slashes every share pro rata,
+    // by at most the max slashing percentage; returns the amount of pool tokens sent
     #[endpoint(takeFunds)]
-    fn take_funds(&self, pool_token: TokenIdentifier, amount: BigUint) {
+    fn take_funds(&self, pool_token: TokenIdentifier, amount: BigUint) -> BigUint {
         require!(amount > 0, "amount must be greater than 0");
 
         let caller_address = self.blockchain().get_caller();
@@ -68,53 +165,88 @@ pub trait SafetyModule {
             "invalid caller address"
         );
 
-        self.convert_wegld(pool_token.clone(), amount.clone());
+        let available_wegld = self.get_available_slashing();
+        let wegld_needed = self.get_wegld_amount_in_max(&pool_token, &amount);
+        let cover_amount = if wegld_needed > available_wegld {
+            &amount * &available_wegld / wegld_needed
+        } else {
+            amount.clone()
+        };
+        self.take_funds_event(&caller_address, &pool_token, &amount, &cover_amount);
+        if cover_amount == 0 {
+            return cover_amount;
+        }
+
+        let wegld_spent = self.convert_wegld(pool_token.clone(), cover_amount.clone());
+        self.slashed_in_window()
+            .update(|slashed| *slashed += wegld_spent);
 
         self.send()
-            .direct_esdt(&caller_address, &pool_token, 0, &amount);
+            .direct_esdt(&caller_address, &pool_token, 0, &cover_amount);
+
+        cover_amount
     }
 
+    // Starts the countdown after which the position may be withdrawn; the NFT is sent back
     #[payable("*")]
-    #[endpoint(withdraw)]
-    fn withdraw(&self) -> BigUint {
-        let (token_id, nft_nonce, amount) = self.call_value().single_esdt().into_tuple();
+    #[endpoint]
+    fn cooldown(&self) -> MultiValue2<u64, u64> {
+        let (token_id, nft_nonce, shares) = self.call_value().single_esdt().into_tuple();
         let caller_address = self.blockchain().get_caller();
 
-        require!(amount > 0, "amount must be greater than 0");
         require!(token_id == self.nft_token().get(), "invalid token");
 
-        let nft_info = self.blockchain().get_esdt_token_data(
-            &self.blockchain().get_sc_address(),
-            &token_id,
-            nft_nonce,
-        );
+        let (window_start, window_end) = self.start_cooldown(nft_nonce).into_tuple();
+        self.cooldown_event(&caller_address, nft_nonce, window_start, window_end);
+        self.send()
+            .direct_esdt(&caller_address, &token_id, nft_nonce, &shares);
 
-        let nft_metadata = nft_info.decode_attributes::<DepositPosition<Self::Api>>();
-        let rounds_in_pool = self.blockchain().get_block_round() - nft_metadata.round;
+        MultiValue2::from((window_start, window_end))
+    }
 
-        require!(rounds_in_pool > 0, "Invalid round");
+    // Burns the position NFT for its share of the WEGLD balance; needs an open unstake window
+    #[payable("*")]
+    #[endpoint(withdraw)]
+    fn withdraw(&self) -> BigUint {
+        let (token_id, nft_nonce, shares) = self.call_value().single_esdt().into_tuple();
+        let caller_address = self.blockchain().get_caller();
 
-        let withdraw_amount =
-            self.calculate_amount_for_withdrawal(amount, BigUint::from(rounds_in_pool));
+        require!(shares > 0, "amount must be greater than 0");
+        require!(token_id == self.nft_token().get(), "invalid token");
+        self.require_unstake_window_open(nft_nonce);
 
-        let wegld_token_id = &self.wegld_token().get();
-        let contract_balance = self.blockchain().get_esdt_balance(
-            &self.blockchain().get_sc_address(),
-            wegld_token_id,
-            0,
-        );
+        let total_shares = self.total_shares().get();
+        let withdraw_amount =
+            self.convert_to_wegld_amount(&shares, &self.get_wegld_balance(), &total_shares);
 
-        require!(
-            withdraw_amount <= contract_balance,
-            "the amount withdrawn is too high"
-        );
+        self.total_shares().set(total_shares - &shares);
+        self.withdraw_event(&caller_address, nft_nonce, &shares, &withdraw_amount);
+        self.nft_burn(token_id, nft_nonce, shares);
 
-        self.send()
-            .direct_esdt(&caller_address, wegld_token_id, 0, &withdraw_amount);
+        if withdraw_amount > 0 {
+            let wegld_token_id = self.wegld_token().get();
+            self.send()
+                .direct_esdt(&caller_address, &wegld_token_id, 0, &withdraw_amount);
+        }
 
         withdraw_amount
     }
 
+    // WEGLD per share, scaled by BP
+    #[view(getExchangeRate)]
+    fn get_exchange_rate(&self) -> BigUint {
+        self.compute_exchange_rate(&self.get_wegld_balance(), &self.total_shares().get())
+    }
+
+    #[view(getShareValue)]
+    fn get_share_value(&self, shares: BigUint) -> BigUint {
+        self.convert_to_wegld_amount(
+            &shares,
+            &self.get_wegld_balance(),
+            &self.total_shares().get(),
+        )
+    }
+
     #[only_owner]
     #[endpoint(setLocalRolesNftToken)]
     fn set_local_roles_nft_token(&self, roles: MultiValueEncoded<EsdtLocalRole>) {
@@ -124,6 +256,19 @@ pub trait SafetyModule {
         self.set_local_roles(token, roles.to_vec());
     }
 
+    #[callback]
+    fn issue_callback(&self, #[call_result] result: ManagedAsyncCallResult<TokenIdentifier>) {
+        match result {
+            ManagedAsyncCallResult::Ok(token_id) => {
+                self.nft_token().set(&token_id);
+                self.last_error_message().clear();
+            }
+            ManagedAsyncCallResult::Err(message) => {
+                self.last_error_message().set(&message.err_msg);
+            }
+        }
+    }
+
     #[callback]
     fn change_roles_callback(&self, #[call_result] result: ManagedAsyncCallResult<()>) {
         match result {
@@ -145,10 +290,53 @@ pub trait SafetyModule {
             .call_and_exit();
     }
 
-    fn calculate_amount_for_withdrawal(self, deposit_amount: BigUint, time: BigUint) -> BigUint {
-        let percent = (time * self.deposit_apy().get()) / BigUint::from(SECONDS_PER_YEAR);
+    fn compute_exchange_rate(&self, total_wegld: &BigUint, total_shares: &BigUint) -> BigUint {
+        (total_wegld + VIRTUAL_WEGLD) * BP / (total_shares + VIRTUAL_SHARES)
+    }
+
+    fn convert_to_shares(
+        &self,
+        amount: &BigUint,
+        total_wegld: &BigUint,
+        total_shares: &BigUint,
+    ) -> BigUint {
+        amount * &(total_shares + VIRTUAL_SHARES) / (total_wegld + VIRTUAL_WEGLD)
+    }
+
+    fn convert_to_wegld_amount(
+        &self,
+        shares: &BigUint,
+        total_wegld: &BigUint,
+        total_shares: &BigUint,
+    ) -> BigUint {
+        shares * &(total_wegld + VIRTUAL_WEGLD) / (total_shares + VIRTUAL_SHARES)
+    }
 
-        deposit_amount.clone() + ((percent * deposit_amount) / BigUint::from(BP))
+    // What is left to slash in the current window, capped by the percentage of both the balance
+    // the window started with and the current one; a new window starts once the last one is over
+    fn get_available_slashing(&self) -> BigUint {
+        let current_timestamp = self.blockchain().get_block_timestamp();
+        let wegld_balance = self.get_wegld_balance();
+        if self.slashing_window_balance().is_empty()
+            || current_timestamp
+                >= self.slashing_window_start().get() + self.slashing_window().get()
+        {
+            self.slashing_window_start().set(current_timestamp);
+            self.slashing_window_balance().set(&wegld_balance);
+            self.slashed_in_window().clear();
+        }
+
+        let max_slashing_percentage = self.max_slashing_percentage().get();
+        let window_cap = self.slashing_window_balance().get() * &max_slashing_percentage / BP;
+        let slashed = self.slashed_in_window().get();
+        if window_cap <= slashed {
+            return BigUint::zero();
+        }
+
+        BigUint::min(
+            window_cap - slashed,
+            wegld_balance * &max_slashing_percentage / BP,
+        )
     }
 
     fn nft_burn(&self, token_identifier: TokenIdentifier, nonce: u64, amount: BigUint) {
@@ -156,14 +344,10 @@ pub trait SafetyModule {
             .esdt_local_burn(&token_identifier, nonce, &amount);
     }
 
-    fn mint_deposit_nft(
-        self,
-        deposit_metadata: &DepositPosition<Self::Api>,
-        amount: BigUint,
-    ) -> u64 {
+    fn mint_deposit_nft(&self, position: &SafetyModulePosition<Self::Api>, amount: BigUint) -> u64 {
         let big_zero = BigUint::zero();
         let empty_buffer = ManagedBuffer::new();
-        let empty_vec = ManagedVec::from_raw_handle(empty_buffer.get_raw_handle());
+        let empty_vec = ManagedVec::new();
 
         self.send().esdt_nft_create(
             &self.nft_token().get(),
@@ -171,30 +355,37 @@ pub trait SafetyModule {
             &empty_buffer,
             &big_zero,
             &empty_buffer,
-            deposit_metadata,
+            position,
             &empty_vec,
         )
     }
 
-    fn convert_wegld(&self, _pool_token: TokenIdentifier, _amount: BigUint) {
-        //TODO:  integration with dex
-    }
-
-    fn convert_to_wegld(&self, _pool_token: TokenIdentifier, _amount: BigUint) {
-        //TODO:  integration with dex
-    }
-
     #[view]
     #[storage_mapper("pools")]
     fn pools(&self, token: TokenIdentifier) -> SingleValueMapper<ManagedAddress>;
 
-    #[view]
-    #[storage_mapper("wegld_token")]
-    fn wegld_token(&self) -> SingleValueMapper<TokenIdentifier>;
+    #[view(getMaxSlashingPercentage)]
+    #[storage_mapper("max_slashing_percentage")]
+    fn max_slashing_percentage(&self) -> SingleValueMapper<BigUint>;
 
-    #[view]
-    #[storage_mapper("deposit_apy")]
-    fn deposit_apy(&self) -> SingleValueMapper<BigUint>;
+    #[view(getSlashingWindow)]
+    #[storage_mapper("slashing_window")]
+    fn slashing_window(&self) -> SingleValueMapper<u64>;
+
+    #[view(getSlashingWindowStart)]
+    #[storage_mapper("slashing_window_start")]
+    fn slashing_window_start(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("slashing_window_balance")]
+    fn slashing_window_balance(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getSlashedInWindow)]
+    #[storage_mapper("slashed_in_window")]
+    fn slashed_in_window(&self) -> SingleValueMapper<BigUint>;
+
+    #[view(getTotalShares)]
+    #[storage_mapper("total_shares")]
+    fn total_shares(&self) -> SingleValueMapper<BigUint>;
 
     #[view(nftToken)]
     #[storage_mapper("nftToken")]
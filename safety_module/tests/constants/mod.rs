@@ -0,0 +1,23 @@
+// safety module constants
+
+pub const SAFETY_MODULE_WASM_PATH: &str = "../safety_module/output/safety-module.wasm";
+pub const PAIR_WASM_PATH: &str = "../pair_mock/output/pair-mock.wasm";
+pub const PRICE_AGGREGATOR_WASM_PATH: &str = "../aggregator_mock/output/aggregator-mock.wasm";
+pub const DOLLAR_TICKER: &[u8] = b"USD";
+pub const WEGLD_TICKER: &[u8] = b"WEGLD";
+pub const USDC_TICKER: &[u8] = b"USDC";
+pub const WEGLD_TOKEN_ID: &[u8] = b"WEGLD-123456";
+pub const USDC_TOKEN_ID: &[u8] = b"USDC-123456";
+pub const NFT_TOKEN_ID: &[u8] = b"SAFE-123456";
+pub const TOKEN_DECIMALS: u8 = 18;
+pub const MAX_SLIPPAGE: u64 = 10_000_000; // 1%
+pub const MAX_SLASHING_PERCENTAGE: u64 = 600_000_000; // 60%
+pub const SLASHING_WINDOW: u64 = 1_000;
+pub const USDC_RESERVE: u64 = 200_000_000_000;
+pub const WEGLD_RESERVE: u64 = 1_000_000_000; // 1 WEGLD = 200 USDC
+pub const WEGLD_PRICE_IN_DOLLARS: u64 = 20_000; // $200, with 2 decimals
+pub const USDC_PRICE_IN_DOLLARS: u64 = 100; // $1
+pub const USER_BALANCE: u64 = 100_000_000_000;
+pub const START_TIMESTAMP: u64 = 1_000;
+pub const COOLDOWN_PERIOD: u64 = 100;
+pub const UNSTAKE_WINDOW: u64 = 50;
@@ -0,0 +1,487 @@
+use constants::*;
+
+use common_structs::SafetyModulePosition;
+use multiversx_sc_scenario::{
+    managed_address, managed_biguint, managed_token_id, rust_biguint, DebugApi,
+};
+use safety_module::{cooldown::CooldownModule, SafetyModule};
+use setup::SafetySetup;
+
+pub mod constants;
+pub mod setup;
+
+#[test]
+fn fund_from_pool_swaps_to_wegld_test() {
+    let mut safety_setup = SafetySetup::deploy(
+        safety_module::contract_obj,
+        pair_mock::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let user_addr = safety_setup.user_addr.clone();
+
+    // 200_000 USDC is worth 1_000 WEGLD by the oracle, the swap pays 999 after price impact
+    let wegld_out = safety_setup.fund_from_pool(&user_addr, USDC_TOKEN_ID, 200_000, None);
+    assert_eq!(wegld_out, 999);
+
+    safety_setup.b_mock.check_esdt_balance(
+        safety_setup.safety_module_wrapper.address_ref(),
+        WEGLD_TOKEN_ID,
+        &rust_biguint!(999),
+    );
+    safety_setup.b_mock.check_esdt_balance(
+        safety_setup.safety_module_wrapper.address_ref(),
+        USDC_TOKEN_ID,
+        &rust_biguint!(0),
+    );
+
+    // WEGLD is kept as is
+    let wegld_out = safety_setup.fund_from_pool(&user_addr, WEGLD_TOKEN_ID, 500, Some(500));
+    assert_eq!(wegld_out, 500);
+}
+
+#[test]
+fn fund_from_pool_slippage_test() {
+    let mut safety_setup = SafetySetup::deploy(
+        safety_module::contract_obj,
+        pair_mock::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let user_addr = safety_setup.user_addr.clone();
+
+    // 5% of the USDC reserve moves the price more than the allowed 1%
+    safety_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &safety_setup.safety_module_wrapper,
+            USDC_TOKEN_ID,
+            0,
+            &rust_biguint!(10_000_000_000),
+            |sc| {
+                sc.fund_from_pool(None.into());
+            },
+        )
+        .assert_user_error("Slippage exceeded");
+
+    // The caller's own minimum is enforced as well
+    safety_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &safety_setup.safety_module_wrapper,
+            USDC_TOKEN_ID,
+            0,
+            &rust_biguint!(200_000),
+            |sc| {
+                sc.fund_from_pool(Some(managed_biguint!(1_000)).into());
+            },
+        )
+        .assert_user_error("Slippage exceeded");
+
+    // The limit follows the oracle, so a pair trading away from it is refused
+    safety_setup.set_price_feed(WEGLD_TICKER, DOLLAR_TICKER, 10_000);
+    safety_setup
+        .b_mock
+        .execute_esdt_transfer(
+            &user_addr,
+            &safety_setup.safety_module_wrapper,
+            USDC_TOKEN_ID,
+            0,
+            &rust_biguint!(200_000),
+            |sc| {
+                sc.fund_from_pool(None.into());
+            },
+        )
+        .assert_user_error("Slippage exceeded");
+}
+
+#[test]
+fn take_funds_buys_pool_token_test() {
+    let mut safety_setup = SafetySetup::deploy(
+        safety_module::contract_obj,
+        pair_mock::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let user_addr = safety_setup.user_addr.clone();
+    let pool_addr = safety_setup.pool_addr.clone();
+
+    safety_setup.fund_from_pool(&user_addr, WEGLD_TOKEN_ID, 1_000, None);
+
+    // 100_000 USDC costs 501 WEGLD, under the 505 allowed by the slippage limit
+    safety_setup
+        .b_mock
+        .execute_tx(
+            &pool_addr,
+            &safety_setup.safety_module_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.take_funds(managed_token_id!(USDC_TOKEN_ID), managed_biguint!(100_000));
+            },
+        )
+        .assert_ok();
+
+    safety_setup
+        .b_mock
+        .check_esdt_balance(&pool_addr, USDC_TOKEN_ID, &rust_biguint!(100_000));
+    safety_setup.b_mock.check_esdt_balance(
+        safety_setup.safety_module_wrapper.address_ref(),
+        WEGLD_TOKEN_ID,
+        &rust_biguint!(499),
+    );
+
+    // Buying 5% of the USDC reserve costs more WEGLD than the 1% slippage allows
+    safety_setup.fund_from_pool(&user_addr, WEGLD_TOKEN_ID, 100_000_000, None);
+    safety_setup
+        .b_mock
+        .execute_tx(
+            &pool_addr,
+            &safety_setup.safety_module_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.take_funds(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    managed_biguint!(10_000_000_000),
+                );
+            },
+        )
+        .assert_user_error("Slippage exceeded");
+}
+
+#[test]
+fn take_funds_cover_cap_test() {
+    let mut safety_setup = SafetySetup::deploy(
+        safety_module::contract_obj,
+        pair_mock::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let user_addr = safety_setup.user_addr.clone();
+    let pool_addr = safety_setup.pool_addr.clone();
+
+    safety_setup.fund_from_pool(&user_addr, WEGLD_TOKEN_ID, 1_000, None);
+
+    // 200_000 USDC may cost up to 1_010 WEGLD, only 60% of the 1_000 WEGLD can be spent
+    safety_setup
+        .b_mock
+        .execute_tx(
+            &pool_addr,
+            &safety_setup.safety_module_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let covered_amount =
+                    sc.take_funds(managed_token_id!(USDC_TOKEN_ID), managed_biguint!(200_000));
+                assert_eq!(covered_amount, managed_biguint!(118_811));
+            },
+        )
+        .assert_ok();
+
+    safety_setup
+        .b_mock
+        .check_esdt_balance(&pool_addr, USDC_TOKEN_ID, &rust_biguint!(118_811));
+    safety_setup.b_mock.check_esdt_balance(
+        safety_setup.safety_module_wrapper.address_ref(),
+        WEGLD_TOKEN_ID,
+        &rust_biguint!(405),
+    );
+
+    // Only registered pools can take funds
+    safety_setup
+        .b_mock
+        .execute_tx(
+            &user_addr,
+            &safety_setup.safety_module_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.take_funds(managed_token_id!(USDC_TOKEN_ID), managed_biguint!(1_000));
+            },
+        )
+        .assert_user_error("invalid caller address");
+}
+
+#[test]
+fn fund_and_withdraw_shares_test() {
+    let _ = DebugApi::dummy();
+    let mut safety_setup = SafetySetup::deploy(
+        safety_module::contract_obj,
+        pair_mock::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = safety_setup.owner_addr.clone();
+    let user_addr = safety_setup.user_addr.clone();
+
+    // The first deposit gets shares one to one
+    let (first_nonce, first_shares) = safety_setup.fund(&user_addr, 1_000);
+    assert_eq!(first_shares, 1_000);
+    let position = safety_setup
+        .b_mock
+        .get_nft_attributes::<SafetyModulePosition<DebugApi>>(&user_addr, NFT_TOKEN_ID, first_nonce)
+        .unwrap();
+    assert_eq!(position.deposited_amount, managed_biguint!(1_000));
+    assert_eq!(position.shares, managed_biguint!(1_000));
+
+    // Pool fees swapped to 999 WEGLD raise the value of every share; the rate counts the
+    // virtual share and WEGLD, 2_000 / 1_001
+    safety_setup.fund_from_pool(&user_addr, USDC_TOKEN_ID, 200_000, None);
+    safety_setup
+        .b_mock
+        .execute_query(&safety_setup.safety_module_wrapper, |sc| {
+            assert_eq!(sc.get_exchange_rate(), managed_biguint!(1_998_001_998));
+            assert_eq!(sc.total_shares().get(), managed_biguint!(1_000));
+        })
+        .assert_ok();
+
+    // A later deposit of the same value gets as many shares
+    safety_setup
+        .b_mock
+        .set_esdt_balance(&owner_addr, WEGLD_TOKEN_ID, &rust_biguint!(1_999));
+    let (second_nonce, second_shares) = safety_setup.fund(&owner_addr, 1_999);
+    assert_eq!(second_shares, 1_000);
+
+    safety_setup.cooldown(&user_addr, first_nonce, first_shares);
+    safety_setup.cooldown(&owner_addr, second_nonce, second_shares);
+    safety_setup
+        .b_mock
+        .set_block_timestamp(START_TIMESTAMP + COOLDOWN_PERIOD);
+
+    let wegld_out = safety_setup.withdraw(&user_addr, first_nonce, 400);
+    assert_eq!(wegld_out, 799);
+    // 1_000 * 3_200 / 1_601, the virtual share keeps its part of the rounding
+    let wegld_out = safety_setup.withdraw(&owner_addr, second_nonce, 1_000);
+    assert_eq!(wegld_out, 1_998);
+
+    assert_eq!(
+        safety_setup
+            .b_mock
+            .get_esdt_balance(&user_addr, NFT_TOKEN_ID, first_nonce),
+        rust_biguint!(600)
+    );
+    safety_setup
+        .b_mock
+        .execute_query(&safety_setup.safety_module_wrapper, |sc| {
+            assert_eq!(sc.total_shares().get(), managed_biguint!(600));
+            assert_eq!(
+                sc.get_share_value(managed_biguint!(600)),
+                managed_biguint!(1_200)
+            );
+        })
+        .assert_ok();
+}
+
+#[test]
+fn cooldown_and_unstake_window_test() {
+    let mut safety_setup = SafetySetup::deploy(
+        safety_module::contract_obj,
+        pair_mock::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let user_addr = safety_setup.user_addr.clone();
+
+    let (nft_nonce, _) = safety_setup.fund(&user_addr, 1_000);
+
+    safety_setup.check_withdraw_error(&user_addr, nft_nonce, 100, "cooldown not started");
+
+    safety_setup.cooldown(&user_addr, nft_nonce, 1_000);
+    safety_setup
+        .b_mock
+        .execute_query(&safety_setup.safety_module_wrapper, |sc| {
+            let (window_start, window_end) = sc.get_unstake_window(nft_nonce).into_tuple();
+            assert_eq!(window_start, START_TIMESTAMP + COOLDOWN_PERIOD);
+            assert_eq!(
+                window_end,
+                START_TIMESTAMP + COOLDOWN_PERIOD + UNSTAKE_WINDOW
+            );
+            assert!(!sc.can_unstake(nft_nonce));
+        })
+        .assert_ok();
+
+    // The NFT comes back, and the cooldown belongs to the position rather than the caller
+    assert_eq!(
+        safety_setup
+            .b_mock
+            .get_esdt_balance(&user_addr, NFT_TOKEN_ID, nft_nonce),
+        rust_biguint!(1_000)
+    );
+    let (other_nonce, _) = safety_setup.fund(&user_addr, 1_000);
+
+    safety_setup
+        .b_mock
+        .set_block_timestamp(START_TIMESTAMP + COOLDOWN_PERIOD - 1);
+    safety_setup.check_withdraw_error(&user_addr, nft_nonce, 100, "cooldown not finished");
+
+    safety_setup
+        .b_mock
+        .set_block_timestamp(START_TIMESTAMP + COOLDOWN_PERIOD + UNSTAKE_WINDOW);
+    safety_setup
+        .b_mock
+        .execute_query(&safety_setup.safety_module_wrapper, |sc| {
+            assert!(sc.can_unstake(nft_nonce));
+        })
+        .assert_ok();
+    assert_eq!(safety_setup.withdraw(&user_addr, nft_nonce, 100), 100);
+    safety_setup.check_withdraw_error(&user_addr, other_nonce, 100, "cooldown not started");
+
+    safety_setup
+        .b_mock
+        .set_block_timestamp(START_TIMESTAMP + COOLDOWN_PERIOD + UNSTAKE_WINDOW + 1);
+    safety_setup.check_withdraw_error(&user_addr, nft_nonce, 100, "unstake window expired");
+}
+
+#[test]
+fn take_funds_slashes_stakers_pro_rata_test() {
+    let mut safety_setup = SafetySetup::deploy(
+        safety_module::contract_obj,
+        pair_mock::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = safety_setup.owner_addr.clone();
+    let user_addr = safety_setup.user_addr.clone();
+    let pool_addr = safety_setup.pool_addr.clone();
+
+    safety_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &safety_setup.safety_module_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.add_pool(
+                    managed_token_id!(WEGLD_TOKEN_ID),
+                    &managed_address!(&pool_addr),
+                );
+            },
+        )
+        .assert_ok();
+
+    safety_setup
+        .b_mock
+        .set_esdt_balance(&owner_addr, WEGLD_TOKEN_ID, &rust_biguint!(1_000));
+    let (user_nonce, _) = safety_setup.fund(&user_addr, 1_000);
+    safety_setup.fund(&owner_addr, 1_000);
+
+    // A 1_500 WEGLD shortfall is capped at 60% of the 2_000 WEGLD staked
+    safety_setup
+        .b_mock
+        .execute_tx(
+            &pool_addr,
+            &safety_setup.safety_module_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                let covered_amount =
+                    sc.take_funds(managed_token_id!(WEGLD_TOKEN_ID), managed_biguint!(1_500));
+                assert_eq!(covered_amount, managed_biguint!(1_200));
+            },
+        )
+        .assert_ok();
+
+    safety_setup
+        .b_mock
+        .execute_query(&safety_setup.safety_module_wrapper, |sc| {
+            // 801 / 2_001 with the virtual share and WEGLD
+            assert_eq!(sc.get_exchange_rate(), managed_biguint!(400_299_850));
+            assert_eq!(
+                sc.get_share_value(managed_biguint!(1_000)),
+                managed_biguint!(400)
+            );
+        })
+        .assert_ok();
+
+    safety_setup.cooldown(&user_addr, user_nonce, 1_000);
+    safety_setup
+        .b_mock
+        .set_block_timestamp(START_TIMESTAMP + COOLDOWN_PERIOD);
+    assert_eq!(safety_setup.withdraw(&user_addr, user_nonce, 1_000), 400);
+}
+
+#[test]
+fn take_funds_window_cap_test() {
+    let mut safety_setup = SafetySetup::deploy(
+        safety_module::contract_obj,
+        pair_mock::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = safety_setup.owner_addr.clone();
+    let user_addr = safety_setup.user_addr.clone();
+    let pool_addr = safety_setup.pool_addr.clone();
+
+    safety_setup
+        .b_mock
+        .execute_tx(
+            &owner_addr,
+            &safety_setup.safety_module_wrapper,
+            &rust_biguint!(0),
+            |sc| {
+                sc.add_pool(
+                    managed_token_id!(WEGLD_TOKEN_ID),
+                    &managed_address!(&pool_addr),
+                );
+            },
+        )
+        .assert_ok();
+    safety_setup.fund(&user_addr, 2_000);
+
+    // 60% of the 2_000 WEGLD staked when the window opened is shared by every call in it
+    assert_eq!(
+        safety_setup.take_funds(&pool_addr, WEGLD_TOKEN_ID, 600),
+        600
+    );
+    assert_eq!(
+        safety_setup.take_funds(&pool_addr, WEGLD_TOKEN_ID, 1_000),
+        600
+    );
+    assert_eq!(safety_setup.take_funds(&pool_addr, WEGLD_TOKEN_ID, 100), 0);
+    safety_setup
+        .b_mock
+        .execute_query(&safety_setup.safety_module_wrapper, |sc| {
+            assert_eq!(sc.slashed_in_window().get(), managed_biguint!(1_200));
+        })
+        .assert_ok();
+
+    // The next window starts from the 800 WEGLD left
+    safety_setup
+        .b_mock
+        .set_block_timestamp(START_TIMESTAMP + SLASHING_WINDOW);
+    assert_eq!(
+        safety_setup.take_funds(&pool_addr, WEGLD_TOKEN_ID, 1_000),
+        480
+    );
+}
+
+#[test]
+fn first_deposit_inflation_test() {
+    let mut safety_setup = SafetySetup::deploy(
+        safety_module::contract_obj,
+        pair_mock::contract_obj,
+        aggregator_mock::contract_obj,
+    );
+    let owner_addr = safety_setup.owner_addr.clone();
+    let user_addr = safety_setup.user_addr.clone();
+
+    // The first depositor takes a single share and donates 1_000 WEGLD to inflate its price
+    safety_setup
+        .b_mock
+        .set_esdt_balance(&owner_addr, WEGLD_TOKEN_ID, &rust_biguint!(1));
+    let (_, attacker_shares) = safety_setup.fund(&owner_addr, 1);
+    assert_eq!(attacker_shares, 1);
+    safety_setup.b_mock.set_esdt_balance(
+        safety_setup.safety_module_wrapper.address_ref(),
+        WEGLD_TOKEN_ID,
+        &rust_biguint!(1_001),
+    );
+
+    // 2_000 * 2 / 1_002 rounds down to 3 shares, worth 3 * 3_002 / 5
+    let (_, user_shares) = safety_setup.fund(&user_addr, 2_000);
+    assert_eq!(user_shares, 3);
+    safety_setup
+        .b_mock
+        .execute_query(&safety_setup.safety_module_wrapper, |sc| {
+            assert_eq!(
+                sc.get_share_value(managed_biguint!(3)),
+                managed_biguint!(1_801)
+            );
+            // The attacker paid 1_001 WEGLD for a share now worth 600
+            assert_eq!(
+                sc.get_share_value(managed_biguint!(1)),
+                managed_biguint!(600)
+            );
+        })
+        .assert_ok();
+}
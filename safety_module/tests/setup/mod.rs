@@ -0,0 +1,284 @@
+use multiversx_sc::{
+    codec::multi_types::OptionalValue,
+    types::{Address, EsdtLocalRole},
+};
+use multiversx_sc_scenario::{
+    managed_address, managed_biguint, managed_buffer, managed_token_id, rust_biguint,
+    whitebox::{BlockchainStateWrapper, ContractObjWrapper, TxTokenTransfer},
+    DebugApi,
+};
+
+use crate::constants::*;
+use aggregator_mock::PriceAggregatorMock;
+use pair_mock::PairMock;
+use price_aggregator_proxy::PriceAggregatorModule;
+use safety_module::{cooldown::CooldownModule, dex::DexModule, SafetyModule};
+
+pub struct SafetySetup<SafetyModuleObjBuilder, PairObjBuilder, PriceAggregatorObjBuilder>
+where
+    SafetyModuleObjBuilder: 'static + Copy + Fn() -> safety_module::ContractObj<DebugApi>,
+    PairObjBuilder: 'static + Copy + Fn() -> pair_mock::ContractObj<DebugApi>,
+    PriceAggregatorObjBuilder: 'static + Copy + Fn() -> aggregator_mock::ContractObj<DebugApi>,
+{
+    pub b_mock: BlockchainStateWrapper,
+    pub owner_addr: Address,
+    pub user_addr: Address,
+    pub pool_addr: Address,
+    pub safety_module_wrapper:
+        ContractObjWrapper<safety_module::ContractObj<DebugApi>, SafetyModuleObjBuilder>,
+    pub pair_wrapper: ContractObjWrapper<pair_mock::ContractObj<DebugApi>, PairObjBuilder>,
+    pub price_aggregator_wrapper:
+        ContractObjWrapper<aggregator_mock::ContractObj<DebugApi>, PriceAggregatorObjBuilder>,
+}
+
+impl<SafetyModuleObjBuilder, PairObjBuilder, PriceAggregatorObjBuilder>
+    SafetySetup<SafetyModuleObjBuilder, PairObjBuilder, PriceAggregatorObjBuilder>
+where
+    SafetyModuleObjBuilder: 'static + Copy + Fn() -> safety_module::ContractObj<DebugApi>,
+    PairObjBuilder: 'static + Copy + Fn() -> pair_mock::ContractObj<DebugApi>,
+    PriceAggregatorObjBuilder: 'static + Copy + Fn() -> aggregator_mock::ContractObj<DebugApi>,
+{
+    pub fn deploy(
+        safety_module_builder: SafetyModuleObjBuilder,
+        pair_builder: PairObjBuilder,
+        price_aggregator_builder: PriceAggregatorObjBuilder,
+    ) -> Self {
+        let rust_zero = rust_biguint!(0u64);
+        let mut b_mock = BlockchainStateWrapper::new();
+        let owner_addr = b_mock.create_user_account(&rust_zero);
+        let user_addr = b_mock.create_user_account(&rust_zero);
+        let pool_addr = b_mock.create_user_account(&rust_zero);
+        b_mock.set_block_timestamp(START_TIMESTAMP);
+
+        b_mock.set_esdt_balance(&owner_addr, USDC_TOKEN_ID, &rust_biguint!(USDC_RESERVE));
+        b_mock.set_esdt_balance(&owner_addr, WEGLD_TOKEN_ID, &rust_biguint!(WEGLD_RESERVE));
+        b_mock.set_esdt_balance(&user_addr, USDC_TOKEN_ID, &rust_biguint!(USER_BALANCE));
+        b_mock.set_esdt_balance(&user_addr, WEGLD_TOKEN_ID, &rust_biguint!(USER_BALANCE));
+
+        let pair_wrapper =
+            b_mock.create_sc_account(&rust_zero, Some(&owner_addr), pair_builder, PAIR_WASM_PATH);
+        b_mock
+            .execute_tx(&owner_addr, &pair_wrapper, &rust_zero, |sc| {
+                sc.init(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    managed_token_id!(WEGLD_TOKEN_ID),
+                );
+            })
+            .assert_ok();
+
+        let liquidity = [
+            TxTokenTransfer {
+                token_identifier: USDC_TOKEN_ID.to_vec(),
+                nonce: 0,
+                value: rust_biguint!(USDC_RESERVE),
+            },
+            TxTokenTransfer {
+                token_identifier: WEGLD_TOKEN_ID.to_vec(),
+                nonce: 0,
+                value: rust_biguint!(WEGLD_RESERVE),
+            },
+        ];
+        b_mock
+            .execute_esdt_multi_transfer(&owner_addr, &pair_wrapper, &liquidity, |sc| {
+                sc.add_initial_liquidity();
+            })
+            .assert_ok();
+
+        let price_aggregator_wrapper = b_mock.create_sc_account(
+            &rust_zero,
+            Some(&owner_addr),
+            price_aggregator_builder,
+            PRICE_AGGREGATOR_WASM_PATH,
+        );
+        b_mock
+            .execute_tx(&owner_addr, &price_aggregator_wrapper, &rust_zero, |sc| {
+                sc.set_latest_price_feed(
+                    managed_buffer!(WEGLD_TICKER),
+                    managed_buffer!(DOLLAR_TICKER),
+                    managed_biguint!(WEGLD_PRICE_IN_DOLLARS),
+                );
+                sc.set_latest_price_feed(
+                    managed_buffer!(USDC_TICKER),
+                    managed_buffer!(DOLLAR_TICKER),
+                    managed_biguint!(USDC_PRICE_IN_DOLLARS),
+                );
+            })
+            .assert_ok();
+
+        let safety_module_wrapper = b_mock.create_sc_account(
+            &rust_zero,
+            Some(&owner_addr),
+            safety_module_builder,
+            SAFETY_MODULE_WASM_PATH,
+        );
+        b_mock
+            .execute_tx(&owner_addr, &safety_module_wrapper, &rust_zero, |sc| {
+                sc.init(managed_token_id!(WEGLD_TOKEN_ID));
+                sc.nft_token().set(managed_token_id!(NFT_TOKEN_ID));
+                sc.add_pool(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    &managed_address!(&pool_addr),
+                );
+                sc.set_price_aggregator_address(managed_address!(
+                    price_aggregator_wrapper.address_ref()
+                ));
+                sc.set_pair(
+                    managed_token_id!(USDC_TOKEN_ID),
+                    managed_address!(pair_wrapper.address_ref()),
+                    TOKEN_DECIMALS,
+                );
+                sc.set_max_slippage(managed_biguint!(MAX_SLIPPAGE));
+                sc.set_max_slashing_percentage(
+                    managed_biguint!(MAX_SLASHING_PERCENTAGE),
+                    SLASHING_WINDOW,
+                );
+                sc.set_cooldown_params(COOLDOWN_PERIOD, UNSTAKE_WINDOW);
+            })
+            .assert_ok();
+
+        b_mock.set_esdt_local_roles(
+            safety_module_wrapper.address_ref(),
+            NFT_TOKEN_ID,
+            &[EsdtLocalRole::NftCreate, EsdtLocalRole::NftBurn],
+        );
+
+        SafetySetup {
+            b_mock,
+            owner_addr,
+            user_addr,
+            pool_addr,
+            safety_module_wrapper,
+            pair_wrapper,
+            price_aggregator_wrapper,
+        }
+    }
+
+    pub fn set_price_feed(&mut self, from: &[u8], to: &[u8], price: u64) {
+        self.b_mock
+            .execute_tx(
+                &self.owner_addr,
+                &self.price_aggregator_wrapper,
+                &rust_biguint!(0),
+                |sc| {
+                    sc.set_latest_price_feed(
+                        managed_buffer!(from),
+                        managed_buffer!(to),
+                        managed_biguint!(price),
+                    );
+                },
+            )
+            .assert_ok();
+    }
+
+    pub fn fund(&mut self, from: &Address, amount: u64) -> (u64, u64) {
+        let mut position = (0u64, 0u64);
+        self.b_mock
+            .execute_esdt_transfer(
+                from,
+                &self.safety_module_wrapper,
+                WEGLD_TOKEN_ID,
+                0,
+                &rust_biguint!(amount),
+                |sc| {
+                    let payment = sc.fund(OptionalValue::None);
+                    position = (payment.token_nonce, payment.amount.to_u64().unwrap());
+                },
+            )
+            .assert_ok();
+
+        position
+    }
+
+    pub fn cooldown(&mut self, from: &Address, nft_nonce: u64, shares: u64) {
+        self.b_mock
+            .execute_esdt_transfer(
+                from,
+                &self.safety_module_wrapper,
+                NFT_TOKEN_ID,
+                nft_nonce,
+                &rust_biguint!(shares),
+                |sc| {
+                    sc.cooldown();
+                },
+            )
+            .assert_ok();
+    }
+
+    pub fn take_funds(&mut self, from: &Address, token_id: &[u8], amount: u64) -> u64 {
+        let mut covered_amount = 0u64;
+        self.b_mock
+            .execute_tx(from, &self.safety_module_wrapper, &rust_biguint!(0), |sc| {
+                covered_amount = sc
+                    .take_funds(managed_token_id!(token_id), managed_biguint!(amount))
+                    .to_u64()
+                    .unwrap();
+            })
+            .assert_ok();
+
+        covered_amount
+    }
+
+    pub fn withdraw(&mut self, from: &Address, nft_nonce: u64, shares: u64) -> u64 {
+        let mut wegld_out = 0u64;
+        self.b_mock
+            .execute_esdt_transfer(
+                from,
+                &self.safety_module_wrapper,
+                NFT_TOKEN_ID,
+                nft_nonce,
+                &rust_biguint!(shares),
+                |sc| {
+                    wegld_out = sc.withdraw().to_u64().unwrap();
+                },
+            )
+            .assert_ok();
+
+        wegld_out
+    }
+
+    pub fn check_withdraw_error(
+        &mut self,
+        from: &Address,
+        nft_nonce: u64,
+        shares: u64,
+        expected_err: &str,
+    ) {
+        self.b_mock
+            .execute_esdt_transfer(
+                from,
+                &self.safety_module_wrapper,
+                NFT_TOKEN_ID,
+                nft_nonce,
+                &rust_biguint!(shares),
+                |sc| {
+                    sc.withdraw();
+                },
+            )
+            .assert_user_error(expected_err);
+    }
+
+    pub fn fund_from_pool(
+        &mut self,
+        from: &Address,
+        token_id: &[u8],
+        amount: u64,
+        min_wegld_out: Option<u64>,
+    ) -> u64 {
+        let mut wegld_out = 0u64;
+        self.b_mock
+            .execute_esdt_transfer(
+                from,
+                &self.safety_module_wrapper,
+                token_id,
+                0,
+                &rust_biguint!(amount),
+                |sc| {
+                    let min_out = min_wegld_out.map(|min| managed_biguint!(min)).into();
+                    wegld_out = sc.fund_from_pool(min_out).to_u64().unwrap();
+                },
+            )
+            .assert_ok();
+
+        wegld_out
+    }
+}
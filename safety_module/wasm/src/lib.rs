@@ -5,9 +5,9 @@
 ////////////////////////////////////////////////////
 
 // Init:                                 1
-// Endpoints:                           12
+// Endpoints:                           48
 // Async Callback:                       1
-// Total number of exported functions:  14
+// Total number of exported functions:  50
 
 #![no_std]
 #![feature(alloc_error_handler, lang_items)]
@@ -18,18 +18,54 @@ multiversx_sc_wasm_adapter::panic_handler!();
 multiversx_sc_wasm_adapter::endpoints! {
     safety_module
     (
+        registerNftToken
         addPool
         removePool
+        setMaxSlashingPercentage
         fund
         fundFromPool
         takeFunds
+        cooldown
         withdraw
+        getExchangeRate
+        getShareValue
         setLocalRolesNftToken
         pools
-        wegld_token
-        deposit_apy
+        getMaxSlashingPercentage
+        getSlashingWindow
+        getSlashingWindowStart
+        getSlashedInWindow
+        getTotalShares
         nftToken
         lastErrorMessage
+        setCooldownParams
+        getUnstakeWindow
+        canUnstake
+        getCooldownStart
+        getCooldownPeriod
+        getUnstakeWindowLength
+        setPair
+        removePair
+        setMaxSlippage
+        getPair
+        getTokenDecimals
+        getMaxSlippage
+        wegld_token
+        grantRole
+        revokeRole
+        hasRole
+        getRoleMembers
+        setPriceAggregatorAddress
+        addPriceAggregatorAddress
+        removePriceAggregatorAddress
+        setFallbackPriceSource
+        setMaxMissingPriceFeeds
+        setMaxPriceStaleness
+        getAggregatorAddresses
+        getFallbackPriceSource
+        getMaxMissingPriceFeeds
+        getMaxPriceStaleness
+        getLastPriceRound
         callBack
     )
 }